@@ -131,6 +131,7 @@ export interface PluginStatus {
 
 export interface Plugin {
   id: string;
+  kind: 'process' | 'wasm';
   name: string;
   version: string;
   author: string;
@@ -141,6 +142,48 @@ export interface Plugin {
   health: 'healthy' | 'unhealthy' | 'disabled';
   pid: number | null;
   socket_path: string | null;
+  usage?: PluginUsage;
+  requests?: PluginRequestSummary;
+}
+
+export interface PluginRequestSummary {
+  requests: number;
+  errors: number;
+  timeouts: number;
+  mean_ms: number | null;
+  p95_ms: number | null;
+}
+
+export interface PluginRequestMetrics {
+  since: string;
+  requests: number;
+  timeouts: number;
+  rejected: number;
+  bytes_in: number;
+  bytes_out: number;
+  status: Record<string, number>;
+  status_class: Record<string, number>;
+  latency: {
+    buckets: { le_ms: number | null; count: number }[];
+    mean_ms: number | null;
+    max_ms: number | null;
+    p50_ms: number | null;
+    p95_ms: number | null;
+    p99_ms: number | null;
+  };
+}
+
+export interface PluginUsage {
+  kv: { keys: number; bytes: number };
+  log_bytes_today: number;
+  data_bytes: number;
+  quota: {
+    kv_max_bytes: number;
+    kv_max_keys: number;
+    kv_max_value_bytes: number;
+    log_max_bytes_per_day: number;
+    data_max_bytes: number;
+  };
 }
 
 export interface PluginLogEntry {
@@ -152,12 +195,68 @@ export interface PluginLogEntry {
   pid?: number;
 }
 
+export interface PluginLogSearchResponse {
+  logs: PluginLogEntry[];
+  next_cursor: string | null;
+}
+
+export interface LogSinkStatus {
+  sink: string;
+  queued: number;
+  dropped: number;
+  last_error: string | null;
+}
+
+export type PluginLiveLogMessage =
+  | ({ type: 'entry' } & PluginLogEntry)
+  | { type: 'lagged'; skipped: number };
+
 export interface PluginLogsResponse {
   logs: PluginLogEntry[];
   page: number;
   page_size: number;
 }
 
+export interface PluginEvent {
+  id: number;
+  plugin_id: string;
+  event_type: string;
+  timestamp: string;
+  details: string | null;
+}
+
+export interface EventSummary {
+  uptime_24h: number | null;
+  uptime_7d: number | null;
+  crashes_24h: number;
+  crashes_7d: number;
+}
+
+export interface PluginEventsResponse {
+  events: PluginEvent[];
+  next_cursor: string | null;
+  summary?: EventSummary;
+}
+
+export interface PluginEventsOptions {
+  type?: string[];
+  since?: string;
+  until?: string;
+  cursor?: string;
+  limit?: number;
+}
+
+function pluginEventsParams(options?: PluginEventsOptions & { plugin_id?: string }): string {
+  const params = new URLSearchParams();
+  if (options?.plugin_id) params.set('plugin_id', options.plugin_id);
+  if (options?.type?.length) params.set('type', options.type.join(','));
+  if (options?.since) params.set('since', options.since);
+  if (options?.until) params.set('until', options.until);
+  if (options?.cursor) params.set('cursor', options.cursor);
+  if (options?.limit !== undefined) params.set('limit', options.limit.toString());
+  return params.toString() ? '?' + params.toString() : '';
+}
+
 async function handleResponse<T>(res: Response, endpoint: string): Promise<T> {
   if (!res.ok) {
     const errorText = await res.text().catch(() => 'Unknown error');
@@ -376,4 +475,57 @@ export const api = {
     const res = await request(url);
     return handleAuthResponse(res, url);
   },
+
+  getLogSinks: async (): Promise<LogSinkStatus[]> => {
+    const url = '/plugins/logs/sinks';
+    const res = await request(url);
+    return handleAuthResponse(res, url);
+  },
+
+  searchPluginLogs: async (
+    id: string,
+    options?: { level?: string; q?: string; re?: string; since?: string; until?: string; cursor?: string; limit?: number },
+  ): Promise<PluginLogSearchResponse> => {
+    const params = new URLSearchParams();
+    if (options?.level) params.set('level', options.level);
+    if (options?.q) params.set('q', options.q);
+    if (options?.re) params.set('re', options.re);
+    if (options?.since) params.set('since', options.since);
+    if (options?.until) params.set('until', options.until);
+    if (options?.cursor) params.set('cursor', options.cursor);
+    if (options?.limit !== undefined) params.set('limit', options.limit.toString());
+
+    const url = `/plugins/${id}/logs/search${params.toString() ? '?' + params.toString() : ''}`;
+    const res = await request(url);
+    return handleAuthResponse(res, url);
+  },
+
+  // WebSocket URL streaming new log entries as PluginLiveLogMessage frames
+  pluginLiveLogsUrl: (id: string, options?: { level?: string; q?: string }): string => {
+    const params = new URLSearchParams();
+    if (options?.level) params.set('level', options.level);
+    if (options?.q) params.set('q', options.q);
+
+    const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+    const query = params.toString() ? '?' + params.toString() : '';
+    return `${protocol}//${window.location.host}${API_BASE}/plugins/${id}/logs/live${query}`;
+  },
+
+  getPluginMetrics: async (id: string): Promise<PluginRequestMetrics | null> => {
+    const url = `/plugins/${id}/metrics`;
+    const res = await request(url);
+    return handleAuthResponse(res, url);
+  },
+
+  getPluginEvents: async (id: string, options?: PluginEventsOptions): Promise<PluginEventsResponse> => {
+    const url = `/plugins/${id}/events${pluginEventsParams(options)}`;
+    const res = await request(url);
+    return handleAuthResponse(res, url);
+  },
+
+  listPluginEvents: async (options?: PluginEventsOptions & { plugin_id?: string }): Promise<PluginEventsResponse> => {
+    const url = `/plugins/events${pluginEventsParams(options)}`;
+    const res = await request(url);
+    return handleAuthResponse(res, url);
+  },
 };
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -91,6 +91,22 @@ pub struct LoginAttempt {
     pub attempted_at: String,
 }
 
+/// Add a column to a table created by an older version
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
 pub fn init_db() -> Result<DbPool> {
     let conn = Connection::open("steering.db")?;
 
@@ -179,10 +195,12 @@ pub fn init_db() -> Result<DbPool> {
             plugin_id TEXT NOT NULL,
             key TEXT NOT NULL,
             value TEXT,
+            expires_at INTEGER,
             PRIMARY KEY (plugin_id, key)
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "plugin_kv", "expires_at", "INTEGER")?;
 
     // Plugin events (for observability)
     conn.execute(
@@ -203,6 +221,40 @@ pub fn init_db() -> Result<DbPool> {
         [],
     )?;
 
+    // Plugin time-series samples (raw, pushed by plugins over the host socket)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS plugin_metrics (
+            plugin_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            labels TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            value REAL NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_plugin_metrics_series_time
+         ON plugin_metrics(plugin_id, name, timestamp)",
+        [],
+    )?;
+
+    // Downsampled plugin time-series (raw samples rolled up after retention)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS plugin_metrics_rollup (
+            plugin_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            labels TEXT NOT NULL,
+            bucket INTEGER NOT NULL,
+            sample_count INTEGER NOT NULL,
+            value_sum REAL NOT NULL,
+            min_value REAL NOT NULL,
+            max_value REAL NOT NULL,
+            PRIMARY KEY (plugin_id, name, labels, bucket)
+        )",
+        [],
+    )?;
+
     // Insert default settings
     conn.execute(
         "INSERT OR IGNORE INTO settings (key, value) VALUES ('scripts_dir', './scripts')",
@@ -562,16 +614,17 @@ pub async fn cleanup_expired_sessions(pool: &DbPool) -> Result<()> {
 
 // ============ Plugin Types ============
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)] // Used by plugins, not yet integrated (Phase 5+)
+/// A KV entry of one plugin, as shown to admins and in exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PluginKvEntry {
-    pub plugin_id: String,
     pub key: String,
     pub value: String,
+    /// Expiry as unix milliseconds, None for entries without TTL
+    #[serde(default)]
+    pub expires_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)] // Used by plugins, not yet integrated (Phase 5+)
 pub struct PluginEvent {
     pub id: i64,
     pub plugin_id: String,
@@ -580,6 +633,26 @@ pub struct PluginEvent {
     pub details: Option<String>, // JSON
 }
 
+/// A raw sample ready to be stored (labels already serialized as canonical JSON)
+#[derive(Debug, Clone)]
+pub struct MetricRow {
+    pub name: String,
+    pub labels: String,
+    pub timestamp: i64,
+    pub value: f64,
+}
+
+/// Aggregated time-series bucket as returned by `plugin_metrics_query`
+#[derive(Debug, Clone)]
+pub struct MetricBucket {
+    pub labels: String, // JSON object
+    pub bucket: i64,
+    pub count: i64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
 // ============ Login Attempts functions ============
 
 pub async fn record_login_attempt(pool: &DbPool, attempt: &LoginAttempt) -> Result<()> {
@@ -683,24 +756,234 @@ pub async fn cleanup_old_login_attempts(pool: &DbPool) -> Result<()> {
     Ok(())
 }
 
+// ============ Plugin quota functions ============
+
+/// Storage limits of a plugin
+///
+/// Defaults come from the `plugin_quota_*` settings; a setting named
+/// `<setting>.<plugin_id>` overrides it for a single plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PluginQuota {
+    /// Total size of all keys and values in bytes
+    pub kv_max_bytes: u64,
+    /// Number of keys
+    pub kv_max_keys: u64,
+    /// Size of a single value in bytes
+    pub kv_max_value_bytes: u64,
+    /// Log output accepted per day in bytes
+    pub log_max_bytes_per_day: u64,
+    /// Total size of the files in the plugin's data directory in bytes
+    pub data_max_bytes: u64,
+}
+
+impl Default for PluginQuota {
+    fn default() -> Self {
+        Self {
+            kv_max_bytes: 10 * 1024 * 1024,
+            kv_max_keys: 10_000,
+            kv_max_value_bytes: 1024 * 1024,
+            log_max_bytes_per_day: 50 * 1024 * 1024,
+            data_max_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+impl PluginQuota {
+    fn load(conn: &Connection, plugin_id: &str) -> Result<Self> {
+        let defaults = Self::default();
+        let get = |name: &str, default: u64| -> Result<u64> {
+            let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+            for key in [format!("{}.{}", name, plugin_id), name.to_string()] {
+                let value: Option<String> =
+                    stmt.query_row(params![key], |row| row.get(0)).optional()?;
+                if let Some(value) = value.and_then(|v| v.parse::<u64>().ok()) {
+                    if value > 0 {
+                        return Ok(value);
+                    }
+                }
+            }
+            Ok(default)
+        };
+
+        Ok(Self {
+            kv_max_bytes: get("plugin_quota_kv_max_bytes", defaults.kv_max_bytes)?,
+            kv_max_keys: get("plugin_quota_kv_max_keys", defaults.kv_max_keys)?,
+            kv_max_value_bytes: get(
+                "plugin_quota_kv_max_value_bytes",
+                defaults.kv_max_value_bytes,
+            )?,
+            log_max_bytes_per_day: get(
+                "plugin_quota_log_max_bytes_per_day",
+                defaults.log_max_bytes_per_day,
+            )?,
+            data_max_bytes: get("plugin_quota_data_max_bytes", defaults.data_max_bytes)?,
+        })
+    }
+}
+
+/// Error returned when a write would exceed a plugin's quota
+#[derive(Debug)]
+pub struct QuotaExceeded(pub String);
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Space used by a plugin in KV storage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PluginKvUsage {
+    pub keys: u64,
+    /// Total size of all keys and values in bytes
+    pub bytes: u64,
+}
+
+/// Get the quota of a plugin
+pub async fn plugin_quota(pool: &DbPool, plugin_id: &str) -> Result<PluginQuota> {
+    let conn = pool.lock().await;
+    PluginQuota::load(&conn, plugin_id)
+}
+
+/// Get the KV space used by a plugin (expired entries excluded)
+pub async fn plugin_kv_usage(pool: &DbPool, plugin_id: &str) -> Result<PluginKvUsage> {
+    let conn = pool.lock().await;
+    kv_usage(&conn, plugin_id)
+}
+
+fn kv_usage(conn: &Connection, plugin_id: &str) -> Result<PluginKvUsage> {
+    let (keys, bytes): (i64, i64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(length(CAST(key AS BLOB)) + COALESCE(length(CAST(value AS BLOB)), 0)), 0)
+             FROM plugin_kv WHERE plugin_id = ?1 AND {}",
+            KV_LIVE
+        ),
+        params![plugin_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(PluginKvUsage {
+        keys: keys as u64,
+        bytes: bytes as u64,
+    })
+}
+
+/// Check that writing `writes` keeps a plugin within its KV quota
+///
+/// Writes that shrink usage are always allowed, so a plugin over a
+/// lowered quota can still clean up.
+fn check_kv_quota(conn: &Connection, plugin_id: &str, writes: &[(&str, &str)]) -> Result<()> {
+    let quota = PluginQuota::load(conn, plugin_id)?;
+    for (key, value) in writes {
+        if value.len() as u64 > quota.kv_max_value_bytes {
+            return Err(QuotaExceeded(format!(
+                "Value of {:?} is {} bytes, the quota allows {}",
+                key,
+                value.len(),
+                quota.kv_max_value_bytes
+            ))
+            .into());
+        }
+    }
+
+    // Later writes to the same key replace earlier ones
+    let writes: std::collections::HashMap<&str, &str> = writes.iter().copied().collect();
+    let current = kv_usage(conn, plugin_id)?;
+    let mut usage = current;
+    for (key, value) in writes {
+        let existing: Option<i64> = conn
+            .query_row(
+                &format!(
+                    "SELECT COALESCE(length(CAST(value AS BLOB)), 0) FROM plugin_kv
+                     WHERE plugin_id = ?1 AND key = ?2 AND {}",
+                    KV_LIVE
+                ),
+                params![plugin_id, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match existing {
+            Some(old_len) => usage.bytes = usage.bytes.saturating_sub(old_len as u64),
+            None => {
+                usage.keys += 1;
+                usage.bytes += key.len() as u64;
+            }
+        }
+        usage.bytes += value.len() as u64;
+    }
+
+    if usage.keys > quota.kv_max_keys && usage.keys > current.keys {
+        return Err(QuotaExceeded(format!(
+            "KV key quota of {} keys exceeded",
+            quota.kv_max_keys
+        ))
+        .into());
+    }
+    if usage.bytes > quota.kv_max_bytes && usage.bytes > current.bytes {
+        return Err(QuotaExceeded(format!(
+            "KV storage quota of {} bytes exceeded",
+            quota.kv_max_bytes
+        ))
+        .into());
+    }
+    Ok(())
+}
+
 // ============ Plugin KV functions ============
 
+/// Rows that have not expired, for use in WHERE clauses
+/// (julianday gives sub-second precision, unlike strftime('%s'))
+const KV_LIVE: &str =
+    "(expires_at IS NULL OR expires_at > (julianday('now') - 2440587.5) * 86400000.0)";
+
+/// Expiry timestamp (unix milliseconds) for a value living `ttl_secs`
+fn kv_expires_at(ttl_secs: Option<u64>) -> Option<i64> {
+    ttl_secs.map(|ttl| {
+        let ttl_ms = i64::try_from(ttl.saturating_mul(1000)).unwrap_or(i64::MAX);
+        chrono::Utc::now().timestamp_millis().saturating_add(ttl_ms)
+    })
+}
+
+fn kv_get_live(conn: &Connection, plugin_id: &str, key: &str) -> Result<Option<String>> {
+    let value = conn
+        .query_row(
+            &format!(
+                "SELECT value FROM plugin_kv WHERE plugin_id = ?1 AND key = ?2 AND {}",
+                KV_LIVE
+            ),
+            params![plugin_id, key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value)
+}
+
 /// Get a value from plugin KV storage
 pub async fn plugin_kv_get(pool: &DbPool, plugin_id: &str, key: &str) -> Result<Option<String>> {
     let conn = pool.lock().await;
-    let mut stmt = conn.prepare("SELECT value FROM plugin_kv WHERE plugin_id = ?1 AND key = ?2")?;
-    let value: Option<String> = stmt
-        .query_row(params![plugin_id, key], |row| row.get(0))
-        .ok();
-    Ok(value)
+    kv_get_live(&conn, plugin_id, key)
 }
 
 /// Set a value in plugin KV storage
 pub async fn plugin_kv_set(pool: &DbPool, plugin_id: &str, key: &str, value: &str) -> Result<()> {
+    plugin_kv_set_with_ttl(pool, plugin_id, key, value, None).await
+}
+
+/// Set a value in plugin KV storage that expires after `ttl_secs`
+pub async fn plugin_kv_set_with_ttl(
+    pool: &DbPool,
+    plugin_id: &str,
+    key: &str,
+    value: &str,
+    ttl_secs: Option<u64>,
+) -> Result<()> {
     let conn = pool.lock().await;
+    check_kv_quota(&conn, plugin_id, &[(key, value)])?;
     conn.execute(
-        "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value) VALUES (?1, ?2, ?3)",
-        params![plugin_id, key, value],
+        "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        params![plugin_id, key, value, kv_expires_at(ttl_secs)],
     )?;
     Ok(())
 }
@@ -716,16 +999,37 @@ pub async fn plugin_kv_delete(pool: &DbPool, plugin_id: &str, key: &str) -> Resu
 }
 
 /// Get all KV entries for a plugin
-#[allow(dead_code)] // Used by plugins, not yet integrated (Phase 5+)
 pub async fn plugin_kv_get_all(pool: &DbPool, plugin_id: &str) -> Result<Vec<PluginKvEntry>> {
+    plugin_kv_browse(pool, plugin_id, None, None, usize::MAX).await
+}
+
+/// Page through a plugin's KV entries in key order
+///
+/// # Arguments
+/// * `search` - Only entries whose key or value contains this text
+/// * `after` - Only return keys after this one (pagination cursor)
+/// * `limit` - Maximum number of entries
+pub async fn plugin_kv_browse(
+    pool: &DbPool,
+    plugin_id: &str,
+    search: Option<&str>,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Vec<PluginKvEntry>> {
     let conn = pool.lock().await;
-    let mut stmt = conn
-        .prepare("SELECT plugin_id, key, value FROM plugin_kv WHERE plugin_id = ?1 ORDER BY key")?;
-    let rows = stmt.query_map(params![plugin_id], |row| {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT key, value, expires_at FROM plugin_kv
+         WHERE plugin_id = ?1 AND (?2 IS NULL OR instr(key, ?2) > 0 OR instr(value, ?2) > 0)
+           AND (?3 IS NULL OR key > ?3) AND {}
+         ORDER BY key LIMIT ?4",
+        KV_LIVE
+    ))?;
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = stmt.query_map(params![plugin_id, search, after, limit], |row| {
         Ok(PluginKvEntry {
-            plugin_id: row.get(0)?,
-            key: row.get(1)?,
-            value: row.get(2)?,
+            key: row.get(0)?,
+            value: row.get(1)?,
+            expires_at: row.get(2)?,
         })
     })?;
 
@@ -736,6 +1040,219 @@ pub async fn plugin_kv_get_all(pool: &DbPool, plugin_id: &str) -> Result<Vec<Plu
     Ok(entries)
 }
 
+/// Write exported entries back into a plugin's namespace in one transaction
+///
+/// Imports bypass quotas, so a migration never stops halfway. Entries that
+/// expired since the export are skipped.
+///
+/// # Arguments
+/// * `entries` - Entries to write, existing keys are overwritten
+/// * `replace` - Delete all existing entries first
+///
+/// # Returns
+/// Number of imported entries
+pub async fn plugin_kv_import(
+    pool: &DbPool,
+    plugin_id: &str,
+    entries: &[PluginKvEntry],
+    replace: bool,
+) -> Result<usize> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut conn = pool.lock().await;
+    let tx = conn.transaction()?;
+    if replace {
+        tx.execute(
+            "DELETE FROM plugin_kv WHERE plugin_id = ?1",
+            params![plugin_id],
+        )?;
+    }
+
+    let mut imported = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for entry in entries {
+            if entry.expires_at.is_some_and(|at| at <= now) {
+                continue;
+            }
+            stmt.execute(params![plugin_id, entry.key, entry.value, entry.expires_at])?;
+            imported += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(imported)
+}
+
+/// List KV entries whose key starts with `prefix`, in key order
+///
+/// # Arguments
+/// * `prefix` - Key prefix, empty for all keys
+/// * `after` - Only return keys after this one (pagination cursor)
+/// * `limit` - Maximum number of entries
+pub async fn plugin_kv_list(
+    pool: &DbPool,
+    plugin_id: &str,
+    prefix: &str,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Vec<(String, String)>> {
+    let conn = pool.lock().await;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT key, value FROM plugin_kv
+         WHERE plugin_id = ?1 AND substr(key, 1, length(?2)) = ?2
+           AND (?3 IS NULL OR key > ?3) AND {}
+         ORDER BY key LIMIT ?4",
+        KV_LIVE
+    ))?;
+    let rows = stmt.query_map(params![plugin_id, prefix, after, limit as i64], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+/// Replace a value only if it currently equals `expected`
+///
+/// `None` as `expected` requires the key to be absent, `None` as `new`
+/// deletes it. Returns whether the swap happened.
+pub async fn plugin_kv_compare_and_swap(
+    pool: &DbPool,
+    plugin_id: &str,
+    key: &str,
+    expected: Option<&str>,
+    new: Option<&str>,
+) -> Result<bool> {
+    let mut conn = pool.lock().await;
+    let tx = conn.transaction()?;
+    if kv_get_live(&tx, plugin_id, key)?.as_deref() != expected {
+        return Ok(false);
+    }
+    match new {
+        Some(new) => {
+            check_kv_quota(&tx, plugin_id, &[(key, new)])?;
+            tx.execute(
+            "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value, expires_at) VALUES (?1, ?2, ?3, NULL)",
+                params![plugin_id, key, new],
+            )?
+        }
+        None => tx.execute(
+            "DELETE FROM plugin_kv WHERE plugin_id = ?1 AND key = ?2",
+            params![plugin_id, key],
+        )?,
+    };
+    tx.commit()?;
+    Ok(true)
+}
+
+/// Add `delta` to an integer value, treating a missing key as 0
+///
+/// A value with a TTL keeps its expiry.
+///
+/// # Returns
+/// The new value, or None if the current value is not an integer or the
+/// result would overflow (the value is left unchanged)
+pub async fn plugin_kv_increment(
+    pool: &DbPool,
+    plugin_id: &str,
+    key: &str,
+    delta: i64,
+) -> Result<Option<i64>> {
+    let mut conn = pool.lock().await;
+    let tx = conn.transaction()?;
+    let current: Option<(String, Option<i64>)> = tx
+        .query_row(
+            &format!(
+                "SELECT value, expires_at FROM plugin_kv WHERE plugin_id = ?1 AND key = ?2 AND {}",
+                KV_LIVE
+            ),
+            params![plugin_id, key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let (value, expires_at) = match current {
+        Some((value, expires_at)) => match value.parse::<i64>() {
+            Ok(value) => (value, expires_at),
+            Err(_) => return Ok(None),
+        },
+        None => (0, None),
+    };
+    let Some(count) = value.checked_add(delta) else {
+        return Ok(None);
+    };
+
+    let count_value = count.to_string();
+    check_kv_quota(&tx, plugin_id, &[(key, &count_value)])?;
+    tx.execute(
+        "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        params![plugin_id, key, count_value, expires_at],
+    )?;
+    tx.commit()?;
+    Ok(Some(count))
+}
+
+/// Get several values, in the order of `keys`
+pub async fn plugin_kv_get_many(
+    pool: &DbPool,
+    plugin_id: &str,
+    keys: &[String],
+) -> Result<Vec<Option<String>>> {
+    let conn = pool.lock().await;
+    keys.iter()
+        .map(|key| kv_get_live(&conn, plugin_id, key))
+        .collect()
+}
+
+/// Set several values in one transaction
+///
+/// # Arguments
+/// * `entries` - Key, value and optional TTL in seconds
+pub async fn plugin_kv_set_many(
+    pool: &DbPool,
+    plugin_id: &str,
+    entries: &[(String, String, Option<u64>)],
+) -> Result<()> {
+    let mut conn = pool.lock().await;
+    let writes: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|(key, value, _)| (key.as_str(), value.as_str()))
+        .collect();
+    check_kv_quota(&conn, plugin_id, &writes)?;
+
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR REPLACE INTO plugin_kv (plugin_id, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (key, value, ttl_secs) in entries {
+            stmt.execute(params![plugin_id, key, value, kv_expires_at(*ttl_secs)])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Remove expired KV entries of all plugins
+///
+/// # Returns
+/// Number of removed entries
+pub async fn plugin_kv_purge_expired(pool: &DbPool) -> Result<usize> {
+    let conn = pool.lock().await;
+    let removed = conn.execute(
+        &format!(
+            "DELETE FROM plugin_kv WHERE expires_at IS NOT NULL AND NOT {}",
+            KV_LIVE
+        ),
+        [],
+    )?;
+    Ok(removed)
+}
+
 // ============ Plugin Event functions ============
 
 /// Log a plugin event
@@ -817,6 +1334,94 @@ pub async fn plugin_event_get_all_recent(pool: &DbPool, limit: i32) -> Result<Ve
     Ok(events)
 }
 
+/// Filter for [`plugin_event_query`]
+#[derive(Debug, Clone, Default)]
+pub struct PluginEventFilter {
+    /// Only events of this plugin, all plugins when None
+    pub plugin_id: Option<String>,
+    /// Only events of these types, all types when empty
+    pub event_types: Vec<String>,
+    /// Only events at or after this time (RFC 3339, UTC)
+    pub since: Option<String>,
+    /// Only events before this time (RFC 3339, UTC)
+    pub until: Option<String>,
+    /// Only events older than the event with this ID (pagination cursor)
+    pub before_id: Option<i64>,
+    pub limit: usize,
+}
+
+/// Query plugin events, newest first
+///
+/// Events are ordered by ID, which follows insertion order, so the ID of
+/// the last event of a page is a stable cursor for the next one.
+pub async fn plugin_event_query(
+    pool: &DbPool,
+    filter: &PluginEventFilter,
+) -> Result<Vec<PluginEvent>> {
+    use rusqlite::types::Value;
+
+    let mut conditions = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+    if let Some(plugin_id) = &filter.plugin_id {
+        values.push(Value::Text(plugin_id.clone()));
+        conditions.push(format!("plugin_id = ?{}", values.len()));
+    }
+    if !filter.event_types.is_empty() {
+        let mut placeholders = Vec::new();
+        for event_type in &filter.event_types {
+            values.push(Value::Text(event_type.clone()));
+            placeholders.push(format!("?{}", values.len()));
+        }
+        conditions.push(format!("event_type IN ({})", placeholders.join(", ")));
+    }
+    if let Some(since) = &filter.since {
+        values.push(Value::Text(since.clone()));
+        conditions.push(format!("timestamp >= ?{}", values.len()));
+    }
+    if let Some(until) = &filter.until {
+        values.push(Value::Text(until.clone()));
+        conditions.push(format!("timestamp < ?{}", values.len()));
+    }
+    if let Some(before_id) = filter.before_id {
+        values.push(Value::Integer(before_id));
+        conditions.push(format!("id < ?{}", values.len()));
+    }
+    values.push(Value::Integer(filter.limit as i64));
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let sql = format!(
+        "SELECT id, plugin_id, event_type, timestamp, details
+         FROM plugin_events
+         {}
+         ORDER BY id DESC
+         LIMIT ?{}",
+        where_clause,
+        values.len()
+    );
+
+    let conn = pool.lock().await;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        Ok(PluginEvent {
+            id: row.get(0)?,
+            plugin_id: row.get(1)?,
+            event_type: row.get(2)?,
+            timestamp: row.get(3)?,
+            details: row.get(4)?,
+        })
+    })?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        events.push(row?);
+    }
+    Ok(events)
+}
+
 /// Clean up old plugin events (keep last 7 days)
 #[allow(dead_code)] // Used by plugins, not yet integrated (Phase 5+)
 pub async fn cleanup_old_plugin_events(pool: &DbPool) -> Result<()> {
@@ -828,3 +1433,155 @@ pub async fn cleanup_old_plugin_events(pool: &DbPool) -> Result<()> {
     )?;
     Ok(())
 }
+
+// ============ Plugin Time Series functions ============
+
+/// Insert raw time-series samples for a plugin in a single transaction
+pub async fn plugin_metrics_insert(
+    pool: &DbPool,
+    plugin_id: &str,
+    rows: &[MetricRow],
+) -> Result<()> {
+    let conn = pool.lock().await;
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO plugin_metrics (plugin_id, name, labels, timestamp, value)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for row in rows {
+            stmt.execute(params![
+                plugin_id,
+                row.name,
+                row.labels,
+                row.timestamp,
+                row.value
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// List the metric names a plugin has pushed (raw or rolled up)
+pub async fn plugin_metrics_names(pool: &DbPool, plugin_id: &str) -> Result<Vec<String>> {
+    let conn = pool.lock().await;
+    let mut stmt = conn.prepare(
+        "SELECT name FROM plugin_metrics WHERE plugin_id = ?1
+         UNION
+         SELECT name FROM plugin_metrics_rollup WHERE plugin_id = ?1
+         ORDER BY name",
+    )?;
+    let rows = stmt.query_map(params![plugin_id], |row| row.get(0))?;
+
+    let mut names = Vec::new();
+    for row in rows {
+        names.push(row?);
+    }
+    Ok(names)
+}
+
+/// Aggregate a metric into `step`-second buckets over [start, end)
+///
+/// Raw samples and rollups are combined, so ranges spanning the raw
+/// retention boundary return continuous series. `label_filters` must only
+/// contain validated label keys (they are interpolated into a JSON path).
+pub async fn plugin_metrics_query(
+    pool: &DbPool,
+    plugin_id: &str,
+    name: &str,
+    label_filters: &[(String, String)],
+    start: i64,
+    end: i64,
+    step: i64,
+) -> Result<Vec<MetricBucket>> {
+    use rusqlite::types::Value;
+
+    let mut sql = String::from(
+        "SELECT labels, (ts - ?3) / ?5 AS b, SUM(cnt), SUM(sm), MIN(mn), MAX(mx) FROM (
+            SELECT labels, timestamp AS ts, 1 AS cnt, value AS sm, value AS mn, value AS mx
+            FROM plugin_metrics
+            WHERE plugin_id = ?1 AND name = ?2 AND timestamp >= ?3 AND timestamp < ?4
+            UNION ALL
+            SELECT labels, bucket, sample_count, value_sum, min_value, max_value
+            FROM plugin_metrics_rollup
+            WHERE plugin_id = ?1 AND name = ?2 AND bucket >= ?3 AND bucket < ?4
+        ) WHERE 1 = 1",
+    );
+    let mut values: Vec<Value> = vec![
+        Value::Text(plugin_id.to_string()),
+        Value::Text(name.to_string()),
+        Value::Integer(start),
+        Value::Integer(end),
+        Value::Integer(step),
+    ];
+    for (key, value) in label_filters {
+        values.push(Value::Text(value.clone()));
+        sql.push_str(&format!(
+            " AND json_extract(labels, '$.{}') = ?{}",
+            key,
+            values.len()
+        ));
+    }
+    sql.push_str(" GROUP BY labels, b ORDER BY labels, b");
+
+    let conn = pool.lock().await;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        let b: i64 = row.get(1)?;
+        Ok(MetricBucket {
+            labels: row.get(0)?,
+            bucket: start + b * step,
+            count: row.get(2)?,
+            sum: row.get(3)?,
+            min: row.get(4)?,
+            max: row.get(5)?,
+        })
+    })?;
+
+    let mut buckets = Vec::new();
+    for row in rows {
+        buckets.push(row?);
+    }
+    Ok(buckets)
+}
+
+/// Roll up raw samples older than `raw_cutoff` into `resolution`-second
+/// buckets, then drop rollups older than `rollup_cutoff`
+///
+/// # Returns
+/// Number of raw samples that were downsampled
+pub async fn plugin_metrics_downsample(
+    pool: &DbPool,
+    raw_cutoff: i64,
+    resolution: i64,
+    rollup_cutoff: i64,
+) -> Result<usize> {
+    let conn = pool.lock().await;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO plugin_metrics_rollup
+            (plugin_id, name, labels, bucket, sample_count, value_sum, min_value, max_value)
+         SELECT plugin_id, name, labels, (timestamp / ?2) * ?2,
+                COUNT(*), SUM(value), MIN(value), MAX(value)
+         FROM plugin_metrics
+         WHERE timestamp < ?1
+         GROUP BY plugin_id, name, labels, (timestamp / ?2) * ?2
+         ON CONFLICT (plugin_id, name, labels, bucket) DO UPDATE SET
+            sample_count = sample_count + excluded.sample_count,
+            value_sum = value_sum + excluded.value_sum,
+            min_value = MIN(min_value, excluded.min_value),
+            max_value = MAX(max_value, excluded.max_value)",
+        params![raw_cutoff, resolution],
+    )?;
+    let downsampled = tx.execute(
+        "DELETE FROM plugin_metrics WHERE timestamp < ?1",
+        params![raw_cutoff],
+    )?;
+    tx.execute(
+        "DELETE FROM plugin_metrics_rollup WHERE bucket < ?1",
+        params![rollup_cutoff],
+    )?;
+    tx.commit()?;
+    Ok(downsampled)
+}
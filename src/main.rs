@@ -109,6 +109,20 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    // Pick up added, replaced and removed plugin binaries without a restart
+    let _plugin_watcher = supervisor.as_ref().and_then(|sup| {
+        match crate::services::plugin_watcher::spawn_plugin_watcher(
+            Arc::clone(sup),
+            PathBuf::from("./plugins"),
+        ) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::warn!("Failed to watch plugins directory: {}", e);
+                None
+            }
+        }
+    });
+
     // Clean up expired sessions and old login attempts on startup
     if let Err(e) = crate::db::cleanup_expired_sessions(&db).await {
         tracing::warn!("Failed to cleanup expired sessions: {}", e);
@@ -154,6 +168,64 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
+    // Spawn background task to downsample plugin time-series hourly
+    let db_timeseries = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match crate::services::timeseries::run_maintenance(&db_timeseries).await {
+                Ok(n) if n > 0 => tracing::info!("Downsampled {} plugin metric samples", n),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to downsample plugin metrics: {}", e),
+            }
+        }
+    });
+
+    // Spawn background task to remove expired plugin KV entries every minute
+    let db_kv = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            match crate::db::plugin_kv_purge_expired(&db_kv).await {
+                Ok(n) if n > 0 => tracing::debug!("Purged {} expired plugin KV entries", n),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to purge expired plugin KV entries: {}", e),
+            }
+        }
+    });
+
+    // Spawn background task to stop plugins exceeding their data quota
+    if let Some(sup) = state.supervisor.clone() {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5 * 60));
+            loop {
+                interval.tick().await;
+                let stopped =
+                    crate::services::plugins::PluginSupervisor::enforce_data_quotas(&sup).await;
+                if !stopped.is_empty() {
+                    tracing::warn!("Stopped plugins over data quota: {:?}", stopped);
+                }
+            }
+        });
+    }
+
+    // Spawn background task to delete rotated logs past their retention hourly
+    if let Some(sup) = state.supervisor.clone() {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+            loop {
+                interval.tick().await;
+                match sup.lock().await.prune_logs().await {
+                    Ok(n) if n > 0 => tracing::info!("Removed {} rotated log files", n),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to prune rotated logs: {}", e),
+                }
+            }
+        });
+    }
+
     // Create API router
     let api_router = create_api_router();
     let auth_router = create_auth_router();
@@ -15,6 +15,7 @@ use crate::db::{self, DbPool, QuickAction, TaskHistory, User, UserRole};
 use crate::routes::auth::{AdminUser, AuthUser};
 use crate::services::auth::{hash_password, validate_password};
 use crate::services::system::{get_system_resources, SystemResources};
+use crate::services::{log_rotation, log_sinks, logging};
 use sysinfo::System;
 
 #[derive(Clone)]
@@ -113,9 +114,26 @@ async fn update_setting(
     Path(key): Path<String>,
     Json(payload): Json<UpdateSettingRequest>,
 ) -> Result<StatusCode, StatusCode> {
+    if key == log_sinks::LOG_SINKS_SETTING && log_sinks::parse_sinks(&payload.value).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     db::set_setting(&state.db, &key, &payload.value)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if log_rotation::is_retention_setting(&key)
+        || key == log_sinks::LOG_SINKS_SETTING
+        || key == logging::ACCESS_LOG_SETTING
+    {
+        if let Some(supervisor) = &state.supervisor {
+            let supervisor = supervisor.lock().await;
+            supervisor.apply_log_settings().await;
+            if let Err(e) = supervisor.prune_logs().await {
+                tracing::warn!("Failed to prune rotated logs: {}", e);
+            }
+        }
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
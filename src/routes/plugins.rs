@@ -1,25 +1,59 @@
 use axum::{
     body::Body,
-    extract::{Path, Query, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Multipart, Path, Query, State,
+    },
     http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
     response::{IntoResponse, Json, Response},
-    routing::{any, get, post},
+    routing::{any, get, post, put},
     Router,
 };
+use axum_extra::extract::cookie::CookieJar;
+use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::db::{self, DbPool};
 use crate::routes::api::AppState;
-use crate::routes::auth::{AdminUser, AuthUser};
-use crate::services::logging::LogLevel;
-use crate::services::plugins::PluginProcess;
+use crate::routes::auth::{AdminUser, AuthUser, SESSION_COOKIE_NAME};
+use crate::services::auth::validate_session;
+use crate::services::kv_store::SqliteKvStore;
+use crate::services::log_search::{self, LogCursor};
+use crate::services::log_sinks::SinkStatus;
+use crate::services::logging::{
+    AccessLogEntry, AccessLogger, LogEntry, LogFilter, LogLevel, PluginLogger,
+};
+use crate::services::plugin_data;
+use crate::services::plugin_events::{self, EventSummary};
+use crate::services::plugin_http::{self, ForwardError, HttpLimits};
+use crate::services::plugin_package;
+use crate::services::plugin_versions::PluginVersion;
+use crate::services::plugins::{PluginKind, PluginProcess, RescanReport};
+use crate::services::request_metrics::{
+    PluginRequestMetrics, RequestMetrics, RequestOutcome, RequestSummary,
+};
+use crate::services::timeseries::{self, Aggregation, RangeQuery, Series};
+use toru_plugin_api::{KvMessageResponse, KvOp, KvSetEntry, PluginError};
+
+/// Maximum size of an uploaded plugin binary
+const MAX_UPGRADE_BINARY_SIZE: usize = 256 * 1024 * 1024;
+
+/// Maximum size of an uploaded KV export
+///
+/// Four times the default 10 MiB KV quota: JSON escaping of values and the
+/// per-entry fields make an export considerably larger than the data it holds.
+const MAX_KV_IMPORT_SIZE: usize = 40 * 1024 * 1024;
 
 /// Plugin status information
 #[derive(Serialize, Clone)]
 pub struct PluginStatus {
     pub id: String,
+    pub kind: PluginKind,
     pub name: String,
     pub version: String,
     pub author: Option<String>,
@@ -29,12 +63,52 @@ pub struct PluginStatus {
     pub health: String, // "healthy", "unhealthy", "disabled"
     pub pid: Option<u32>,
     pub socket_path: Option<String>,
+    /// Storage used against the plugin's quota (plugin list and details only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<PluginUsage>,
+    /// Requests forwarded since the core started (plugin list and details
+    /// only, once the plugin served a request)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<RequestSummary>,
+}
+
+/// Storage used by a plugin and its limits
+#[derive(Serialize, Clone)]
+pub struct PluginUsage {
+    pub kv: db::PluginKvUsage,
+    /// Log output accepted today, in bytes
+    pub log_bytes_today: u64,
+    /// Size of the files in the plugin's data directory, in bytes
+    pub data_bytes: u64,
+    pub quota: db::PluginQuota,
+}
+
+impl PluginUsage {
+    /// Load the usage of a plugin, None if the database cannot be read
+    async fn load(
+        pool: &DbPool,
+        logger: &PluginLogger,
+        plugin_id: &str,
+        data_dir: &std::path::Path,
+    ) -> Option<Self> {
+        Some(Self {
+            kv: db::plugin_kv_usage(pool, plugin_id).await.ok()?,
+            log_bytes_today: logger.log_usage(plugin_id),
+            data_bytes: plugin_data::measure_dir(data_dir.to_path_buf())
+                .await
+                .ok()?,
+            quota: db::plugin_quota(pool, plugin_id).await.ok()?,
+        })
+    }
 }
 
 impl From<&PluginProcess> for PluginStatus {
     fn from(process: &PluginProcess) -> Self {
         let health = if !process.enabled {
             "disabled".to_string()
+        } else if process.kind == PluginKind::Wasm && process.is_running() {
+            // In-process instances have no socket to check
+            "healthy".to_string()
         } else if process.process.is_some()
             && !process.socket_path.is_empty()
             && PathBuf::from(&process.socket_path).exists()
@@ -46,6 +120,7 @@ impl From<&PluginProcess> for PluginStatus {
 
         PluginStatus {
             id: process.id.clone(),
+            kind: process.kind,
             name: process
                 .metadata
                 .as_ref()
@@ -63,7 +138,7 @@ impl From<&PluginProcess> for PluginStatus {
                 .map(|m| m.icon.clone())
                 .unwrap_or_default(),
             enabled: process.enabled,
-            running: process.process.is_some(),
+            running: process.is_running(),
             health,
             pid: process.pid,
             socket_path: if process.socket_path.is_empty() {
@@ -71,6 +146,8 @@ impl From<&PluginProcess> for PluginStatus {
             } else {
                 Some(process.socket_path.clone())
             },
+            usage: None,
+            requests: None,
         }
     }
 }
@@ -79,12 +156,43 @@ pub fn create_plugin_router() -> Router<AppState> {
     // Admin routes router
     let admin_router = Router::new()
         .route("/", get(list_plugins))
+        .route("/rescan", post(rescan_plugins))
+        .route("/events", get(list_all_plugin_events))
+        .route("/logs/sinks", get(get_log_sinks))
         .route("/:id", get(get_plugin))
         .route("/:id/enable", post(enable_plugin))
         .route("/:id/disable", post(disable_plugin))
+        .route(
+            "/:id/upgrade",
+            post(upgrade_plugin).layer(DefaultBodyLimit::max(MAX_UPGRADE_BINARY_SIZE)),
+        )
+        .route("/:id/versions", get(list_plugin_versions))
+        .route("/:id/rollback", post(rollback_plugin))
         .route("/:id/bundle.js", get(get_plugin_bundle))
+        .route("/:id/assets/*path", get(get_plugin_asset))
         .route("/:id/logs", get(get_plugin_logs))
-        .route("/:id/kv", post(plugin_kv_handler));
+        .route("/:id/logs/live", get(tail_plugin_logs))
+        .route("/:id/logs/search", get(search_plugin_logs))
+        .route("/:id/events", get(get_plugin_events))
+        .route("/:id/metrics", get(get_plugin_metrics))
+        .route("/:id/kv", post(plugin_kv_handler))
+        .route("/:id/kv/entries", get(browse_plugin_kv))
+        .route(
+            "/:id/kv/entries/*key",
+            put(put_plugin_kv_entry).delete(delete_plugin_kv_entry),
+        )
+        .route("/:id/kv/export", get(export_plugin_kv))
+        .route(
+            "/:id/kv/import",
+            post(import_plugin_kv).layer(DefaultBodyLimit::max(MAX_KV_IMPORT_SIZE)),
+        )
+        .route("/:id/data", get(list_plugin_data))
+        .route(
+            "/:id/data/*path",
+            get(download_plugin_data).delete(delete_plugin_data),
+        )
+        .route("/:id/timeseries", get(list_plugin_timeseries))
+        .route("/:id/timeseries/:name", get(query_plugin_timeseries));
 
     // Dynamic plugin routes (separate path prefix to avoid conflicts)
     // Plugins declare a route in metadata (e.g., "/hello-plugin")
@@ -112,7 +220,7 @@ pub fn create_plugin_router() -> Router<AppState> {
 /// - Plugin route: "/hello-plugin"
 /// - Plugin path: "/some/path?query=1"
 async fn forward_to_plugin(
-    _auth: AuthUser, // Require authentication (any role)
+    auth: AuthUser, // Require authentication (any role)
     State(state): State<AppState>,
     Path(path): Path<String>,
     method: Method,
@@ -140,12 +248,31 @@ async fn forward_to_plugin(
         .get_plugin_for_route(&format!("/{}", plugin_route))
         .ok_or(StatusCode::NOT_FOUND)?;
 
+    // Pin the serving process and release the lock before talking to the plugin
+    let target = supervisor.http_target(&plugin_id).map_err(|e| {
+        tracing::error!("Failed to forward request to plugin {}: {}", plugin_id, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let metrics = supervisor.request_metrics();
+    let access_logger = supervisor.access_logger();
+    drop(supervisor);
+
     // Build the path to send to plugin
     let plugin_path = if remaining.is_empty() {
         "/".to_string()
     } else {
         format!("/{}", remaining)
     };
+    let record = RequestRecord {
+        started: Instant::now(),
+        plugin_id: plugin_id.clone(),
+        method: method.to_string(),
+        path: plugin_path.clone(),
+        user: auth.username,
+        metrics,
+        access_logger,
+    };
+    let mut outcome = RequestOutcome::default();
 
     // Include query string
     let full_path = if let Some(query) = uri.query() {
@@ -162,10 +289,16 @@ async fn forward_to_plugin(
         }
     }
 
+    // Limits may be narrowed for the first path segment below the route
+    let segment = remaining.split('/').next().unwrap_or_default();
+    let limits = HttpLimits::load(&state.db, &plugin_id, segment).await;
+
     // Read request body
-    let body_bytes = axum::body::to_bytes(body, usize::MAX)
-        .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let body_bytes = match axum::body::to_bytes(body, limits.max_request_bytes).await {
+        Ok(body_bytes) => body_bytes,
+        Err(_) => return Ok(record.finish(StatusCode::PAYLOAD_TOO_LARGE.into_response(), outcome)),
+    };
+    outcome.bytes_in = body_bytes.len() as u64;
     let body_str = if body_bytes.is_empty() {
         None
     } else {
@@ -181,13 +314,17 @@ async fn forward_to_plugin(
     };
 
     // Forward to plugin
-    let response = supervisor
-        .forward_http_request(&plugin_id, &http_request)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to forward request to plugin {}: {}", plugin_id, e);
-            StatusCode::BAD_GATEWAY
-        })?;
+    let response = match target.forward(&http_request, &limits).await {
+        Ok(response) => response,
+        Err(e) => {
+            match e.downcast_ref::<ForwardError>() {
+                Some(ForwardError::Timeout(_)) => outcome.timed_out = true,
+                Some(ForwardError::CircuitOpen { .. }) => outcome.rejected = true,
+                _ => {}
+            }
+            return Ok(record.finish(forward_error_response(&plugin_id, &e), outcome));
+        }
+    };
 
     // Build Axum response from plugin response
     let mut builder = Response::builder().status(response.status);
@@ -202,11 +339,81 @@ async fn forward_to_plugin(
     }
 
     // Set body
+    let body = response.body.unwrap_or_default();
+    outcome.bytes_out = body.len() as u64;
     let response = builder
-        .body(axum::body::Body::from(response.body.unwrap_or_default()))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .body(axum::body::Body::from(body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+
+    Ok(record.finish(response, outcome))
+}
+
+/// A request forwarded to a plugin, counted and logged once answered
+struct RequestRecord {
+    started: Instant,
+    plugin_id: String,
+    method: String,
+    /// Path below the plugin's route, without the query string
+    path: String,
+    user: String,
+    metrics: Arc<RequestMetrics>,
+    access_logger: Arc<AccessLogger>,
+}
+
+impl RequestRecord {
+    /// Count the request and write it to the access log
+    ///
+    /// Every request that reached a plugin's target is recorded, including
+    /// the ones the core answered itself (413, 502, 503, 504).
+    fn finish(self, response: Response, mut outcome: RequestOutcome) -> Response {
+        outcome.status = response.status().as_u16();
+        outcome.latency = self.started.elapsed();
+        self.metrics.record(&self.plugin_id, &outcome);
+
+        if self.access_logger.is_enabled() {
+            self.access_logger.log(AccessLogEntry {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                plugin: self.plugin_id,
+                method: self.method,
+                path: self.path,
+                status: outcome.status,
+                duration_ms: outcome.latency.as_micros() as f64 / 1000.0,
+                bytes_in: outcome.bytes_in,
+                bytes_out: outcome.bytes_out,
+                user: self.user,
+            });
+        }
+        response
+    }
+}
 
-    Ok(response)
+/// Map a failed forward to the response sent to the client
+///
+/// An open circuit answers 503 with `Retry-After`, a timeout 504, and any
+/// other failure 502.
+fn forward_error_response(plugin_id: &str, error: &anyhow::Error) -> Response {
+    match error.downcast_ref::<ForwardError>() {
+        Some(ForwardError::CircuitOpen { retry_after }) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(
+                header::RETRY_AFTER,
+                plugin_http::retry_after_secs(*retry_after).to_string(),
+            )],
+        )
+            .into_response(),
+        Some(ForwardError::Timeout(_)) => {
+            tracing::warn!("Request to plugin {} timed out: {}", plugin_id, error);
+            StatusCode::GATEWAY_TIMEOUT.into_response()
+        }
+        _ => {
+            tracing::error!(
+                "Failed to forward request to plugin {}: {:#}",
+                plugin_id,
+                error
+            );
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+    }
 }
 
 /// List all plugins (available to all authenticated users)
@@ -222,11 +429,50 @@ async fn list_plugins(
         .await;
     let plugins = supervisor.get_all_plugins();
 
-    let plugin_statuses: Vec<PluginStatus> = plugins.values().map(PluginStatus::from).collect();
+    let mut plugin_statuses: Vec<PluginStatus> = plugins.values().map(PluginStatus::from).collect();
+    let metrics = supervisor.request_metrics();
+    for status in plugin_statuses.iter_mut() {
+        status.requests = metrics.summary(&status.id);
+    }
+    let logger = supervisor.plugin_logger();
+    let data_dirs: Vec<PathBuf> = plugin_statuses
+        .iter()
+        .map(|status| supervisor.plugin_data_dir(&status.id))
+        .collect();
+    drop(supervisor);
+
+    for (status, data_dir) in plugin_statuses.iter_mut().zip(&data_dirs) {
+        status.usage = PluginUsage::load(&state.db, &logger, &status.id, data_dir).await;
+    }
 
     Ok(Json(plugin_statuses))
 }
 
+/// Rescan the plugins directory for added, replaced and removed binaries
+async fn rescan_plugins(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+) -> Result<Json<RescanReport>, (StatusCode, Json<serde_json::Value>)> {
+    let mut supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or((
+            StatusCode::NOT_IMPLEMENTED,
+            Json(serde_json::json!({ "error": "Plugin supervisor not initialized" })),
+        ))?
+        .lock()
+        .await;
+
+    let report = supervisor.rescan().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("Failed to rescan plugins: {}", e) })),
+        )
+    })?;
+
+    Ok(Json(report))
+}
+
 /// Get plugin details (available to all authenticated users)
 async fn get_plugin(
     _auth: AuthUser, // Changed from AdminUser to AuthUser
@@ -239,11 +485,39 @@ async fn get_plugin(
         .ok_or(StatusCode::NOT_IMPLEMENTED)?
         .lock()
         .await;
-    let plugin = supervisor
+    let mut status = supervisor
         .get_plugin_status(&id)
+        .map(PluginStatus::from)
         .ok_or(StatusCode::NOT_FOUND)?;
+    status.requests = supervisor.request_metrics().summary(&id);
+    let logger = supervisor.plugin_logger();
+    let data_dir = supervisor.plugin_data_dir(&id);
+    drop(supervisor);
+
+    status.usage = PluginUsage::load(&state.db, &logger, &id, &data_dir).await;
 
-    Ok(Json(PluginStatus::from(plugin)))
+    Ok(Json(status))
+}
+
+/// Get the request counters of a plugin
+///
+/// Returns `null` until the plugin has served a request since the core
+/// started.
+async fn get_plugin_metrics(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Option<PluginRequestMetrics>>, StatusCode> {
+    let supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?
+        .lock()
+        .await;
+    supervisor
+        .get_plugin_status(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(supervisor.request_metrics().metrics(&id)))
 }
 
 /// Enable a plugin
@@ -314,6 +588,179 @@ async fn disable_plugin(
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+/// Upgrade a running plugin to an uploaded binary without downtime
+///
+/// Expects a multipart form with the new binary in the `binary` field.
+async fn upgrade_plugin(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let supervisor = state.supervisor.as_ref().ok_or((
+        StatusCode::NOT_IMPLEMENTED,
+        Json(serde_json::json!({ "error": "Plugin supervisor not initialized" })),
+    ))?;
+
+    let staging_dir = {
+        let supervisor = supervisor.lock().await;
+        if supervisor.get_plugin_status(&id).is_none() {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Plugin not found" })),
+            ));
+        }
+        supervisor.get_plugins_dir().join(".staging")
+    };
+
+    let bad_request = |msg: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": msg })),
+        )
+    };
+    let internal_error = |msg: String| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": msg })),
+        )
+    };
+
+    let mut binary = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() == Some("binary") {
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| bad_request(format!("Failed to read binary: {}", e)))?;
+            binary = Some(data);
+        }
+    }
+    let binary = binary
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| bad_request("Missing 'binary' field".to_string()))?;
+
+    // Stage the binary next to the plugins so the final move is a rename
+    fs::create_dir_all(&staging_dir)
+        .map_err(|e| internal_error(format!("Failed to create staging directory: {}", e)))?;
+    let staged_path = staging_dir.join(format!("{}-{}.binary", id, uuid::Uuid::new_v4()));
+    fs::write(&staged_path, &binary)
+        .map_err(|e| internal_error(format!("Failed to stage binary: {}", e)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged_path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| internal_error(format!("Failed to stage binary: {}", e)))?;
+    }
+
+    let result =
+        crate::services::plugins::PluginSupervisor::upgrade_plugin(supervisor, &id, &staged_path)
+            .await;
+
+    // Only left behind if the upgrade did not install it
+    fs::remove_file(&staged_path).ok();
+
+    result.map_err(|e| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({ "error": format!("Failed to upgrade plugin: {:#}", e) })),
+        )
+    })?;
+
+    let plugin = supervisor
+        .lock()
+        .await
+        .get_plugin_status(&id)
+        .map(PluginStatus::from);
+
+    Ok(Json(
+        serde_json::json!({ "success": true, "plugin": plugin }),
+    ))
+}
+
+/// List archived versions of a plugin, newest first
+async fn list_plugin_versions(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<PluginVersion>>, StatusCode> {
+    let supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?
+        .lock()
+        .await;
+
+    if supervisor.get_plugin_status(&id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let versions = supervisor.list_plugin_versions(&id).map_err(|e| {
+        tracing::error!("Failed to list versions of plugin {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(versions))
+}
+
+/// Rollback request body
+#[derive(Deserialize, Default)]
+pub struct RollbackRequest {
+    /// Archived version to restore (defaults to the most recent)
+    pub version_id: Option<String>,
+}
+
+/// Roll a plugin back to an archived version
+async fn rollback_plugin(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    body: Option<Json<RollbackRequest>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let mut supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or((
+            StatusCode::NOT_IMPLEMENTED,
+            Json(serde_json::json!({ "error": "Plugin supervisor not initialized" })),
+        ))?
+        .lock()
+        .await;
+
+    // Check if plugin exists
+    if supervisor.get_plugin_status(&id).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Plugin not found" })),
+        ));
+    }
+
+    let Json(request) = body.unwrap_or_default();
+    let version = supervisor
+        .rollback_plugin(&id, request.version_id.as_deref())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(
+                    serde_json::json!({ "error": format!("Failed to roll back plugin: {:#}", e) }),
+                ),
+            )
+        })?;
+
+    let plugin = supervisor.get_plugin_status(&id).map(PluginStatus::from);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "version": version,
+        "plugin": plugin,
+    })))
+}
+
 /// Get plugin frontend bundle (available to all authenticated users)
 async fn get_plugin_bundle(
     _auth: AuthUser, // Changed from AdminUser to AuthUser
@@ -340,16 +787,40 @@ async fn get_plugin_bundle(
         return Err(StatusCode::NOT_FOUND);
     }
 
-    // Get plugin bundle path from plugins directory
-    let plugins_dir = supervisor.get_plugins_dir();
-    let bundle_path = plugins_dir.join(&id).join("bundle.js");
+    // Packages ship the bundle with their assets; single binaries keep it
+    // in a directory named after the plugin
+    let bundle_path = match supervisor.plugin_assets_dir(&id) {
+        Some(assets_dir) => assets_dir.join("bundle.js"),
+        None => supervisor.get_plugins_dir().join(&id).join("bundle.js"),
+    };
 
-    if !bundle_path.exists() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    let content = if bundle_path.exists() {
+        drop(supervisor);
+        fs::read_to_string(&bundle_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    } else {
+        // Plugins built with the SDK can embed their bundle and serve it
+        // themselves at GET /bundle.js
+        let target = supervisor
+            .http_target(&id)
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+        drop(supervisor);
 
-    let content =
-        fs::read_to_string(&bundle_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let request = toru_plugin_api::HttpRequest {
+            method: "GET".to_string(),
+            path: "/bundle.js".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let limits = HttpLimits::load(&state.db, &id, "").await;
+        let response = target.forward(&request, &limits).await.map_err(|e| {
+            tracing::warn!("Failed to fetch bundle from plugin {}: {:#}", id, e);
+            StatusCode::BAD_GATEWAY
+        })?;
+        if response.status != 200 {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        response.body.unwrap_or_default()
+    };
 
     Ok((
         [
@@ -360,6 +831,46 @@ async fn get_plugin_bundle(
     ))
 }
 
+/// Serve a static asset of a packaged plugin (available to all authenticated users)
+async fn get_plugin_asset(
+    _auth: AuthUser,
+    State(state): State<AppState>,
+    Path((id, path)): Path<(String, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?
+        .lock()
+        .await;
+    let plugin = supervisor
+        .get_plugin_status(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !plugin.enabled {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let assets_dir = supervisor
+        .plugin_assets_dir(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    drop(supervisor);
+
+    // Security: resolve_asset rejects paths escaping the assets directory
+    let asset_path =
+        plugin_package::resolve_asset(&assets_dir, &path).ok_or(StatusCode::NOT_FOUND)?;
+    let content = fs::read(&asset_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mime = mime_guess::from_path(&asset_path).first_or_octet_stream();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime.to_string()),
+            (header::CACHE_CONTROL, "no-cache".to_string()),
+        ],
+        content,
+    ))
+}
+
 #[derive(Deserialize)]
 struct LogQuery {
     #[serde(default)]
@@ -411,6 +922,343 @@ async fn get_plugin_logs(
     }))
 }
 
+/// Delivery state of the configured log sinks
+async fn get_log_sinks(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SinkStatus>>, StatusCode> {
+    let supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?
+        .lock()
+        .await;
+    Ok(Json(supervisor.log_sink_status()))
+}
+
+#[derive(Deserialize)]
+struct LogSearchQuery {
+    /// Lowest level returned
+    level: Option<String>,
+    /// Text the message or error contains (any case)
+    q: Option<String>,
+    /// Regular expression the message or error matches
+    re: Option<String>,
+    /// Range start (RFC 3339), inclusive
+    since: Option<String>,
+    /// Range end (RFC 3339), exclusive
+    until: Option<String>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    #[serde(default = "default_page_size")]
+    limit: usize,
+}
+
+/// Largest page of a log search
+const MAX_LOGS_PAGE: usize = 1000;
+
+#[derive(Serialize)]
+struct LogSearchResponse {
+    logs: Vec<LogEntry>,
+    /// Pass as `cursor` to get older entries; None when the search is done
+    next_cursor: Option<String>,
+}
+
+impl LogSearchQuery {
+    /// Convert into a search query, rejecting malformed parameters
+    fn into_query(self) -> Result<log_search::LogQuery, StatusCode> {
+        let time = |value: Option<String>| {
+            value
+                .map(|v| {
+                    chrono::DateTime::parse_from_rfc3339(&v)
+                        .map(|t| t.with_timezone(&chrono::Utc))
+                        .map_err(|_| StatusCode::BAD_REQUEST)
+                })
+                .transpose()
+        };
+
+        let mut filter = LogFilter::new(
+            self.level.as_deref().and_then(LogLevel::parse_level),
+            self.q.as_deref(),
+        );
+        if let Some(re) = self.re.filter(|re| !re.is_empty()) {
+            let pattern = regex::RegexBuilder::new(&re)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            filter = filter.with_pattern(pattern);
+        }
+
+        Ok(log_search::LogQuery {
+            filter,
+            since: time(self.since)?,
+            until: time(self.until)?,
+            cursor: self
+                .cursor
+                .map(|c| LogCursor::parse(&c).ok_or(StatusCode::BAD_REQUEST))
+                .transpose()?,
+            skip: 0,
+            limit: self.limit.clamp(1, MAX_LOGS_PAGE),
+        })
+    }
+}
+
+/// Search a plugin's current and rotated logs, newest first
+async fn search_plugin_logs(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogSearchQuery>,
+) -> Result<Json<LogSearchResponse>, StatusCode> {
+    let query = query.into_query()?;
+    let plugin_logger = {
+        let supervisor = state
+            .supervisor
+            .as_ref()
+            .ok_or(StatusCode::NOT_IMPLEMENTED)?
+            .lock()
+            .await;
+        if supervisor.get_plugin_status(&id).is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        supervisor.plugin_logger()
+    };
+
+    let page = plugin_logger
+        .search_plugin_logs(&id, query)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(LogSearchResponse {
+        logs: page.entries,
+        next_cursor: page.next_cursor.map(|c| c.encode()),
+    }))
+}
+
+#[derive(Deserialize)]
+struct LiveLogQuery {
+    /// Lowest level streamed
+    #[serde(default)]
+    level: Option<String>,
+    /// Only entries whose message or error contains this text (any case)
+    #[serde(default)]
+    q: Option<String>,
+}
+
+/// Frame sent to live-tail clients
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LiveLogMessage {
+    Entry(LogEntry),
+    /// The client fell behind and this many entries were not sent
+    Lagged {
+        skipped: u64,
+    },
+}
+
+/// Stream new plugin log entries over a WebSocket as they are written
+async fn tail_plugin_logs(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<LiveLogQuery>,
+    jar: CookieJar,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    // AdminUser already validated the session
+    let session_id = jar
+        .get(SESSION_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let plugin_logger = {
+        let supervisor = state
+            .supervisor
+            .as_ref()
+            .ok_or(StatusCode::NOT_IMPLEMENTED)?
+            .lock()
+            .await;
+        if supervisor.get_plugin_status(&id).is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        supervisor.plugin_logger()
+    };
+
+    let filter = LogFilter::new(
+        query.level.as_deref().and_then(LogLevel::parse_level),
+        query.q.as_deref(),
+    );
+    // Subscribe before the upgrade so nothing written meanwhile is missed
+    let entries = plugin_logger.subscribe(&id);
+
+    Ok(ws.on_upgrade(move |socket| {
+        stream_plugin_logs(socket, state.db, session_id, filter, entries)
+    }))
+}
+
+/// Forward matching entries to the client until either side goes away or
+/// the session ends
+async fn stream_plugin_logs(
+    socket: WebSocket,
+    db: DbPool,
+    session_id: String,
+    filter: LogFilter,
+    mut entries: tokio::sync::broadcast::Receiver<LogEntry>,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let (mut sender, mut receiver) = socket.split();
+    let mut session_check_interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5 minutes
+    session_check_interval.tick().await;
+
+    loop {
+        let message = tokio::select! {
+            _ = session_check_interval.tick() => {
+                if validate_session(&db, &session_id).await.is_none() {
+                    break;
+                }
+                continue;
+            }
+            entry = entries.recv() => match entry {
+                Ok(entry) if filter.matches(&entry) => LiveLogMessage::Entry(entry),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => LiveLogMessage::Lagged { skipped },
+                Err(RecvError::Closed) => break,
+            },
+            incoming = receiver.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        let Ok(text) = serde_json::to_string(&message) else {
+            continue;
+        };
+        if sender.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    /// Only events of this plugin (all-plugins endpoint only)
+    plugin_id: Option<String>,
+    /// Comma-separated event types, e.g. "started,killed"
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    /// Range start (RFC 3339), inclusive
+    since: Option<String>,
+    /// Range end (RFC 3339), exclusive
+    until: Option<String>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    #[serde(default = "default_page_size")]
+    limit: usize,
+}
+
+/// Largest page of events
+const MAX_EVENTS_PAGE: usize = 1000;
+
+#[derive(Serialize)]
+struct EventsResponse {
+    events: Vec<db::PluginEvent>,
+    /// Pass as `cursor` to get older events; None on the last page
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<EventSummary>,
+}
+
+impl EventsQuery {
+    /// Convert into a database filter, rejecting malformed parameters
+    fn into_filter(self, plugin_id: Option<String>) -> Result<db::PluginEventFilter, StatusCode> {
+        let time = |value: Option<String>| -> Result<Option<String>, StatusCode> {
+            value
+                .map(|v| {
+                    chrono::DateTime::parse_from_rfc3339(&v)
+                        .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339())
+                        .map_err(|_| StatusCode::BAD_REQUEST)
+                })
+                .transpose()
+        };
+
+        Ok(db::PluginEventFilter {
+            plugin_id: plugin_id.or(self.plugin_id),
+            event_types: self
+                .event_type
+                .map(|types| {
+                    types
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            since: time(self.since)?,
+            until: time(self.until)?,
+            before_id: self
+                .cursor
+                .map(|c| c.parse().map_err(|_| StatusCode::BAD_REQUEST))
+                .transpose()?,
+            limit: self.limit.clamp(1, MAX_EVENTS_PAGE),
+        })
+    }
+}
+
+/// Load one page of events matching a filter
+async fn event_page(
+    state: &AppState,
+    filter: &db::PluginEventFilter,
+) -> Result<(Vec<db::PluginEvent>, Option<String>), StatusCode> {
+    let events = db::plugin_event_query(&state.db, filter)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let next_cursor = if events.len() == filter.limit {
+        events.last().map(|e| e.id.to_string())
+    } else {
+        None
+    };
+    Ok((events, next_cursor))
+}
+
+/// Event timeline of all plugins, newest first
+async fn list_all_plugin_events(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<EventsResponse>, StatusCode> {
+    let filter = query.into_filter(None)?;
+    let (events, next_cursor) = event_page(&state, &filter).await?;
+    Ok(Json(EventsResponse {
+        events,
+        next_cursor,
+        summary: None,
+    }))
+}
+
+/// Event timeline of a plugin, newest first, with uptime and crash counts
+///
+/// Works for removed plugins too, as long as their events are retained.
+async fn get_plugin_events(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<EventsResponse>, StatusCode> {
+    let filter = query.into_filter(Some(id.clone()))?;
+    let (events, next_cursor) = event_page(&state, &filter).await?;
+    let summary = plugin_events::summarize(&state.db, &id, chrono::Utc::now())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(EventsResponse {
+        events,
+        next_cursor,
+        summary: Some(summary),
+    }))
+}
+
 #[derive(Serialize)]
 struct LogsResponse {
     logs: Vec<crate::services::logging::LogEntry>,
@@ -419,74 +1267,523 @@ struct LogsResponse {
 }
 
 /// KV operation request
+///
+/// Which fields are required depends on the action.
 #[derive(Deserialize)]
 struct KvOperation {
-    action: String, // "get", "set", "delete"
-    key: String,
+    // "get", "set", "delete", "list", "compare_and_swap", "increment",
+    // "get_many", "set_many"
+    action: String,
+    key: Option<String>,
     value: Option<String>,
+    ttl_secs: Option<u64>,
+    #[serde(default)]
+    prefix: String,
+    cursor: Option<String>,
+    limit: Option<u32>,
+    expected: Option<String>,
+    delta: Option<i64>,
+    keys: Option<Vec<String>>,
+    entries: Option<Vec<KvSetEntry>>,
 }
 
-/// KV operation response
-#[derive(Serialize)]
-struct KvResponse {
-    value: Option<String>,
+impl KvOperation {
+    /// Convert to the operation plugins send over the host socket
+    fn into_kv_op(self) -> Result<KvOp, String> {
+        let missing =
+            |field: &str| format!("Missing '{}' field for {} operation", field, self.action);
+        let key = self.key.clone().ok_or_else(|| missing("key"));
+
+        let op = match self.action.as_str() {
+            "get" => KvOp::Get { key: key? },
+            "set" => KvOp::Set {
+                key: key?,
+                value: self.value.clone().ok_or_else(|| missing("value"))?,
+                ttl_secs: self.ttl_secs,
+            },
+            "delete" => KvOp::Delete { key: key? },
+            "list" => KvOp::List {
+                prefix: self.prefix,
+                cursor: self.cursor,
+                limit: self.limit,
+            },
+            "compare_and_swap" => KvOp::CompareAndSwap {
+                key: key?,
+                expected: self.expected,
+                new: self.value,
+            },
+            "increment" => KvOp::Increment {
+                key: key?,
+                delta: self.delta.unwrap_or(1),
+            },
+            "get_many" => KvOp::GetMany {
+                keys: self.keys.clone().ok_or_else(|| missing("keys"))?,
+            },
+            "set_many" => KvOp::SetMany {
+                entries: self.entries.clone().ok_or_else(|| missing("entries"))?,
+            },
+            _ => return Err(format!("Invalid action: {}", self.action)),
+        };
+        Ok(op)
+    }
 }
 
 /// Handle KV storage operations for plugins
+///
+/// The response always has `value` (the stored value for `get` and `set`,
+/// the new count for `increment`) and `result` for the richer operations.
 async fn plugin_kv_handler(
     _auth: AuthUser,
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(op): Json<KvOperation>,
-) -> Result<Json<KvResponse>, (StatusCode, Json<serde_json::Value>)> {
-    // Validate action
-    match op.action.as_str() {
-        "get" => {
-            // Get value from database
-            let value = crate::db::plugin_kv_get(&state.db, &id, &op.key)
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({ "error": format!("Failed to get KV: {}", e) })),
-                    )
-                })?;
-            Ok(Json(KvResponse { value }))
-        }
-        "set" => {
-            // Set value in database
-            let value = op.value.ok_or((
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Missing 'value' field for set operation" })),
-            ))?;
+) -> Result<Json<KvMessageResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let op = op.into_kv_op().map_err(|message| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": message })),
+        )
+    })?;
+    // The frontend expects `set` to echo the stored value
+    let set_value = match &op {
+        KvOp::Set { value, .. } => Some(value.clone()),
+        _ => None,
+    };
 
-            crate::db::plugin_kv_set(&state.db, &id, &op.key, &value)
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({ "error": format!("Failed to set KV: {}", e) })),
-                    )
-                })?;
-
-            Ok(Json(KvResponse { value: Some(value) }))
-        }
-        "delete" => {
-            // Delete value from database
-            crate::db::plugin_kv_delete(&state.db, &id, &op.key)
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({ "error": format!("Failed to delete KV: {}", e) })),
-                    )
-                })?;
-
-            Ok(Json(KvResponse { value: None }))
+    let store = SqliteKvStore::new(state.db.clone(), id);
+    let mut response = toru_plugin_api::kv::execute(&store, op)
+        .await
+        .map_err(|e| {
+            let status = match e {
+                PluginError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+                PluginError::QuotaExceeded(_) => StatusCode::INSUFFICIENT_STORAGE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(serde_json::json!({ "error": e.to_string() })))
+        })?;
+    if set_value.is_some() {
+        response.value = set_value;
+    }
+
+    Ok(Json(response))
+}
+
+/// Largest page of KV entries returned to admins
+const MAX_KV_BROWSE_LIMIT: usize = 1000;
+
+fn kv_error(status: StatusCode, message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (status, Json(serde_json::json!({ "error": message })))
+}
+
+#[derive(Deserialize)]
+struct KvBrowseQuery {
+    /// Only entries whose key or value contains this text
+    search: Option<String>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    #[serde(default = "default_page_size")]
+    limit: usize,
+}
+
+#[derive(Serialize)]
+struct KvBrowseResponse {
+    entries: Vec<db::PluginKvEntry>,
+    next_cursor: Option<String>,
+}
+
+/// List and search a plugin's KV entries
+async fn browse_plugin_kv(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<KvBrowseQuery>,
+) -> Result<Json<KvBrowseResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let limit = query.limit.clamp(1, MAX_KV_BROWSE_LIMIT);
+    let search = query.search.as_deref().filter(|s| !s.is_empty());
+
+    // Fetch one extra entry to know whether another page follows
+    let mut entries =
+        db::plugin_kv_browse(&state.db, &id, search, query.cursor.as_deref(), limit + 1)
+            .await
+            .map_err(|e| {
+                kv_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to list KV entries: {}", e),
+                )
+            })?;
+
+    let next_cursor = if entries.len() > limit {
+        entries.truncate(limit);
+        entries.last().map(|entry| entry.key.clone())
+    } else {
+        None
+    };
+
+    Ok(Json(KvBrowseResponse {
+        entries,
+        next_cursor,
+    }))
+}
+
+#[derive(Deserialize)]
+struct KvEntryUpdate {
+    value: String,
+    /// Expire the entry after this many seconds
+    ttl_secs: Option<u64>,
+}
+
+/// Create or overwrite a single KV entry
+async fn put_plugin_kv_entry(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path((id, key)): Path<(String, String)>,
+    Json(update): Json<KvEntryUpdate>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    db::plugin_kv_set_with_ttl(&state.db, &id, &key, &update.value, update.ttl_secs)
+        .await
+        .map_err(|e| {
+            let status = if e.is::<db::QuotaExceeded>() {
+                StatusCode::INSUFFICIENT_STORAGE
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            kv_error(status, format!("Failed to set KV entry: {}", e))
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete a single KV entry
+async fn delete_plugin_kv_entry(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path((id, key)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    db::plugin_kv_delete(&state.db, &id, &key)
+        .await
+        .map_err(|e| {
+            kv_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete KV entry: {}", e),
+            )
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A plugin's KV namespace as exported and imported
+#[derive(Serialize, Deserialize)]
+struct KvExport {
+    plugin_id: String,
+    exported_at: String,
+    entries: Vec<db::PluginKvEntry>,
+}
+
+/// Download a plugin's KV namespace as JSON
+async fn export_plugin_kv(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    // The ID ends up in the Content-Disposition header
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(kv_error(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid plugin ID: {:?}", id),
+        ));
+    }
+
+    let entries = db::plugin_kv_get_all(&state.db, &id).await.map_err(|e| {
+        kv_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to export KV entries: {}", e),
+        )
+    })?;
+
+    let export = KvExport {
+        plugin_id: id.clone(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        entries,
+    };
+    let disposition = format!("attachment; filename=\"{}-kv.json\"", id);
+
+    Ok(([(header::CONTENT_DISPOSITION, disposition)], Json(export)).into_response())
+}
+
+#[derive(Deserialize)]
+struct KvImportQuery {
+    /// "merge" (default) keeps keys missing from the import, "replace" deletes them
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+/// Import a KV export into a plugin's namespace
+///
+/// The export may come from another plugin ID, e.g. when a plugin was
+/// renamed while migrating servers.
+async fn import_plugin_kv(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<KvImportQuery>,
+    Json(import): Json<KvExport>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let replace = match query.mode.as_deref() {
+        None | Some("merge") => false,
+        Some("replace") => true,
+        Some(mode) => {
+            return Err(kv_error(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid import mode: {}", mode),
+            ))
         }
-        _ => Err((
+    };
+
+    let imported = db::plugin_kv_import(&state.db, &id, &import.entries, replace)
+        .await
+        .map_err(|e| {
+            kv_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to import KV entries: {}", e),
+            )
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "imported": imported,
+        "skipped": import.entries.len() - imported,
+    })))
+}
+
+/// Data directory of a known plugin
+///
+/// Plugin IDs are checked against the supervisor first so the ID from the
+/// URL can never point outside `plugins/.data/`.
+async fn plugin_data_dir(state: &AppState, id: &str) -> Result<PathBuf, StatusCode> {
+    let supervisor = state
+        .supervisor
+        .as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?
+        .lock()
+        .await;
+    supervisor
+        .get_plugin_status(id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(supervisor.plugin_data_dir(id))
+}
+
+/// List the files in a plugin's data directory
+async fn list_plugin_data(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let data_dir = plugin_data_dir(&state, &id).await?;
+    let files =
+        plugin_data::list_files(&data_dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let quota = db::plugin_quota(&state.db, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "plugin_id": id,
+        "total_bytes": files.iter().map(|f| f.size).sum::<u64>(),
+        "quota_bytes": quota.data_max_bytes,
+        "files": files,
+    })))
+}
+
+/// Download a file from a plugin's data directory
+async fn download_plugin_data(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path((id, path)): Path<(String, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let data_dir = plugin_data_dir(&state, &id).await?;
+
+    // Security: resolve_asset rejects paths (and symlinks) escaping the directory
+    let file_path = plugin_package::resolve_asset(&data_dir, &path).ok_or(StatusCode::NOT_FOUND)?;
+    let content = fs::read(&file_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().replace('"', ""))
+        .unwrap_or_default();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        content,
+    ))
+}
+
+/// Delete a file from a plugin's data directory, e.g. to get back under quota
+async fn delete_plugin_data(
+    _auth: AdminUser,
+    State(state): State<AppState>,
+    Path((id, path)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let data_dir = plugin_data_dir(&state, &id).await?;
+    let file_path = plugin_package::resolve_asset(&data_dir, &path).ok_or(StatusCode::NOT_FOUND)?;
+    fs::remove_file(&file_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List metric names pushed by a plugin
+async fn list_plugin_timeseries(
+    _auth: AuthUser,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    let names = crate::db::plugin_metrics_names(&state.db, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(names))
+}
+
+#[derive(Deserialize)]
+struct TimeseriesQuery {
+    /// Range start (unix seconds), defaults to one hour before `end`
+    start: Option<i64>,
+    /// Range end (unix seconds), defaults to now
+    end: Option<i64>,
+    #[serde(default = "default_step")]
+    step: i64,
+    #[serde(default)]
+    agg: Option<String>,
+    /// Label filters as "key=value,key2=value2"
+    #[serde(default)]
+    labels: Option<String>,
+}
+
+fn default_step() -> i64 {
+    60
+}
+
+#[derive(Serialize)]
+struct TimeseriesResponse {
+    name: String,
+    start: i64,
+    end: i64,
+    step: i64,
+    agg: Aggregation,
+    series: Vec<Series>,
+}
+
+/// Query a plugin metric over a time range (for plugin frontends and dashboard widgets)
+async fn query_plugin_timeseries(
+    _auth: AuthUser,
+    State(state): State<AppState>,
+    Path((id, name)): Path<(String, String)>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<Json<TimeseriesResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let bad_request = |msg: String| {
+        (
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": format!("Invalid action: {}", op.action) })),
-        )),
+            Json(serde_json::json!({ "error": msg })),
+        )
+    };
+
+    let aggregation = match query.agg.as_deref() {
+        None => Aggregation::Avg,
+        Some(agg) => Aggregation::parse(agg)
+            .ok_or_else(|| bad_request(format!("Invalid aggregation: {}", agg)))?,
+    };
+
+    let mut labels = Vec::new();
+    if let Some(filter) = query.labels.as_deref().filter(|f| !f.is_empty()) {
+        for pair in filter.split(',') {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| bad_request(format!("Invalid label filter: {}", pair)))?;
+            labels.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let end = query.end.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let range = RangeQuery {
+        name: name.clone(),
+        labels,
+        start: query.start.unwrap_or(end - 3600),
+        end,
+        step: query.step,
+        aggregation,
+    };
+
+    let series = timeseries::query_range(&state.db, &id, &range)
+        .await
+        .map_err(|e| bad_request(e.to_string()))?;
+
+    Ok(Json(TimeseriesResponse {
+        name,
+        start: range.start,
+        end: range.end,
+        step: range.step,
+        agg: aggregation,
+        series,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::auth::create_user_session;
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use sysinfo::System;
+    use tokio::sync::Mutex;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_kv_import_larger_than_default_body_limit() {
+        let pool = db::init_db().unwrap();
+        let session = create_user_session(&pool, None, "admin", db::UserRole::Admin)
+            .await
+            .unwrap();
+        let router = create_plugin_router().with_state(AppState {
+            db: pool.clone(),
+            sys: Arc::new(Mutex::new(System::new())),
+            supervisor: None,
+        });
+        let cookie = format!("{}={}", SESSION_COOKIE_NAME, session.id);
+
+        // 3 MB of values, above axum's default 2 MB body limit
+        let plugin_id = "test-kv-import-large";
+        let export = KvExport {
+            plugin_id: plugin_id.to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            entries: (0..3)
+                .map(|i| db::PluginKvEntry {
+                    key: format!("blob-{}", i),
+                    value: "x".repeat(1024 * 1024),
+                    expires_at: None,
+                })
+                .collect(),
+        };
+        let request = Request::post(format!("/{}/kv/import?mode=replace", plugin_id))
+            .header(header::COOKIE, &cookie)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&export).unwrap()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["imported"], 3);
+
+        // An ID that would break out of the Content-Disposition filename
+        let request = Request::get("/bad%22id/kv/export")
+            .header(header::COOKIE, &cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        db::plugin_kv_import(&pool, plugin_id, &[], true)
+            .await
+            .unwrap();
+        db::delete_session(&pool, &session.id).await.unwrap();
     }
 }
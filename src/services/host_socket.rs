@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use toru_plugin_api::{
+    KvMessagePayload, KvMessageResponse, Message, MessagePayload, PluginError, PluginProtocol,
+    SqlMessagePayload, SqlMessageResponse,
+};
+
+use super::kv_store::SqliteKvStore;
+use super::plugin_database::SqlitePluginDatabase;
+use super::timeseries;
+use crate::db::DbPool;
+
+/// Bind the per-plugin host socket and start serving plugin-initiated messages
+///
+/// Each plugin gets its own socket, so the plugin identity of every message
+/// is derived from the socket it arrived on and never from the payload.
+///
+/// # Arguments
+/// * `plugin_id` - Plugin owning this socket
+/// * `socket_path` - Path to bind (removed first if it already exists)
+/// * `db_pool` - Database pool used to store plugin data
+/// * `database` - The plugin's own SQL database, if it has one
+///
+/// # Returns
+/// Handle of the accept loop task; abort it to stop serving
+pub fn spawn_host_listener(
+    plugin_id: &str,
+    socket_path: &Path,
+    db_pool: DbPool,
+    database: Option<SqlitePluginDatabase>,
+) -> Result<JoinHandle<()>> {
+    if socket_path.exists() {
+        fs::remove_file(socket_path).ok();
+    }
+
+    let listener = UnixListener::bind(socket_path).context("Failed to bind host socket")?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600)).ok();
+    }
+
+    let plugin_id = plugin_id.to_string();
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let plugin_id = plugin_id.clone();
+                    let db_pool = db_pool.clone();
+                    let database = database.clone();
+                    tokio::spawn(async move {
+                        handle_connection(&plugin_id, stream, &db_pool, database.as_ref()).await;
+                    });
+                }
+                Err(e) => {
+                    warn!("Host socket accept failed for plugin {}: {}", plugin_id, e);
+                    break;
+                }
+            }
+        }
+    }))
+}
+
+/// Read messages from a single plugin connection until it closes
+async fn handle_connection(
+    plugin_id: &str,
+    mut stream: UnixStream,
+    db_pool: &DbPool,
+    database: Option<&SqlitePluginDatabase>,
+) {
+    let mut protocol = PluginProtocol::new();
+
+    loop {
+        let message = match protocol.read_message(&mut stream).await {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("Host connection from plugin {} closed: {}", plugin_id, e);
+                break;
+            }
+        };
+
+        // The plugin is waiting for an answer to these
+        let expects_reply = matches!(
+            message.payload,
+            MessagePayload::Kv { .. } | MessagePayload::Sql { .. }
+        );
+
+        match handle_message(plugin_id, message, db_pool, database).await {
+            Ok(Some(reply)) => {
+                if let Err(e) = protocol.write_message(&mut stream, &reply).await {
+                    debug!("Failed to reply to plugin {}: {}", plugin_id, e);
+                    break;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Failed to handle message from plugin {}: {}", plugin_id, e);
+                // Don't leave the plugin waiting for a reply that never comes
+                if expects_reply {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Dispatch a single plugin-initiated message
+///
+/// # Returns
+/// The reply to send back, if the message expects one
+async fn handle_message(
+    plugin_id: &str,
+    message: Message,
+    db_pool: &DbPool,
+    database: Option<&SqlitePluginDatabase>,
+) -> Result<Option<Message>> {
+    match message.payload {
+        MessagePayload::Metrics { samples } => {
+            let stored = timeseries::record_samples(db_pool, plugin_id, &samples).await?;
+            debug!("Stored {} metric samples from plugin {}", stored, plugin_id);
+            Ok(None)
+        }
+        MessagePayload::Kv {
+            request_id,
+            payload: KvMessagePayload::Request(op),
+        } => {
+            // Keys are always scoped to the plugin owning this socket
+            let store = SqliteKvStore::new(db_pool.clone(), plugin_id.to_string());
+            let response = toru_plugin_api::kv::execute(&store, op)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("KV operation from plugin {} failed: {}", plugin_id, e);
+                    KvMessageResponse::failed(&e)
+                });
+            Ok(Some(Message::new_kv_reply(request_id, response)))
+        }
+        MessagePayload::Sql {
+            request_id,
+            payload: SqlMessagePayload::Request(op),
+        } => {
+            // Only ever the database of the plugin owning this socket
+            let response = match database {
+                Some(database) => toru_plugin_api::sql::execute(database, op).await,
+                None => Err(PluginError::Internal(
+                    "No database is available to this plugin".to_string(),
+                )),
+            }
+            .unwrap_or_else(|e| {
+                debug!("SQL operation from plugin {} failed: {}", plugin_id, e);
+                SqlMessageResponse::failed(&e)
+            });
+            Ok(Some(Message::new_sql_reply(request_id, response)))
+        }
+        // Both payloads are untagged, so an unknown operation parses as a
+        // response; answer it rather than leave the plugin waiting
+        MessagePayload::Kv {
+            request_id,
+            payload: KvMessagePayload::Response(_),
+        } => {
+            warn!("Unrecognized KV operation from plugin {}", plugin_id);
+            let error = PluginError::InvalidRequest("Unrecognized KV operation".to_string());
+            Ok(Some(Message::new_kv_reply(
+                request_id,
+                KvMessageResponse::failed(&error),
+            )))
+        }
+        MessagePayload::Sql {
+            request_id,
+            payload: SqlMessagePayload::Response(_),
+        } => {
+            warn!("Unrecognized SQL operation from plugin {}", plugin_id);
+            let error = PluginError::InvalidRequest("Unrecognized SQL operation".to_string());
+            Ok(Some(Message::new_sql_reply(
+                request_id,
+                SqlMessageResponse::failed(&error),
+            )))
+        }
+        _ => {
+            debug!(
+                "Ignoring unsupported {} message from plugin {}",
+                message.message_type, plugin_id
+            );
+            Ok(None)
+        }
+    }
+}
@@ -1,19 +1,18 @@
-use crate::db::DbPool;
-use toru_plugin_api::{PluginError, PluginResult};
+use std::time::Duration;
+
+use crate::db::{DbPool, QuotaExceeded};
+use toru_plugin_api::{KvEntry, KvPage, KvSetEntry, PluginError, PluginResult};
 
 /// Sqlite-backed key-value store for plugins
 ///
 /// Each plugin gets its own isolated namespace in the plugin_kv table.
 /// This implements the PluginKvStore trait from toru-plugin-api.
-// TODO: Integrate KV store into plugin API when plugins need persistent storage
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct SqliteKvStore {
     pool: DbPool,
     plugin_id: String,
 }
 
-#[allow(dead_code)]
 impl SqliteKvStore {
     /// Create a new SqliteKvStore for a specific plugin
     ///
@@ -25,11 +24,20 @@ impl SqliteKvStore {
     }
 
     /// Get the plugin ID
+    #[allow(dead_code)]
     pub fn plugin_id(&self) -> &str {
         &self.plugin_id
     }
 }
 
+/// Convert a database error, keeping quota violations distinguishable
+fn storage_error(context: &str, error: anyhow::Error) -> PluginError {
+    match error.downcast_ref::<QuotaExceeded>() {
+        Some(quota) => PluginError::QuotaExceeded(quota.to_string()),
+        None => PluginError::Internal(format!("{}: {}", context, error)),
+    }
+}
+
 #[async_trait::async_trait]
 impl toru_plugin_api::PluginKvStore for SqliteKvStore {
     /// Get a value from the plugin's KV namespace
@@ -42,7 +50,7 @@ impl toru_plugin_api::PluginKvStore for SqliteKvStore {
     async fn get(&self, key: &str) -> PluginResult<Option<String>> {
         crate::db::plugin_kv_get(&self.pool, &self.plugin_id, key)
             .await
-            .map_err(|e| PluginError::Internal(format!("Failed to get value: {}", e)))
+            .map_err(|e| storage_error("Failed to get value", e))
     }
 
     /// Set a value in the plugin's KV namespace
@@ -53,7 +61,7 @@ impl toru_plugin_api::PluginKvStore for SqliteKvStore {
     async fn set(&self, key: &str, value: &str) -> PluginResult<()> {
         crate::db::plugin_kv_set(&self.pool, &self.plugin_id, key, value)
             .await
-            .map_err(|e| PluginError::Internal(format!("Failed to set value: {}", e)))
+            .map_err(|e| storage_error("Failed to set value", e))
     }
 
     /// Delete a value from the plugin's KV namespace
@@ -63,7 +71,93 @@ impl toru_plugin_api::PluginKvStore for SqliteKvStore {
     async fn delete(&self, key: &str) -> PluginResult<()> {
         crate::db::plugin_kv_delete(&self.pool, &self.plugin_id, key)
             .await
-            .map_err(|e| PluginError::Internal(format!("Failed to delete value: {}", e)))
+            .map_err(|e| storage_error("Failed to delete value", e))
+    }
+
+    /// Set a value that expires after `ttl`
+    ///
+    /// Expired values are invisible immediately and removed from the
+    /// database by a periodic purge.
+    async fn set_with_ttl(&self, key: &str, value: &str, ttl: Duration) -> PluginResult<()> {
+        crate::db::plugin_kv_set_with_ttl(
+            &self.pool,
+            &self.plugin_id,
+            key,
+            value,
+            Some(ttl.as_secs()),
+        )
+        .await
+        .map_err(|e| storage_error("Failed to set value", e))
+    }
+
+    /// List entries by key prefix
+    ///
+    /// # Arguments
+    /// * `prefix` - Key prefix, empty for all keys
+    /// * `cursor` - Last key of the previous page
+    /// * `limit` - Maximum number of entries
+    async fn list(&self, prefix: &str, cursor: Option<&str>, limit: usize) -> PluginResult<KvPage> {
+        // Fetch one extra row to know whether another page follows
+        let mut rows =
+            crate::db::plugin_kv_list(&self.pool, &self.plugin_id, prefix, cursor, limit + 1)
+                .await
+                .map_err(|e| storage_error("Failed to list values", e))?;
+
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|(key, _)| key.clone())
+        } else {
+            None
+        };
+        Ok(KvPage {
+            entries: rows
+                .into_iter()
+                .map(|(key, value)| KvEntry { key, value })
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Replace a value only if it currently equals `expected`
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        expected: Option<&str>,
+        new: Option<&str>,
+    ) -> PluginResult<bool> {
+        crate::db::plugin_kv_compare_and_swap(&self.pool, &self.plugin_id, key, expected, new)
+            .await
+            .map_err(|e| storage_error("Failed to swap value", e))
+    }
+
+    /// Add `delta` to an integer value
+    ///
+    /// Fails with InvalidRequest if the current value is not an integer.
+    async fn increment(&self, key: &str, delta: i64) -> PluginResult<i64> {
+        crate::db::plugin_kv_increment(&self.pool, &self.plugin_id, key, delta)
+            .await
+            .map_err(|e| storage_error("Failed to increment value", e))?
+            .ok_or_else(|| {
+                PluginError::InvalidRequest(format!("Value of {:?} cannot be incremented", key))
+            })
+    }
+
+    /// Get several values with a single lock of the database
+    async fn get_many(&self, keys: &[String]) -> PluginResult<Vec<Option<String>>> {
+        crate::db::plugin_kv_get_many(&self.pool, &self.plugin_id, keys)
+            .await
+            .map_err(|e| storage_error("Failed to get values", e))
+    }
+
+    /// Set several values in one transaction
+    async fn set_many(&self, entries: &[KvSetEntry]) -> PluginResult<()> {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|e| (e.key.clone(), e.value.clone(), e.ttl_secs))
+            .collect();
+        crate::db::plugin_kv_set_many(&self.pool, &self.plugin_id, &entries)
+            .await
+            .map_err(|e| storage_error("Failed to set values", e))
     }
 }
 
@@ -119,4 +213,146 @@ mod tests {
             Some("value-b".to_string())
         );
     }
+
+    #[tokio::test]
+    async fn test_kv_store_list_pagination() {
+        let pool = crate::db::init_db().unwrap();
+        let kv = SqliteKvStore::new(pool, "test-plugin-list".to_string());
+
+        for i in 0..5 {
+            kv.set(&format!("item:{}", i), &i.to_string())
+                .await
+                .unwrap();
+        }
+        kv.set("other", "x").await.unwrap();
+
+        let first = kv.list("item:", None, 2).await.unwrap();
+        assert_eq!(first.entries.len(), 2);
+        assert_eq!(first.entries[0].key, "item:0");
+        assert_eq!(first.next_cursor.as_deref(), Some("item:1"));
+
+        let second = kv
+            .list("item:", first.next_cursor.as_deref(), 10)
+            .await
+            .unwrap();
+        let keys: Vec<_> = second.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["item:2", "item:3", "item:4"]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_kv_store_ttl_expiry() {
+        let pool = crate::db::init_db().unwrap();
+        let kv = SqliteKvStore::new(pool.clone(), "test-plugin-ttl".to_string());
+
+        kv.set_with_ttl("short", "gone", Duration::ZERO)
+            .await
+            .unwrap();
+        kv.set_with_ttl("long", "kept", Duration::from_secs(3600))
+            .await
+            .unwrap();
+
+        assert_eq!(kv.get("short").await.unwrap(), None);
+        assert_eq!(kv.get("long").await.unwrap(), Some("kept".to_string()));
+        assert!(kv.list("", None, 10).await.unwrap().entries.len() == 1);
+
+        crate::db::plugin_kv_purge_expired(&pool).await.unwrap();
+        let conn = pool.lock().await;
+        let rows: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM plugin_kv WHERE plugin_id = 'test-plugin-ttl' AND key = 'short'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_kv_store_compare_and_swap_and_increment() {
+        let pool = crate::db::init_db().unwrap();
+        let kv = SqliteKvStore::new(pool, "test-plugin-cas".to_string());
+        kv.delete("lock").await.unwrap();
+        kv.delete("counter").await.unwrap();
+
+        assert!(kv.compare_and_swap("lock", None, Some("a")).await.unwrap());
+        assert!(!kv.compare_and_swap("lock", None, Some("b")).await.unwrap());
+        assert!(kv.compare_and_swap("lock", Some("a"), None).await.unwrap());
+        assert_eq!(kv.get("lock").await.unwrap(), None);
+
+        assert_eq!(kv.increment("counter", 5).await.unwrap(), 5);
+        assert_eq!(kv.increment("counter", -2).await.unwrap(), 3);
+        kv.set("lock", "text").await.unwrap();
+        assert!(matches!(
+            kv.increment("lock", 1).await,
+            Err(PluginError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_kv_store_batches() {
+        let pool = crate::db::init_db().unwrap();
+        let kv = SqliteKvStore::new(pool, "test-plugin-batch".to_string());
+
+        kv.set_many(&[
+            KvSetEntry {
+                key: "a".to_string(),
+                value: "1".to_string(),
+                ttl_secs: None,
+            },
+            KvSetEntry {
+                key: "b".to_string(),
+                value: "2".to_string(),
+                ttl_secs: Some(3600),
+            },
+        ])
+        .await
+        .unwrap();
+
+        let values = kv
+            .get_many(&["b".to_string(), "missing".to_string(), "a".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(values, [Some("2".to_string()), None, Some("1".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_kv_store_quota() {
+        let pool = crate::db::init_db().unwrap();
+        let plugin_id = "test-plugin-quota";
+        for (name, value) in [
+            ("plugin_quota_kv_max_keys", "2"),
+            ("plugin_quota_kv_max_value_bytes", "8"),
+            ("plugin_quota_kv_max_bytes", "16"),
+        ] {
+            crate::db::set_setting(&pool, &format!("{}.{}", name, plugin_id), value)
+                .await
+                .unwrap();
+        }
+        let kv = SqliteKvStore::new(pool.clone(), plugin_id.to_string());
+        for key in ["a", "b", "c"] {
+            kv.delete(key).await.unwrap();
+        }
+
+        assert!(matches!(
+            kv.set("a", "123456789").await,
+            Err(PluginError::QuotaExceeded(_))
+        ));
+        kv.set("a", "12345678").await.unwrap();
+        kv.set("b", "1234").await.unwrap();
+        assert!(matches!(
+            kv.set("c", "1").await,
+            Err(PluginError::QuotaExceeded(_))
+        ));
+        // 1 + 8 + 1 + 4 bytes used, growing "b" by 4 would exceed 16
+        assert!(matches!(
+            kv.set("b", "12345678").await,
+            Err(PluginError::QuotaExceeded(_))
+        ));
+        kv.set("b", "1").await.unwrap();
+
+        let usage = crate::db::plugin_kv_usage(&pool, plugin_id).await.unwrap();
+        assert_eq!(usage.keys, 2);
+        assert_eq!(usage.bytes, 11);
+    }
 }
@@ -0,0 +1,432 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::db::{self, DbPool};
+
+/// Settings key: size in bytes at which a log file is rotated
+pub const MAX_FILE_BYTES_SETTING: &str = "log_max_file_bytes";
+/// Settings key: rotated files kept per log
+pub const MAX_ROTATED_FILES_SETTING: &str = "log_max_rotated_files";
+/// Settings key: days a rotated file is kept, 0 for no age limit
+pub const MAX_AGE_DAYS_SETTING: &str = "log_max_age_days";
+/// Settings key: total bytes of rotated files kept per log, 0 for no limit
+pub const MAX_TOTAL_BYTES_SETTING: &str = "log_max_total_bytes";
+/// Settings key: "false" keeps rotated files uncompressed
+pub const COMPRESS_SETTING: &str = "log_compress_rotated";
+
+/// Format of the timestamp in rotated file names
+const ROTATION_TIMESTAMP: &str = "%Y%m%d-%H%M%S";
+
+/// How log files are rotated and how long rotated files are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRetention {
+    /// Size at which a log file is rotated (in bytes)
+    pub max_file_size: u64,
+    /// Rotated files kept per log
+    pub max_rotated_files: usize,
+    /// Rotated files older than this many days are deleted
+    pub max_age_days: Option<u64>,
+    /// Total size of rotated files kept per log (in bytes)
+    pub max_total_bytes: Option<u64>,
+    /// Whether rotated files are gzip-compressed
+    pub compress: bool,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_file_size: 10 * 1024 * 1024, // 10 MB
+            max_rotated_files: 5,
+            max_age_days: Some(30),
+            max_total_bytes: None,
+            compress: true,
+        }
+    }
+}
+
+impl LogRetention {
+    /// Load the policy from settings, using defaults for unset or invalid values
+    pub async fn load(pool: &DbPool) -> Self {
+        let defaults = Self::default();
+        let number = |key: &'static str| async move {
+            let value = db::get_setting(pool, key).await.ok().flatten()?;
+            match value.trim().parse::<u64>() {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid {} setting {:?}", key, value);
+                    None
+                }
+            }
+        };
+        // 0 disables the age and total size limits
+        let limit = |value: Option<u64>, default: Option<u64>| match value {
+            Some(0) => None,
+            Some(value) => Some(value),
+            None => default,
+        };
+
+        Self {
+            max_file_size: number(MAX_FILE_BYTES_SETTING)
+                .await
+                .filter(|&bytes| bytes > 0)
+                .unwrap_or(defaults.max_file_size),
+            max_rotated_files: number(MAX_ROTATED_FILES_SETTING)
+                .await
+                .map(|files| files as usize)
+                .unwrap_or(defaults.max_rotated_files),
+            max_age_days: limit(number(MAX_AGE_DAYS_SETTING).await, defaults.max_age_days),
+            max_total_bytes: limit(
+                number(MAX_TOTAL_BYTES_SETTING).await,
+                defaults.max_total_bytes,
+            ),
+            compress: db::get_setting(pool, COMPRESS_SETTING)
+                .await
+                .ok()
+                .flatten()
+                .map_or(defaults.compress, |v| v != "false"),
+        }
+    }
+}
+
+/// Whether a settings key configures log retention
+pub fn is_retention_setting(key: &str) -> bool {
+    [
+        MAX_FILE_BYTES_SETTING,
+        MAX_ROTATED_FILES_SETTING,
+        MAX_AGE_DAYS_SETTING,
+        MAX_TOTAL_BYTES_SETTING,
+        COMPRESS_SETTING,
+    ]
+    .contains(&key)
+}
+
+/// A rotated copy of a log file
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotatedLog {
+    pub path: PathBuf,
+    pub rotated_at: DateTime<Utc>,
+    /// Orders files rotated within the same second, 0 for the first
+    pub sequence: u32,
+    pub compressed: bool,
+}
+
+/// Split a file name into log stem, rotation time, sequence and compression
+///
+/// Rotated files are named `<stem>-<%Y%m%d-%H%M%S>.log`, or
+/// `<stem>-<%Y%m%d-%H%M%S>-<n>.log` when rotated again within the same
+/// second, plus `.gz` once compressed.
+fn parse_rotated_name(name: &str) -> Option<(&str, DateTime<Utc>, u32, bool)> {
+    let (rest, compressed) = match name.strip_suffix(".gz") {
+        Some(rest) => (rest, true),
+        None => (name, false),
+    };
+    let rest = rest.strip_suffix(".log")?;
+    if let Some((stem, rotated_at)) = split_timestamp(rest) {
+        return Some((stem, rotated_at, 0, compressed));
+    }
+
+    let (rest, sequence) = rest.rsplit_once('-')?;
+    if !sequence.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let sequence = sequence.parse().ok().filter(|&n| n > 0)?;
+    let (stem, rotated_at) = split_timestamp(rest)?;
+    Some((stem, rotated_at, sequence, compressed))
+}
+
+/// Split `<stem>-<%Y%m%d-%H%M%S>` into its stem and time
+fn split_timestamp(name: &str) -> Option<(&str, DateTime<Utc>)> {
+    let split = name.len().checked_sub(16)?;
+    let (stem, timestamp) = (name.get(..split)?, name.get(split..)?.strip_prefix('-')?);
+    let rotated_at = NaiveDateTime::parse_from_str(timestamp, ROTATION_TIMESTAMP).ok()?;
+    (!stem.is_empty()).then_some((stem, rotated_at.and_utc()))
+}
+
+/// Rotated copies of a log file, newest first
+///
+/// Only files rotated from exactly this log are listed, not those of logs
+/// whose name merely starts the same. While a file is being compressed,
+/// only its uncompressed copy is listed.
+///
+/// # Arguments
+/// * `log_path` - Path of the current log file, which need not exist
+pub fn rotated_logs(log_path: &Path) -> Vec<RotatedLog> {
+    let (Some(dir), Some(stem)) = (
+        log_path.parent(),
+        log_path.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut logs: Vec<RotatedLog> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let name = entry.ok()?.file_name().into_string().ok()?;
+                    let (log_stem, rotated_at, sequence, compressed) = parse_rotated_name(&name)?;
+                    (log_stem == stem).then(|| RotatedLog {
+                        path: dir.join(&name),
+                        rotated_at,
+                        sequence,
+                        compressed,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Newest first, uncompressed before compressed copies of the same file
+    logs.sort_by(|a, b| {
+        (b.rotated_at, b.sequence)
+            .cmp(&(a.rotated_at, a.sequence))
+            .then(a.compressed.cmp(&b.compressed))
+    });
+    logs.dedup_by(|later, earlier| {
+        (later.rotated_at, later.sequence) == (earlier.rotated_at, earlier.sequence)
+    });
+    logs
+}
+
+/// Stems of all logs in a directory that have rotated files
+pub fn rotated_stems(dir: &Path) -> Vec<String> {
+    let mut stems: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let name = entry.ok()?.file_name().into_string().ok()?;
+                    parse_rotated_name(&name).map(|(stem, ..)| stem.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    stems.sort_unstable();
+    stems.dedup();
+    stems
+}
+
+/// Rotate a log file, then compress and prune its rotated copies
+///
+/// Compression and pruning run on a blocking thread so the async runtime
+/// keeps serving while a large file is compressed.
+///
+/// # Arguments
+/// * `log_path` - Log file to rotate
+/// * `retention` - Policy applied to the rotated copies
+pub async fn rotate(log_path: &Path, retention: &LogRetention) -> Result<()> {
+    let now = Utc::now();
+    let stem = log_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("log");
+    let timestamp = now.format(ROTATION_TIMESTAMP).to_string();
+
+    // Names have one-second resolution, and a rename would replace a file
+    // rotated earlier within the same second
+    let taken = |name: &str| {
+        let path = log_path.with_file_name(name);
+        let mut compressed = path.clone().into_os_string();
+        compressed.push(".gz");
+        path.exists() || Path::new(&compressed).exists()
+    };
+    let mut name = format!("{}-{}.log", stem, timestamp);
+    let mut sequence = 0;
+    while taken(&name) {
+        sequence += 1;
+        name = format!("{}-{}-{}.log", stem, timestamp, sequence);
+    }
+    let rotated_path = log_path.with_file_name(name);
+
+    match fs::rename(log_path, &rotated_path) {
+        Ok(()) => {}
+        // Rotated by a concurrent writer
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).context("Failed to rename log file for rotation"),
+    }
+
+    let log_path = log_path.to_path_buf();
+    let retention = retention.clone();
+    tokio::task::spawn_blocking(move || {
+        if retention.compress {
+            compress(&rotated_path)?;
+        }
+        prune(&log_path, &retention, now).map(|_| ())
+    })
+    .await
+    .context("Log rotation task failed")?
+}
+
+/// Replace a rotated file with a gzip-compressed copy
+fn compress(path: &Path) -> Result<()> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    let compressed = PathBuf::from(name);
+    let mut partial = compressed.clone().into_os_string();
+    partial.push(".tmp");
+    let partial = PathBuf::from(partial);
+
+    let mut input = File::open(path).context("Failed to open rotated log")?;
+    let mut encoder = GzEncoder::new(
+        BufWriter::new(File::create(&partial).context("Failed to create compressed log")?),
+        Compression::default(),
+    );
+    std::io::copy(&mut input, &mut encoder).context("Failed to compress rotated log")?;
+    encoder.finish()?.into_inner().map_err(|e| e.into_error())?;
+
+    fs::rename(&partial, &compressed)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Delete the rotated copies of a log the policy no longer keeps
+///
+/// A copy is kept while it is among the `max_rotated_files` newest, younger
+/// than `max_age_days`, and it and all newer copies fit in `max_total_bytes`.
+///
+/// # Arguments
+/// * `log_path` - Path of the current log file
+/// * `retention` - Policy to apply
+/// * `now` - Time ages are measured from
+///
+/// # Returns
+/// The number of files deleted
+pub fn prune(log_path: &Path, retention: &LogRetention, now: DateTime<Utc>) -> Result<usize> {
+    let mut total = 0;
+    let mut removed = 0;
+
+    for (index, log) in rotated_logs(log_path).into_iter().enumerate() {
+        total += fs::metadata(&log.path).map(|m| m.len()).unwrap_or(0);
+        let keep = index < retention.max_rotated_files
+            && retention
+                .max_age_days
+                .is_none_or(|days| now - log.rotated_at <= chrono::Duration::days(days as i64))
+            && retention.max_total_bytes.is_none_or(|max| total <= max);
+        if keep {
+            continue;
+        }
+
+        // Remove both copies of a file caught half way through compression
+        let mut compressed = log.path.clone().into_os_string();
+        compressed.push(".gz");
+        for path in [log.path, PathBuf::from(compressed)] {
+            match fs::remove_file(&path) {
+                Ok(()) => removed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("Failed to remove {:?}", path)),
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_parse_rotated_name() {
+        let (stem, at, sequence, compressed) =
+            parse_rotated_name("my-plugin-20250110-120000.log.gz").unwrap();
+        assert_eq!(stem, "my-plugin");
+        assert_eq!(at.to_rfc3339(), "2025-01-10T12:00:00+00:00");
+        assert_eq!(sequence, 0);
+        assert!(compressed);
+
+        let (stem, _, sequence, compressed) =
+            parse_rotated_name("my-plugin-20250110-120000-2.log").unwrap();
+        assert_eq!((stem, sequence, compressed), ("my-plugin", 2, false));
+        // A log whose own name ends in a timestamp
+        let (stem, ..) = parse_rotated_name("a-20250110-120000-20250111-090000.log").unwrap();
+        assert_eq!(stem, "a-20250110-120000");
+        assert!(parse_rotated_name("my-plugin-20250110-120000-+2.log").is_none());
+
+        assert!(parse_rotated_name("my-plugin.log").is_none());
+        assert!(parse_rotated_name("my-plugin-other.log").is_none());
+        assert!(parse_rotated_name("-20250110-120000.log").is_none());
+        assert!(parse_rotated_name("x-20251310-120000.log").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_compresses_and_prunes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("app.log");
+        let now = Utc::now();
+
+        // Older rotated files, and a log whose name starts the same
+        for days in [1, 2, 40] {
+            let at = now - chrono::Duration::days(days);
+            let name = format!("app-{}.log", at.format(ROTATION_TIMESTAMP));
+            fs::write(temp_dir.path().join(name), "x".repeat(100)).unwrap();
+        }
+        let other = temp_dir
+            .path()
+            .join(format!("app-other-{}.log", now.format(ROTATION_TIMESTAMP)));
+        fs::write(&other, "other").unwrap();
+
+        fs::write(&log_path, "current\n").unwrap();
+        let retention = LogRetention {
+            max_rotated_files: 3,
+            ..Default::default()
+        };
+        rotate(&log_path, &retention).await.unwrap();
+
+        assert!(!log_path.exists());
+        assert!(other.exists());
+        let logs = rotated_logs(&log_path);
+        assert_eq!(logs.len(), 3, "the file past max_age_days is gone");
+        assert!(logs[0].compressed);
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(File::open(&logs[0].path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "current\n");
+
+        // Total size keeps only the newest files that fit
+        let retention = LogRetention {
+            max_total_bytes: Some(logs[0].path.metadata().unwrap().len() + 100),
+            ..Default::default()
+        };
+        assert_eq!(prune(&log_path, &retention, now).unwrap(), 1);
+        assert_eq!(rotated_logs(&log_path).len(), 2);
+        assert_eq!(rotated_stems(temp_dir.path()), ["app", "app-other"]);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_twice_within_a_second() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("app.log");
+        let retention = LogRetention {
+            compress: false,
+            ..Default::default()
+        };
+
+        // Retry in the rare case the rotations straddle a second boundary
+        let logs = loop {
+            for content in ["first", "second", "third"] {
+                fs::write(&log_path, content).unwrap();
+                rotate(&log_path, &retention).await.unwrap();
+            }
+            let logs = rotated_logs(&log_path);
+            if logs.iter().all(|log| log.rotated_at == logs[0].rotated_at) {
+                break logs;
+            }
+            for log in logs {
+                fs::remove_file(log.path).unwrap();
+            }
+        };
+
+        let contents: Vec<String> = logs
+            .iter()
+            .map(|log| fs::read_to_string(&log.path).unwrap())
+            .collect();
+        assert_eq!(contents, ["third", "second", "first"]);
+        assert_eq!(logs[2].sequence, 0);
+        assert_eq!(logs[0].sequence, 2);
+    }
+}
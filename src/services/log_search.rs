@@ -0,0 +1,481 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::logging::{LogEntry, LogFilter};
+
+/// Bytes read at a time when scanning a log file backwards
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Longest first line read to identify a log file
+const MAX_FIRST_LINE: u64 = 64 * 1024;
+
+/// Most lines examined by one search; a search stopping here returns a
+/// cursor to continue from
+pub const MAX_SCANNED_LINES: usize = 100_000;
+
+/// Position in a plugin's log history
+///
+/// Files are identified by their first line rather than their name, so a
+/// cursor stays valid while lines are appended and the file is rotated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogCursor {
+    file: u64,
+    /// End of the part of the file not returned yet
+    offset: u64,
+}
+
+impl LogCursor {
+    /// Opaque string form handed to clients
+    pub fn encode(&self) -> String {
+        format!("{:016x}-{}", self.file, self.offset)
+    }
+
+    /// Parse a cursor returned by `encode`
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let (file, offset) = cursor.split_once('-')?;
+        Some(Self {
+            file: u64::from_str_radix(file, 16).ok()?,
+            offset: offset.parse().ok()?,
+        })
+    }
+}
+
+/// Log search parameters
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    pub filter: LogFilter,
+    /// Entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Entries before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Continue where a previous page ended
+    pub cursor: Option<LogCursor>,
+    /// Matching entries skipped before the page starts
+    pub skip: usize,
+    pub limit: usize,
+}
+
+/// One page of search results, newest first
+#[derive(Debug, Default)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Where the next page starts, None when the search is exhausted
+    pub next_cursor: Option<LogCursor>,
+}
+
+/// Something a log can be read from in any order
+trait LogReader: Read + Seek {}
+
+impl<T: Read + Seek> LogReader for T {}
+
+/// A log file opened for searching
+struct OpenLog {
+    reader: Box<dyn LogReader>,
+    /// Length of the (uncompressed) content
+    len: u64,
+    /// Identifies the file across renames and compression
+    key: u64,
+    /// Time of the first entry, if it has one
+    first_time: Option<DateTime<Utc>>,
+}
+
+/// Open a log file for searching
+///
+/// Compressed (`.gz`) files are decompressed into memory one at a time, as
+/// gzip streams cannot be read backwards.
+///
+/// # Returns
+/// None if the file is gone or empty
+fn open_log(path: &Path) -> Result<Option<OpenLog>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        // Rotated or removed since the files were listed
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {:?}", path)),
+    };
+
+    let (mut reader, len): (Box<dyn LogReader>, u64) =
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            let mut content = Vec::new();
+            flate2::read::GzDecoder::new(file)
+                .read_to_end(&mut content)
+                .with_context(|| format!("Failed to decompress {:?}", path))?;
+            let len = content.len() as u64;
+            (Box::new(std::io::Cursor::new(content)), len)
+        } else {
+            let len = file.metadata()?.len();
+            (Box::new(file), len)
+        };
+
+    Ok(
+        file_identity(&mut reader)?.map(|(key, first_time)| OpenLog {
+            reader,
+            len,
+            key,
+            first_time,
+        }),
+    )
+}
+
+/// Reads a file's lines from the end towards the start
+struct ReverseLines {
+    file: Box<dyn LogReader>,
+    /// Offset of `buf` in the file
+    pos: u64,
+    /// Bytes read but not returned yet
+    buf: Vec<u8>,
+}
+
+impl ReverseLines {
+    /// Read the lines before `end`
+    fn new(file: Box<dyn LogReader>, end: u64) -> Self {
+        Self {
+            file,
+            pos: end,
+            buf: Vec::new(),
+        }
+    }
+
+    /// End of the part of the file not returned yet
+    fn end(&self) -> u64 {
+        self.pos + self.buf.len() as u64
+    }
+
+    /// Next line towards the start of the file, with its offset
+    fn next_line(&mut self) -> std::io::Result<Option<(u64, Vec<u8>)>> {
+        loop {
+            // The newline terminating the last line is not part of it
+            let content = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+            if let Some(newline) = content.iter().rposition(|&b| b == b'\n') {
+                let line = content[newline + 1..].to_vec();
+                self.buf.truncate(newline + 1);
+                return Ok(Some((self.pos + newline as u64 + 1, line)));
+            }
+
+            if self.pos == 0 {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                let line = content.to_vec();
+                self.buf.clear();
+                return Ok(Some((0, line)));
+            }
+
+            let read = self.pos.min(CHUNK_SIZE as u64);
+            self.pos -= read;
+            let mut chunk = vec![0; read as usize];
+            self.file.seek(SeekFrom::Start(self.pos))?;
+            self.file.read_exact(&mut chunk)?;
+            chunk.extend_from_slice(&self.buf);
+            self.buf = chunk;
+        }
+    }
+}
+
+/// Identify a log file by its first line
+///
+/// # Returns
+/// None for an empty file, otherwise the file's key and the time of its
+/// first entry, if it has one
+fn file_identity(reader: &mut dyn LogReader) -> Result<Option<(u64, Option<DateTime<Utc>>)>> {
+    let mut first_line = Vec::new();
+    reader.seek(SeekFrom::Start(0))?;
+    BufReader::new(reader.take(MAX_FIRST_LINE)).read_until(b'\n', &mut first_line)?;
+    if first_line.is_empty() {
+        return Ok(None);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    first_line.hash(&mut hasher);
+    let time = serde_json::from_slice::<LogEntry>(&first_line)
+        .ok()
+        .and_then(|entry| entry_time(&entry));
+    Ok(Some((hasher.finish(), time)))
+}
+
+fn entry_time(entry: &LogEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Search log files for matching entries, newest first
+///
+/// Files are read backwards a chunk at a time and never loaded whole.
+/// Plugins may send their own timestamps, so entries within a file are not
+/// necessarily in time order; the search only stops early once it has read
+/// a whole file that starts before `since`, as older files hold nothing
+/// later.
+///
+/// # Arguments
+/// * `files` - Log files of one plugin, newest first
+/// * `query` - What to look for
+pub fn search(files: &[PathBuf], query: &LogQuery) -> Result<LogPage> {
+    let mut page = LogPage::default();
+    if query.limit == 0 {
+        return Ok(page);
+    }
+    let mut skip = query.skip;
+    let mut scanned = 0;
+    let mut cursor = query.cursor;
+
+    for path in files {
+        let Some(OpenLog {
+            reader,
+            len,
+            key,
+            first_time,
+        }) = open_log(path)?
+        else {
+            continue;
+        };
+
+        let end = match cursor {
+            Some(c) if c.file != key => continue,
+            Some(c) => {
+                cursor = None;
+                c.offset
+            }
+            None => len,
+        };
+        if let (Some(until), Some(first_time)) = (query.until, first_time) {
+            if first_time >= until {
+                continue;
+            }
+        }
+
+        let mut lines = ReverseLines::new(reader, end);
+        loop {
+            if scanned == MAX_SCANNED_LINES {
+                page.next_cursor = Some(LogCursor {
+                    file: key,
+                    offset: lines.end(),
+                });
+                return Ok(page);
+            }
+            let Some((_, line)) = lines.next_line()? else {
+                break;
+            };
+            scanned += 1;
+
+            let Ok(entry) = serde_json::from_slice::<LogEntry>(&line) else {
+                continue;
+            };
+            if query.since.is_some() || query.until.is_some() {
+                let Some(time) = entry_time(&entry) else {
+                    continue;
+                };
+                if query.since.is_some_and(|since| time < since)
+                    || query.until.is_some_and(|until| time >= until)
+                {
+                    continue;
+                }
+            }
+            if !query.filter.matches(&entry) {
+                continue;
+            }
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+
+            page.entries.push(entry);
+            if page.entries.len() == query.limit {
+                page.next_cursor = Some(LogCursor {
+                    file: key,
+                    offset: lines.end(),
+                });
+                return Ok(page);
+            }
+        }
+
+        if let (Some(since), Some(first_time)) = (query.since, first_time) {
+            if first_time < since {
+                break;
+            }
+        }
+    }
+
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::logging::{LogLevel, PluginLogger};
+
+    fn entry(message: &str, timestamp: &str) -> LogEntry {
+        let mut entry = LogEntry::new(LogLevel::Info, message).with_plugin("search");
+        entry.timestamp = timestamp.to_string();
+        entry
+    }
+
+    #[test]
+    fn test_reverse_lines_across_chunks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("lines");
+        let long = "x".repeat(CHUNK_SIZE + 10);
+        std::fs::write(&path, format!("first\n{}\n\nlast\n", long)).unwrap();
+
+        let open = |path: &PathBuf| -> Box<dyn LogReader> { Box::new(File::open(path).unwrap()) };
+        let mut lines = ReverseLines::new(open(&path), 0);
+        assert!(lines.next_line().unwrap().is_none());
+
+        let len = std::fs::metadata(&path).unwrap().len();
+        let mut lines = ReverseLines::new(open(&path), len);
+        let mut read = Vec::new();
+        while let Some((offset, line)) = lines.next_line().unwrap() {
+            read.push((offset, String::from_utf8(line).unwrap()));
+        }
+        let long_len = long.len() as u64;
+        assert_eq!(
+            read,
+            [
+                (long_len + 8, "last".to_string()),
+                (long_len + 7, String::new()),
+                (6, long),
+                (0, "first".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_across_rotated_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = PluginLogger::from_directory(temp_dir.path()).unwrap();
+        let current = logger.get_plugin_log_path("search");
+        let rotated = current.with_file_name("search-20250110-120000.log");
+
+        let mut older = String::new();
+        for i in 0..5 {
+            let line = entry(&format!("old {}", i), &format!("2025-01-10T10:0{}:00Z", i));
+            older.push_str(&format!("{}\n", line.to_json().unwrap()));
+        }
+        std::fs::write(&rotated, older).unwrap();
+        for i in 0..3 {
+            let line = entry(&format!("new {}", i), &format!("2025-01-10T13:0{}:00Z", i));
+            logger.log_plugin(line).await.unwrap();
+        }
+        // Another plugin whose ID starts with the same name
+        std::fs::write(
+            current.with_file_name("search-other.log"),
+            format!(
+                "{}\n",
+                entry("other", "2025-01-10T14:00:00Z").to_json().unwrap()
+            ),
+        )
+        .unwrap();
+
+        let files = logger.plugin_log_files("search");
+        assert_eq!(files, [current.clone(), rotated.clone()]);
+
+        let messages = |page: &LogPage| -> Vec<String> {
+            page.entries.iter().map(|e| e.message.clone()).collect()
+        };
+        let first = search(
+            &files,
+            &LogQuery {
+                limit: 4,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(messages(&first), ["new 2", "new 1", "new 0", "old 4"]);
+
+        // Appending and rotating does not move the cursor
+        logger
+            .log_plugin(entry("newest", "2025-01-10T13:30:00Z"))
+            .await
+            .unwrap();
+        std::fs::rename(
+            &current,
+            current.with_file_name("search-20250110-133000.log"),
+        )
+        .unwrap();
+        // Compressing the file the cursor points into does not either
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(rotated.with_extension("log.gz")).unwrap(),
+            flate2::Compression::default(),
+        );
+        std::io::copy(&mut File::open(&rotated).unwrap(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+        let files = logger.plugin_log_files("search");
+        let query = LogQuery {
+            cursor: first.next_cursor,
+            limit: 2,
+            ..Default::default()
+        };
+        let second = search(&files, &query).unwrap();
+        assert_eq!(messages(&second), ["old 3", "old 2"]);
+        let cursor = LogCursor::parse(&second.next_cursor.unwrap().encode());
+
+        // Time range and text filters
+        let filtered = search(
+            &files,
+            &LogQuery {
+                filter: LogFilter::new(None, Some("OLD"))
+                    .with_pattern(regex::Regex::new(r"[13]$").unwrap()),
+                since: Some("2025-01-10T10:01:00Z".parse().unwrap()),
+                until: Some("2025-01-10T13:00:00Z".parse().unwrap()),
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(messages(&filtered), ["old 3", "old 1"]);
+        assert!(filtered.next_cursor.is_none());
+
+        let rest = search(
+            &files,
+            &LogQuery {
+                cursor,
+                skip: 1,
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(messages(&rest), ["old 0"]);
+    }
+
+    #[tokio::test]
+    async fn test_search_past_backdated_entry() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = PluginLogger::from_directory(temp_dir.path()).unwrap();
+        let current = logger.get_plugin_log_path("search");
+        std::fs::write(
+            current.with_file_name("search-20250110-120000.log"),
+            format!(
+                "{}\n",
+                entry("rotated", "2025-01-10T10:00:00Z").to_json().unwrap()
+            ),
+        )
+        .unwrap();
+        for (message, timestamp) in [
+            ("early", "2025-01-10T13:00:00Z"),
+            ("in range", "2025-01-10T13:05:00Z"),
+            ("backdated", "2020-01-01T00:00:00Z"),
+            ("late", "2025-01-10T13:10:00Z"),
+        ] {
+            logger.log_plugin(entry(message, timestamp)).await.unwrap();
+        }
+
+        let page = search(
+            &logger.plugin_log_files("search"),
+            &LogQuery {
+                since: Some("2025-01-10T13:01:00Z".parse().unwrap()),
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let messages: Vec<_> = page.entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["late", "in range"]);
+    }
+}
@@ -0,0 +1,950 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use http_body_util::Full;
+use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket, UnixDatagram};
+use tokio::sync::mpsc;
+
+use super::logging::{LogEntry, LogLevel};
+
+/// Settings key holding the JSON array of log sinks
+pub const LOG_SINKS_SETTING: &str = "log_sinks";
+
+/// Default socket of the journald native protocol
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// How long one delivery attempt may take
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivery attempts of a batch before it is dropped
+const MAX_ATTEMPTS: u32 = 5;
+
+fn default_queue_size() -> usize {
+    10_000
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_flush_interval_ms() -> u64 {
+    1000
+}
+
+/// Which logger an entry was written by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    Plugin,
+    Supervisor,
+}
+
+impl LogSource {
+    /// Application name reported to syslog and journald
+    fn app_name(&self) -> &'static str {
+        match self {
+            LogSource::Plugin => "toru-plugin",
+            LogSource::Supervisor => "toru-supervisor",
+        }
+    }
+}
+
+/// A log entry on its way to the sinks
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardedEntry {
+    pub source: LogSource,
+    #[serde(flatten)]
+    pub entry: LogEntry,
+}
+
+/// Transport of a syslog sink
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogTransport {
+    Udp,
+    Tcp,
+    Unix,
+}
+
+/// Body format of an HTTP sink
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpFormat {
+    /// JSON array of entries
+    #[default]
+    Json,
+    /// Loki push API
+    Loki,
+    /// Elasticsearch bulk API
+    Elastic,
+}
+
+/// Where a sink delivers entries
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkKind {
+    /// RFC 5424 syslog
+    Syslog {
+        transport: SyslogTransport,
+        /// `host:port`, or a socket path for the unix transport
+        address: String,
+        /// Facility name (`user`, `daemon`, `local0`...) or number
+        #[serde(default)]
+        facility: Option<String>,
+    },
+    /// systemd journal, native protocol
+    Journald {
+        #[serde(default)]
+        socket: Option<PathBuf>,
+    },
+    /// Batches of JSON POSTed to a collector
+    Http {
+        /// `http://` URL the batches are POSTed to
+        url: String,
+        #[serde(default)]
+        format: HttpFormat,
+        /// Extra request headers, e.g. Authorization
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+/// A configured log sink
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SinkConfig {
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Lowest level forwarded, all levels if unset
+    #[serde(default)]
+    pub min_level: Option<String>,
+    /// Entries buffered while the sink is slow or down
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+    /// Most entries delivered at once
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// How long a batch waits to fill up
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+}
+
+impl SinkConfig {
+    /// Short description used in status reports and warnings
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            SinkKind::Syslog {
+                transport, address, ..
+            } => format!("syslog {:?} {}", transport, address).to_lowercase(),
+            SinkKind::Journald { socket } => format!(
+                "journald {}",
+                socket
+                    .as_deref()
+                    .unwrap_or(std::path::Path::new(JOURNALD_SOCKET))
+                    .display()
+            ),
+            SinkKind::Http { url, .. } => format!("http {}", url),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(level) = &self.min_level {
+            LogLevel::parse_level(level).ok_or_else(|| anyhow!("Unknown level {:?}", level))?;
+        }
+        if self.queue_size == 0 || self.batch_size == 0 {
+            bail!("queue_size and batch_size must be positive");
+        }
+        match &self.kind {
+            SinkKind::Syslog { facility, .. } => {
+                parse_facility(facility.as_deref())?;
+            }
+            SinkKind::Journald { .. } => {}
+            SinkKind::Http { url, headers, .. } => {
+                HttpTarget::parse(url)?;
+                for (name, value) in headers {
+                    hyper::header::HeaderName::from_bytes(name.as_bytes())
+                        .with_context(|| format!("Invalid header name {:?}", name))?;
+                    hyper::header::HeaderValue::from_str(value)
+                        .with_context(|| format!("Invalid value of header {:?}", name))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn build(&self) -> Result<Box<dyn LogSink>> {
+        Ok(match &self.kind {
+            SinkKind::Syslog {
+                transport,
+                address,
+                facility,
+            } => Box::new(SyslogSink {
+                transport: *transport,
+                address: address.clone(),
+                facility: parse_facility(facility.as_deref())?,
+                hostname: sysinfo::System::host_name().unwrap_or_else(|| "-".to_string()),
+                connection: None,
+            }),
+            SinkKind::Journald { socket } => Box::new(JournaldSink {
+                socket: socket
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(JOURNALD_SOCKET)),
+                connection: None,
+            }),
+            SinkKind::Http {
+                url,
+                format,
+                headers,
+            } => Box::new(HttpSink {
+                target: HttpTarget::parse(url)?,
+                format: *format,
+                headers: headers.clone(),
+                sender: None,
+            }),
+        })
+    }
+}
+
+/// Parse and validate the `log_sinks` setting
+pub fn parse_sinks(json: &str) -> Result<Vec<SinkConfig>> {
+    let sinks: Vec<SinkConfig> = serde_json::from_str(json).context("Invalid log sinks")?;
+    for sink in &sinks {
+        sink.validate()
+            .with_context(|| format!("Invalid log sink {}", sink.describe()))?;
+    }
+    Ok(sinks)
+}
+
+/// Syslog facility code from a name or number, `user` by default
+fn parse_facility(facility: Option<&str>) -> Result<u8> {
+    const NAMES: [&str; 16] = [
+        "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron",
+        "authpriv", "ftp", "ntp", "security", "console", "clock",
+    ];
+    let Some(facility) = facility else {
+        return Ok(1);
+    };
+    if let Some(code) = NAMES.iter().position(|name| *name == facility) {
+        return Ok(code as u8);
+    }
+    if let Some(local) = facility
+        .strip_prefix("local")
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| *n < 8)
+    {
+        return Ok(16 + local);
+    }
+    match facility.parse::<u8>() {
+        Ok(code) if code < 24 => Ok(code),
+        _ => bail!("Unknown syslog facility {:?}", facility),
+    }
+}
+
+/// Syslog severity of an entry
+fn severity(entry: &LogEntry) -> u8 {
+    match LogLevel::parse_level(&entry.level) {
+        Some(LogLevel::Error) => 3,
+        Some(LogLevel::Warn) => 4,
+        Some(LogLevel::Info) | None => 6,
+        Some(LogLevel::Debug) | Some(LogLevel::Trace) => 7,
+    }
+}
+
+fn entry_time(entry: &LogEntry) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Message text with the error appended, if any
+fn message_text(entry: &LogEntry) -> String {
+    match &entry.error {
+        Some(error) => format!("{}: {}", entry.message, error),
+        None => entry.message.clone(),
+    }
+}
+
+/// Format an entry as an RFC 5424 syslog message
+fn format_syslog(forwarded: &ForwardedEntry, facility: u8, hostname: &str) -> String {
+    let entry = &forwarded.entry;
+    // Header fields are printable ASCII without spaces, at most 32 chars
+    let field = |value: Option<&str>, max: usize| -> String {
+        let value: String = value
+            .unwrap_or("")
+            .chars()
+            .filter(|c| c.is_ascii_graphic())
+            .take(max)
+            .collect();
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value
+        }
+    };
+
+    format!(
+        "<{}>1 {} {} {} {} {} - {}",
+        facility * 8 + severity(entry),
+        entry_time(entry).to_rfc3339_opts(SecondsFormat::Micros, true),
+        field(Some(hostname), 255),
+        forwarded.source.app_name(),
+        field(entry.pid.map(|pid| pid.to_string()).as_deref(), 128),
+        field(entry.plugin.as_deref(), 32),
+        message_text(entry)
+    )
+}
+
+/// Encode an entry as a journald native protocol datagram
+fn format_journald(forwarded: &ForwardedEntry) -> Vec<u8> {
+    let entry = &forwarded.entry;
+    let mut datagram = Vec::new();
+    let mut field = |name: &str, value: &str| {
+        datagram.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Binary-safe form: name, newline, little-endian length, value
+            datagram.push(b'\n');
+            datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            datagram.push(b'=');
+        }
+        datagram.extend_from_slice(value.as_bytes());
+        datagram.push(b'\n');
+    };
+
+    field("MESSAGE", &entry.message);
+    field("PRIORITY", &severity(entry).to_string());
+    field("SYSLOG_IDENTIFIER", forwarded.source.app_name());
+    if let Some(plugin) = &entry.plugin {
+        field("TORU_PLUGIN", plugin);
+    }
+    if let Some(pid) = entry.pid {
+        field("TORU_PLUGIN_PID", &pid.to_string());
+    }
+    if let Some(stream) = &entry.stream {
+        field("TORU_STREAM", stream);
+    }
+    if let Some(error) = &entry.error {
+        field("TORU_ERROR", error);
+    }
+    field("TORU_TIMESTAMP", &entry.timestamp);
+    datagram
+}
+
+/// Request body for a batch sent to an HTTP sink
+///
+/// # Returns
+/// The content type and the body
+fn format_http(format: HttpFormat, batch: &[ForwardedEntry]) -> Result<(&'static str, Vec<u8>)> {
+    match format {
+        HttpFormat::Json => Ok(("application/json", serde_json::to_vec(batch)?)),
+        HttpFormat::Loki => {
+            // One stream per label set, values in batch order
+            let mut streams: BTreeMap<(LogSource, &str, &str), Vec<[String; 2]>> = BTreeMap::new();
+            for forwarded in batch {
+                let entry = &forwarded.entry;
+                let nanos = entry_time(entry).timestamp_nanos_opt().unwrap_or_default();
+                streams
+                    .entry((
+                        forwarded.source,
+                        entry.plugin.as_deref().unwrap_or(""),
+                        entry.level.as_str(),
+                    ))
+                    .or_default()
+                    .push([nanos.to_string(), serde_json::to_string(entry)?]);
+            }
+
+            let streams: Vec<serde_json::Value> = streams
+                .into_iter()
+                .map(|((source, plugin, level), values)| {
+                    let mut labels = serde_json::json!({
+                        "job": "toru",
+                        "source": source,
+                        "level": level.to_lowercase(),
+                    });
+                    if !plugin.is_empty() {
+                        labels["plugin"] = plugin.into();
+                    }
+                    serde_json::json!({ "stream": labels, "values": values })
+                })
+                .collect();
+            Ok((
+                "application/json",
+                serde_json::to_vec(&serde_json::json!({ "streams": streams }))?,
+            ))
+        }
+        HttpFormat::Elastic => {
+            let mut body = Vec::new();
+            for forwarded in batch {
+                let mut document = serde_json::to_value(forwarded)?;
+                document["@timestamp"] = forwarded.entry.timestamp.clone().into();
+                body.extend_from_slice(b"{\"create\":{}}\n");
+                serde_json::to_writer(&mut body, &document)?;
+                body.push(b'\n');
+            }
+            Ok(("application/x-ndjson", body))
+        }
+    }
+}
+
+/// Destination entries are delivered to
+#[async_trait::async_trait]
+trait LogSink: Send {
+    /// Deliver a batch; on error the whole batch is retried
+    async fn send(&mut self, batch: &[ForwardedEntry]) -> Result<()>;
+}
+
+enum SyslogConnection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Unix(UnixDatagram),
+}
+
+struct SyslogSink {
+    transport: SyslogTransport,
+    address: String,
+    facility: u8,
+    hostname: String,
+    connection: Option<SyslogConnection>,
+}
+
+impl SyslogSink {
+    async fn connect(&self) -> Result<SyslogConnection> {
+        Ok(match self.transport {
+            SyslogTransport::Udp => {
+                let target = tokio::net::lookup_host(&self.address)
+                    .await?
+                    .next()
+                    .ok_or_else(|| anyhow!("No address for {}", self.address))?;
+                let local = if target.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(local).await?;
+                socket.connect(target).await?;
+                SyslogConnection::Udp(socket)
+            }
+            SyslogTransport::Tcp => SyslogConnection::Tcp(TcpStream::connect(&self.address).await?),
+            SyslogTransport::Unix => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(&self.address)?;
+                SyslogConnection::Unix(socket)
+            }
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl LogSink for SyslogSink {
+    async fn send(&mut self, batch: &[ForwardedEntry]) -> Result<()> {
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => self.connect().await?,
+        };
+
+        for forwarded in batch {
+            let message = format_syslog(forwarded, self.facility, &self.hostname);
+            match &mut connection {
+                SyslogConnection::Udp(socket) => {
+                    socket.send(message.as_bytes()).await?;
+                }
+                SyslogConnection::Unix(socket) => {
+                    socket.send(message.as_bytes()).await?;
+                }
+                // Octet counting framing (RFC 6587)
+                SyslogConnection::Tcp(stream) => {
+                    let framed = format!("{} {}", message.len(), message);
+                    stream.write_all(framed.as_bytes()).await?;
+                }
+            }
+        }
+
+        // A failed connection is dropped above and reopened next time
+        self.connection = Some(connection);
+        Ok(())
+    }
+}
+
+struct JournaldSink {
+    socket: PathBuf,
+    connection: Option<UnixDatagram>,
+}
+
+#[async_trait::async_trait]
+impl LogSink for JournaldSink {
+    async fn send(&mut self, batch: &[ForwardedEntry]) -> Result<()> {
+        let socket = match self.connection.take() {
+            Some(socket) => socket,
+            None => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(&self.socket)?;
+                socket
+            }
+        };
+        for forwarded in batch {
+            socket.send(&format_journald(forwarded)).await?;
+        }
+        self.connection = Some(socket);
+        Ok(())
+    }
+}
+
+/// Where an HTTP sink connects to
+struct HttpTarget {
+    uri: hyper::Uri,
+    /// `host:port` to connect to
+    authority: String,
+}
+
+impl HttpTarget {
+    fn parse(url: &str) -> Result<Self> {
+        let uri: hyper::Uri = url
+            .parse()
+            .with_context(|| format!("Invalid URL {:?}", url))?;
+        if uri.scheme_str() != Some("http") {
+            bail!("Only http:// URLs are supported, forward to a local collector for TLS");
+        }
+        let host = uri
+            .host()
+            .ok_or_else(|| anyhow!("URL {:?} has no host", url))?;
+        let authority = format!("{}:{}", host, uri.port_u16().unwrap_or(80));
+        Ok(Self { uri, authority })
+    }
+}
+
+struct HttpSink {
+    target: HttpTarget,
+    format: HttpFormat,
+    headers: BTreeMap<String, String>,
+    /// Kept-alive connection to the collector
+    sender: Option<hyper::client::conn::http1::SendRequest<Full<Bytes>>>,
+}
+
+impl HttpSink {
+    async fn connect(&self) -> Result<hyper::client::conn::http1::SendRequest<Full<Bytes>>> {
+        let stream = TcpStream::connect(&self.target.authority).await?;
+        let (sender, connection) =
+            hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(stream)).await?;
+        tokio::spawn(connection);
+        Ok(sender)
+    }
+}
+
+#[async_trait::async_trait]
+impl LogSink for HttpSink {
+    async fn send(&mut self, batch: &[ForwardedEntry]) -> Result<()> {
+        let (content_type, body) = format_http(self.format, batch)?;
+
+        let mut sender = match self.sender.take() {
+            Some(sender) if !sender.is_closed() => sender,
+            _ => self.connect().await?,
+        };
+        sender.ready().await?;
+
+        let mut request = hyper::Request::post(self.target.uri.clone())
+            .header(hyper::header::HOST, self.target.authority.as_str())
+            .header(hyper::header::CONTENT_TYPE, content_type);
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = sender
+            .send_request(request.body(Full::new(Bytes::from(body)))?)
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Collector answered {}", status);
+        }
+        self.sender = Some(sender);
+        Ok(())
+    }
+}
+
+/// Delivery state of a sink
+#[derive(Debug, Clone, Serialize)]
+pub struct SinkStatus {
+    pub sink: String,
+    /// Entries waiting to be delivered
+    pub queued: usize,
+    /// Entries dropped since the sink was configured
+    pub dropped: u64,
+    /// Error of the last failed delivery, cleared when one succeeds
+    pub last_error: Option<String>,
+}
+
+/// Counters shared by a sink's queue and its delivery task
+#[derive(Debug, Default)]
+struct SinkState {
+    /// Dropped entries not yet reported to the sink itself
+    unreported: AtomicU64,
+    dropped: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+struct SinkHandle {
+    config: SinkConfig,
+    min_severity: u8,
+    queue: mpsc::Sender<ForwardedEntry>,
+    state: Arc<SinkState>,
+}
+
+/// Forwards log entries to the configured sinks
+///
+/// Every sink has a bounded queue drained by its own task. When a sink is
+/// slow or down its queue fills up and further entries are dropped and
+/// counted, so writing a log entry never waits for a sink.
+#[derive(Default)]
+pub struct LogForwarder {
+    sinks: Mutex<Vec<SinkHandle>>,
+}
+
+impl std::fmt::Debug for LogForwarder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogForwarder")
+            .field("sinks", &self.status())
+            .finish()
+    }
+}
+
+impl LogForwarder {
+    /// Replace the configured sinks
+    ///
+    /// Sinks whose configuration is unchanged keep their queue. Removed
+    /// sinks deliver what they have queued, then stop. If a new sink is
+    /// invalid or fails to start, the current sinks are left in place.
+    pub fn configure(&self, configs: Vec<SinkConfig>) -> Result<()> {
+        enum Planned {
+            Kept(usize),
+            Started(SinkHandle),
+        }
+
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        let mut kept = vec![false; sinks.len()];
+        let mut planned = Vec::with_capacity(configs.len());
+        for config in configs {
+            let existing = (0..sinks.len()).find(|&i| !kept[i] && sinks[i].config == config);
+            if let Some(index) = existing {
+                kept[index] = true;
+                planned.push(Planned::Kept(index));
+                continue;
+            }
+            // Sinks started so far stop when dropped on error
+            config.validate()?;
+            planned.push(Planned::Started(spawn_sink(config)?));
+        }
+
+        let mut current: Vec<Option<SinkHandle>> =
+            std::mem::take(&mut *sinks).into_iter().map(Some).collect();
+        *sinks = planned
+            .into_iter()
+            .filter_map(|planned| match planned {
+                Planned::Kept(index) => current[index].take(),
+                Planned::Started(sink) => Some(sink),
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Queue an entry for every sink that takes its level
+    pub fn forward(&self, source: LogSource, entry: &LogEntry) {
+        let sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        if sinks.is_empty() {
+            return;
+        }
+        let entry_severity = LogLevel::parse_level(&entry.level)
+            .map(|level| level.severity())
+            .unwrap_or(u8::MAX);
+
+        for sink in sinks.iter() {
+            if entry_severity < sink.min_severity {
+                continue;
+            }
+            let forwarded = ForwardedEntry {
+                source,
+                entry: entry.clone(),
+            };
+            if sink.queue.try_send(forwarded).is_err() {
+                sink.state.dropped.fetch_add(1, Ordering::Relaxed);
+                sink.state.unreported.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Delivery state of every sink
+    pub fn status(&self) -> Vec<SinkStatus> {
+        let sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        sinks
+            .iter()
+            .map(|sink| SinkStatus {
+                sink: sink.config.describe(),
+                queued: sink.config.queue_size - sink.queue.capacity(),
+                dropped: sink.state.dropped.load(Ordering::Relaxed),
+                last_error: sink
+                    .state
+                    .last_error
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone(),
+            })
+            .collect()
+    }
+}
+
+/// Start the delivery task of a sink
+fn spawn_sink(config: SinkConfig) -> Result<SinkHandle> {
+    let sink = config.build()?;
+    let (queue, entries) = mpsc::channel(config.queue_size);
+    let state = Arc::new(SinkState::default());
+    let min_severity = config
+        .min_level
+        .as_deref()
+        .and_then(LogLevel::parse_level)
+        .map(|level| level.severity())
+        .unwrap_or(0);
+
+    tokio::spawn(deliver(
+        sink,
+        entries,
+        config.batch_size,
+        Duration::from_millis(config.flush_interval_ms),
+        config.describe(),
+        Arc::clone(&state),
+    ));
+
+    Ok(SinkHandle {
+        config,
+        min_severity,
+        queue,
+        state,
+    })
+}
+
+/// Deliver queued entries in batches until the queue is closed
+///
+/// A failed batch is retried with exponential backoff; while it is, new
+/// entries pile up in the queue and are dropped once it is full.
+async fn deliver(
+    mut sink: Box<dyn LogSink>,
+    mut entries: mpsc::Receiver<ForwardedEntry>,
+    batch_size: usize,
+    flush_interval: Duration,
+    name: String,
+    state: Arc<SinkState>,
+) {
+    while let Some(first) = entries.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + flush_interval;
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, entries.recv()).await {
+                Ok(Some(entry)) => batch.push(entry),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let dropped = state.unreported.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            let notice = LogEntry::new(
+                LogLevel::Warn,
+                &format!("Dropped {} log entries (sink queue full)", dropped),
+            );
+            batch.push(ForwardedEntry {
+                source: LogSource::Supervisor,
+                entry: notice,
+            });
+        }
+
+        let mut backoff = Duration::from_secs(1);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let result = match tokio::time::timeout(SEND_TIMEOUT, sink.send(&batch)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!("Timed out")),
+            };
+            let delivered = {
+                let mut last_error = state.last_error.lock().unwrap_or_else(|e| e.into_inner());
+                match result {
+                    Ok(()) => {
+                        *last_error = None;
+                        true
+                    }
+                    Err(e) => {
+                        if last_error.is_none() {
+                            tracing::warn!("Failed to forward logs to {}: {:#}", name, e);
+                        }
+                        *last_error = Some(format!("{:#}", e));
+                        false
+                    }
+                }
+            };
+            if delivered {
+                break;
+            }
+
+            if attempt == MAX_ATTEMPTS {
+                state
+                    .dropped
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+                state
+                    .unreported
+                    .fetch_add(batch.len() as u64, Ordering::Relaxed);
+            } else {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(30));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(level: LogLevel, message: &str) -> ForwardedEntry {
+        let mut entry = LogEntry::new(level, message).with_plugin("weather");
+        entry.timestamp = "2025-01-10T12:00:00.123456789Z".to_string();
+        entry.pid = Some(42);
+        ForwardedEntry {
+            source: LogSource::Plugin,
+            entry,
+        }
+    }
+
+    #[test]
+    fn test_parse_sinks() {
+        let sinks = parse_sinks(
+            r#"[
+                {"type": "syslog", "transport": "udp", "address": "127.0.0.1:514", "facility": "local3"},
+                {"type": "journald", "min_level": "warn"},
+                {"type": "http", "url": "http://loki:3100/loki/api/v1/push", "format": "loki", "batch_size": 500}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(sinks.len(), 3);
+        assert_eq!(sinks[1].queue_size, 10_000);
+        assert_eq!(sinks[2].batch_size, 500);
+        assert_eq!(parse_facility(Some("local3")).unwrap(), 19);
+
+        assert!(parse_sinks(r#"[{"type": "http", "url": "https://elastic:9200/_bulk"}]"#).is_err());
+        assert!(parse_sinks(
+            r#"[{"type": "syslog", "transport": "tcp", "address": "x:514", "facility": "nope"}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_formats() {
+        let entry = forwarded(LogLevel::Warn, "disk low");
+        assert_eq!(
+            format_syslog(&entry, 16, "host one"),
+            "<132>1 2025-01-10T12:00:00.123456Z hostone toru-plugin 42 weather - disk low"
+        );
+
+        let multiline = forwarded(LogLevel::Error, "line 1\nline 2");
+        let datagram = format_journald(&multiline);
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&13u64.to_le_bytes());
+        expected.extend_from_slice(b"line 1\nline 2\nPRIORITY=3\n");
+        assert!(datagram.starts_with(&expected));
+
+        let (_, body) = format_http(HttpFormat::Loki, &[entry.clone(), entry]).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["streams"][0]["stream"]["plugin"], "weather");
+        assert_eq!(body["streams"][0]["stream"]["level"], "warn");
+        assert_eq!(body["streams"][0]["values"][1][0], "1736510400123456789");
+    }
+
+    #[tokio::test]
+    async fn test_forward_to_syslog_and_http() {
+        let syslog = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (bodies_tx, mut bodies) = mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/push",
+            axum::routing::post(move |body: String| async move {
+                bodies_tx.send(body).unwrap();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http_address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let forwarder = LogForwarder::default();
+        let sinks = format!(
+            r#"[
+                {{"type": "syslog", "transport": "udp", "address": "{}", "min_level": "info"}},
+                {{"type": "http", "url": "http://{}/push", "flush_interval_ms": 50}}
+            ]"#,
+            syslog.local_addr().unwrap(),
+            http_address
+        );
+        forwarder.configure(parse_sinks(&sinks).unwrap()).unwrap();
+
+        forwarder.forward(
+            LogSource::Plugin,
+            &forwarded(LogLevel::Debug, "verbose").entry,
+        );
+        forwarder.forward(LogSource::Plugin, &forwarded(LogLevel::Info, "hello").entry);
+
+        let mut datagram = vec![0; 2048];
+        let len = syslog.recv(&mut datagram).await.unwrap();
+        let message = String::from_utf8_lossy(&datagram[..len]);
+        assert!(message.starts_with("<14>1 "), "{}", message);
+        assert!(message.ends_with(" weather - hello"), "{}", message);
+
+        let body: serde_json::Value = serde_json::from_str(&bodies.recv().await.unwrap()).unwrap();
+        let messages: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(messages, ["verbose", "hello"]);
+        assert_eq!(body[0]["source"], "plugin");
+        assert!(forwarder.status().iter().all(|s| s.dropped == 0));
+
+        // An invalid sink leaves the working ones in place
+        let invalid = format!(
+            r#"[{{"type": "http", "url": "http://{}/push", "flush_interval_ms": 50}}]"#,
+            http_address
+        );
+        let mut configs = parse_sinks(&invalid).unwrap();
+        configs[0].queue_size = 0;
+        assert!(forwarder.configure(configs).is_err());
+        assert_eq!(forwarder.status().len(), 2);
+        forwarder.forward(LogSource::Plugin, &forwarded(LogLevel::Info, "after").entry);
+        let len = syslog.recv(&mut datagram).await.unwrap();
+        assert!(String::from_utf8_lossy(&datagram[..len]).ends_with(" weather - after"));
+    }
+
+    #[tokio::test]
+    async fn test_dead_sink_drops_instead_of_blocking() {
+        // Nothing listens on this port
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let forwarder = LogForwarder::default();
+        let sinks = format!(
+            r#"[{{"type": "http", "url": "http://{}/", "queue_size": 4, "batch_size": 2, "flush_interval_ms": 10}}]"#,
+            address
+        );
+        forwarder.configure(parse_sinks(&sinks).unwrap()).unwrap();
+
+        for i in 0..100 {
+            forwarder.forward(
+                LogSource::Supervisor,
+                &LogEntry::new(LogLevel::Info, &i.to_string()),
+            );
+        }
+        let status = &forwarder.status()[0];
+        assert!(status.dropped >= 94, "dropped {}", status.dropped);
+        assert!(status.queued <= 4);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(forwarder.status()[0].last_error.is_some());
+    }
+}
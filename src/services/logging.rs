@@ -1,11 +1,20 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use super::log_rotation::{self, LogRetention};
+use super::log_search::{self, LogPage, LogQuery};
+use super::log_sinks::{LogForwarder, LogSource};
 
 /// Log levels for plugin and supervisor logging
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -54,6 +63,9 @@ pub struct LogEntry {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pid: Option<u32>,
+    /// Output stream the entry was captured from ("stdout" or "stderr")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<String>,
 }
 
 impl LogEntry {
@@ -66,6 +78,7 @@ impl LogEntry {
             plugin: None,
             error: None,
             pid: None,
+            stream: None,
         }
     }
 
@@ -91,29 +104,284 @@ impl LogEntry {
         self
     }
 
+    /// Set the output stream the entry was captured from
+    pub fn with_stream(mut self, stream: LogStream) -> Self {
+        self.stream = Some(stream.as_str().to_string());
+        self
+    }
+
     /// Serialize to JSON string
     pub fn to_json(&self) -> Result<String> {
         serde_json::to_string(self).context("Failed to serialize log entry")
     }
 }
 
+/// Server-side filter applied to log entries
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Lowest level kept
+    min_level: Option<LogLevel>,
+    /// Lowercased text the message or error must contain
+    contains: Option<String>,
+    /// Pattern the message or error must match
+    pattern: Option<Regex>,
+}
+
+impl LogFilter {
+    /// Create a filter
+    ///
+    /// # Arguments
+    /// * `min_level` - Lowest level kept, None for all levels
+    /// * `contains` - Text the message or error must contain, ignoring case
+    pub fn new(min_level: Option<LogLevel>, contains: Option<&str>) -> Self {
+        Self {
+            min_level,
+            contains: contains
+                .filter(|text| !text.is_empty())
+                .map(str::to_lowercase),
+            pattern: None,
+        }
+    }
+
+    /// Also require the message or error to match a regular expression
+    pub fn with_pattern(mut self, pattern: Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Whether an entry passes the filter
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(min_level) = &self.min_level {
+            match LogLevel::parse_level(&entry.level) {
+                Some(level) if level.severity() >= min_level.severity() => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(text) = &self.contains {
+            let contains = |field: &str| field.to_lowercase().contains(text);
+            if !contains(&entry.message) && !entry.error.as_deref().is_some_and(contains) {
+                return false;
+            }
+        }
+
+        match &self.pattern {
+            Some(pattern) => {
+                pattern.is_match(&entry.message)
+                    || entry
+                        .error
+                        .as_deref()
+                        .is_some_and(|error| pattern.is_match(error))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Entries buffered per live-tail subscriber before it starts skipping
+const LIVE_TAIL_CAPACITY: usize = 1024;
+
 /// Log configuration
 #[derive(Debug, Clone)]
 pub struct LogConfig {
-    /// Maximum size of a log file before rotation (in bytes)
-    pub max_file_size: u64,
-    /// Maximum number of rotated log files to keep
-    pub max_rotated_files: usize,
+    /// Rotation and retention of log files, until settings are loaded
+    pub retention: LogRetention,
     /// Base directory for logs
     pub log_dir: PathBuf,
+    /// Maximum length of a captured output line (longer lines are truncated)
+    pub max_line_length: usize,
+    /// Sustained number of output lines per second accepted from a plugin
+    pub rate_limit_per_sec: u32,
+    /// Number of lines a plugin may emit in a burst above the sustained rate
+    pub rate_limit_burst: u32,
+    /// Log output accepted per plugin and day (in bytes), unless the
+    /// plugin has its own quota
+    pub max_bytes_per_day: u64,
 }
 
 impl Default for LogConfig {
     fn default() -> Self {
         Self {
-            max_file_size: 10 * 1024 * 1024, // 10 MB
-            max_rotated_files: 5,
+            retention: LogRetention::default(),
             log_dir: PathBuf::from("/var/log/toru"),
+            max_line_length: 16 * 1024,
+            rate_limit_per_sec: 200,
+            rate_limit_burst: 1000,
+            max_bytes_per_day: crate::db::PluginQuota::default().log_max_bytes_per_day,
+        }
+    }
+}
+
+/// Plugin output stream captured by the core
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Structured log line emitted by a plugin
+///
+/// Only the content fields are taken from the plugin; identity fields
+/// (plugin, pid, stream) are always set by the core.
+#[derive(Debug, Deserialize)]
+struct PluginLogLine {
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(alias = "msg")]
+    message: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Token bucket limiting how many output lines a plugin may log
+#[derive(Debug)]
+struct RateLimiter {
+    tokens: f64,
+    last_refill: Instant,
+    /// Lines dropped since the last drop notice was written
+    dropped: u64,
+}
+
+impl RateLimiter {
+    fn new(burst: u32) -> Self {
+        Self {
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    /// Take a token, returning false (and counting the line) if none are left
+    fn try_acquire(&mut self, rate_per_sec: u32, burst: u32) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate_per_sec as f64).min(burst as f64);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+/// Log output accepted from a plugin on the current day
+#[derive(Debug, Default)]
+struct LogVolume {
+    /// Daily quota in bytes, None for the logger default
+    quota: Option<u64>,
+    day: Option<chrono::NaiveDate>,
+    bytes: u64,
+    /// Whether the quota notice was written today
+    notified: bool,
+}
+
+impl LogVolume {
+    /// Start counting from zero when the day changed
+    fn roll_over(&mut self, today: chrono::NaiveDate) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.bytes = 0;
+            self.notified = false;
+        }
+    }
+}
+
+/// Parse one captured output line into a log entry
+///
+/// JSON objects with a `message` field are treated as structured entries;
+/// anything else is logged verbatim at Info level.
+///
+/// # Arguments
+/// * `plugin_id` - Plugin the line was captured from
+/// * `pid` - Process the line was captured from
+/// * `stream` - Stream the line was read from
+/// * `line` - The line without its trailing newline
+pub fn parse_output_line(
+    plugin_id: &str,
+    pid: Option<u32>,
+    stream: LogStream,
+    line: &str,
+) -> LogEntry {
+    let structured = if line.trim_start().starts_with('{') {
+        serde_json::from_str::<PluginLogLine>(line).ok()
+    } else {
+        None
+    };
+
+    let mut entry = match structured {
+        Some(parsed) => {
+            let level = parsed
+                .level
+                .as_deref()
+                .and_then(LogLevel::parse_level)
+                .unwrap_or(LogLevel::Info);
+            let mut entry = LogEntry::new(level, &parsed.message);
+            if let Some(timestamp) = parsed
+                .timestamp
+                .filter(|t| chrono::DateTime::parse_from_rfc3339(t).is_ok())
+            {
+                entry.timestamp = timestamp;
+            }
+            entry.error = parsed.error;
+            entry
+        }
+        None => LogEntry::new(LogLevel::Info, line.trim_end()),
+    };
+
+    entry = entry.with_plugin(plugin_id).with_stream(stream);
+    entry.pid = pid;
+    entry
+}
+
+/// Read one newline-terminated line, keeping at most `max_len` bytes of it
+///
+/// # Returns
+/// None at EOF, otherwise whether the line was truncated
+async fn read_line_capped<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+    line: &mut Vec<u8>,
+    max_len: usize,
+) -> std::io::Result<Option<bool>> {
+    line.clear();
+    let mut truncated = false;
+    let mut read_any = false;
+
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(if read_any { Some(truncated) } else { None });
+        }
+        read_any = true;
+
+        let (chunk, consumed, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(pos) => (&available[..pos], pos + 1, true),
+            None => (available, available.len(), false),
+        };
+
+        let room = max_len.saturating_sub(line.len());
+        if chunk.len() > room {
+            truncated = true;
+        }
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        reader.consume(consumed);
+
+        if done {
+            return Ok(Some(truncated));
         }
     }
 }
@@ -126,6 +394,16 @@ pub struct PluginLogger {
     // TODO: Integrate file handle caching for improved performance
     #[allow(dead_code)]
     log_files: Arc<Mutex<std::collections::HashMap<String, PathBuf>>>,
+    // Output rate limits, shared by all streams of a plugin
+    rate_limits: std::sync::Mutex<std::collections::HashMap<String, RateLimiter>>,
+    // Daily log volume per plugin
+    volumes: std::sync::Mutex<std::collections::HashMap<String, LogVolume>>,
+    // Live-tail channels of plugins someone is watching
+    live: std::sync::Mutex<std::collections::HashMap<String, broadcast::Sender<LogEntry>>>,
+    // Current rotation and retention policy
+    retention: std::sync::Mutex<LogRetention>,
+    // Sinks entries are forwarded to after being written
+    forwarder: Arc<LogForwarder>,
 }
 
 impl PluginLogger {
@@ -139,11 +417,22 @@ impl PluginLogger {
         fs::create_dir_all(&plugins_log_dir).context("Failed to create plugins log directory")?;
 
         Ok(Self {
+            retention: std::sync::Mutex::new(config.retention.clone()),
             config,
             log_files: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            rate_limits: std::sync::Mutex::new(std::collections::HashMap::new()),
+            volumes: std::sync::Mutex::new(std::collections::HashMap::new()),
+            live: std::sync::Mutex::new(std::collections::HashMap::new()),
+            forwarder: Arc::new(LogForwarder::default()),
         })
     }
 
+    /// Forward written entries through a shared forwarder
+    pub fn with_forwarder(mut self, forwarder: Arc<LogForwarder>) -> Self {
+        self.forwarder = forwarder;
+        self
+    }
+
     /// Initialize logger with default config
     // TODO: Use in alternative initialization paths
     #[allow(dead_code)]
@@ -170,6 +459,50 @@ impl PluginLogger {
             .join(format!("{}.log", plugin_id))
     }
 
+    /// Current and rotated log files of a plugin, newest first
+    pub fn plugin_log_files(&self, plugin_id: &str) -> Vec<PathBuf> {
+        let current = self.get_plugin_log_path(plugin_id);
+        let rotated = log_rotation::rotated_logs(&current);
+
+        let mut files: Vec<PathBuf> = current.exists().then_some(current).into_iter().collect();
+        files.extend(rotated.into_iter().map(|log| log.path));
+        files
+    }
+
+    /// Replace the rotation and retention policy
+    pub fn set_retention(&self, retention: LogRetention) {
+        *self.retention.lock().unwrap_or_else(|e| e.into_inner()) = retention;
+    }
+
+    fn retention(&self) -> LogRetention {
+        self.retention
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Delete rotated plugin logs the retention policy no longer keeps
+    ///
+    /// Covers logs of removed plugins too, so their files age out as well.
+    ///
+    /// # Returns
+    /// The number of files deleted
+    pub async fn prune_rotated_logs(&self) -> Result<usize> {
+        let dir = self.config.log_dir.join("plugins");
+        let retention = self.retention();
+        tokio::task::spawn_blocking(move || {
+            let now = Utc::now();
+            let mut removed = 0;
+            for stem in log_rotation::rotated_stems(&dir) {
+                removed +=
+                    log_rotation::prune(&dir.join(format!("{}.log", stem)), &retention, now)?;
+            }
+            Ok(removed)
+        })
+        .await
+        .context("Log pruning task failed")?
+    }
+
     /// Write a log entry to a plugin's log file
     pub async fn log_plugin(&self, entry: LogEntry) -> Result<()> {
         let plugin_id = entry
@@ -193,132 +526,257 @@ impl PluginLogger {
         let json = entry.to_json()?;
         writeln!(file, "{}", json).context("Failed to write log entry")?;
 
+        self.forwarder.forward(LogSource::Plugin, &entry);
+        self.publish(&plugin_id, entry);
         Ok(())
     }
 
-    /// Read logs for a plugin with optional filtering and pagination
-    pub async fn read_plugin_logs(
-        &self,
-        plugin_id: &str,
-        filter_level: Option<LogLevel>,
-        page: usize,
-        page_size: usize,
-    ) -> Result<Vec<LogEntry>> {
-        let log_path = self.get_plugin_log_path(plugin_id);
+    /// Follow the entries written to a plugin's log from now on
+    ///
+    /// A subscriber that falls more than a buffer behind gets
+    /// `RecvError::Lagged` with the number of entries it missed.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin whose log to follow
+    pub fn subscribe(&self, plugin_id: &str) -> broadcast::Receiver<LogEntry> {
+        let mut live = self.live.lock().unwrap_or_else(|e| e.into_inner());
+        live.entry(plugin_id.to_string())
+            .or_insert_with(|| broadcast::channel(LIVE_TAIL_CAPACITY).0)
+            .subscribe()
+    }
 
-        if !log_path.exists() {
-            return Ok(Vec::new());
+    /// Hand a written entry to the plugin's live-tail subscribers
+    fn publish(&self, plugin_id: &str, entry: LogEntry) {
+        let mut live = self.live.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = live.get(plugin_id) {
+            if sender.send(entry).is_err() {
+                // Every subscriber is gone
+                live.remove(plugin_id);
+            }
         }
+    }
 
-        let content = fs::read_to_string(&log_path).context("Failed to read log file")?;
-
-        // Parse all log entries
-        let mut logs: Vec<LogEntry> = content
-            .lines()
-            .filter_map(|line| serde_json::from_str::<LogEntry>(line).ok())
-            .collect();
+    /// Capture a plugin output stream line by line into the plugin log
+    ///
+    /// The stream is always drained, even while lines are being dropped by
+    /// the rate limit, so the plugin never blocks on a full pipe.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin owning the stream
+    /// * `pid` - Process the stream belongs to
+    /// * `stream` - Which stream is being captured
+    /// * `reader` - The pipe to read from
+    ///
+    /// # Returns
+    /// Handle of the capture task, which ends at EOF
+    pub fn capture_output<R>(
+        self: &Arc<Self>,
+        plugin_id: &str,
+        pid: Option<u32>,
+        stream: LogStream,
+        reader: R,
+    ) -> JoinHandle<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let logger = Arc::clone(self);
+        let plugin_id = plugin_id.to_string();
+
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(reader);
+            let mut line = Vec::new();
+
+            loop {
+                let truncated =
+                    match read_line_capped(&mut reader, &mut line, logger.config.max_line_length)
+                        .await
+                    {
+                        Ok(Some(truncated)) => truncated,
+                        Ok(None) | Err(_) => break,
+                    };
+
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches('\r');
+                if text.trim().is_empty() {
+                    continue;
+                }
 
-        // Filter by log level if specified
-        if let Some(level) = filter_level {
-            let min_severity = level.severity();
-            logs.retain(|entry| {
-                if let Some(entry_level) = LogLevel::parse_level(&entry.level) {
-                    entry_level.severity() >= min_severity
+                if let Some(dropped) = logger.acquire_line(&plugin_id) {
+                    if dropped > 0 {
+                        logger.log_dropped_lines(&plugin_id, pid, dropped).await;
+                    }
                 } else {
-                    false
+                    continue;
                 }
-            });
-        }
 
-        // Reverse to show newest first
-        logs.reverse();
+                if !logger
+                    .within_log_quota(&plugin_id, pid, text.len() as u64)
+                    .await
+                {
+                    continue;
+                }
 
-        // Apply pagination
-        let start = page * page_size;
-        let end = start + page_size;
-        if start < logs.len() {
-            logs.truncate(end);
-            Ok(logs[start..].to_vec())
-        } else {
-            Ok(Vec::new())
-        }
+                let mut entry = parse_output_line(&plugin_id, pid, stream, text);
+                if truncated {
+                    entry.message.push_str(" [truncated]");
+                }
+                let _ = logger.log_plugin(entry).await;
+            }
+
+            // Report lines dropped since the last accepted one
+            let dropped = logger.take_dropped(&plugin_id);
+            if dropped > 0 {
+                logger.log_dropped_lines(&plugin_id, pid, dropped).await;
+            }
+        })
     }
 
-    /// Check if log file needs rotation and rotate if necessary
-    async fn check_and_rotate(&self, log_path: &Path) -> Result<()> {
-        if !log_path.exists() {
-            return Ok(());
+    /// Apply the rate limit to one output line
+    ///
+    /// # Returns
+    /// None if the line must be dropped, otherwise the number of lines
+    /// dropped before it that have not been reported yet
+    fn acquire_line(&self, plugin_id: &str) -> Option<u64> {
+        let mut limits = self.rate_limits.lock().unwrap_or_else(|e| e.into_inner());
+        let limiter = limits
+            .entry(plugin_id.to_string())
+            .or_insert_with(|| RateLimiter::new(self.config.rate_limit_burst));
+
+        if limiter.try_acquire(self.config.rate_limit_per_sec, self.config.rate_limit_burst) {
+            Some(std::mem::take(&mut limiter.dropped))
+        } else {
+            None
         }
+    }
 
-        let metadata = fs::metadata(log_path)?;
-        if metadata.len() >= self.config.max_file_size {
-            self.rotate_log(log_path)?;
-        }
+    /// Take the number of dropped lines not reported yet
+    fn take_dropped(&self, plugin_id: &str) -> u64 {
+        let mut limits = self.rate_limits.lock().unwrap_or_else(|e| e.into_inner());
+        limits
+            .get_mut(plugin_id)
+            .map(|limiter| std::mem::take(&mut limiter.dropped))
+            .unwrap_or(0)
+    }
 
-        Ok(())
+    /// Write a notice about lines dropped by the rate limit
+    async fn log_dropped_lines(&self, plugin_id: &str, pid: Option<u32>, dropped: u64) {
+        let mut entry = LogEntry::new(
+            LogLevel::Warn,
+            &format!("Dropped {} output lines (log rate limit exceeded)", dropped),
+        )
+        .with_plugin(plugin_id);
+        entry.pid = pid;
+        let _ = self.log_plugin(entry).await;
     }
 
-    /// Rotate a log file (rename with timestamp, create new)
-    fn rotate_log(&self, log_path: &Path) -> Result<()> {
-        // Get current timestamp for rotation
-        let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+    /// Set the daily log quota of a plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin the quota applies to
+    /// * `bytes` - Log output accepted per day
+    pub fn set_log_quota(&self, plugin_id: &str, bytes: u64) {
+        let mut volumes = self.volumes.lock().unwrap_or_else(|e| e.into_inner());
+        volumes.entry(plugin_id.to_string()).or_default().quota = Some(bytes);
+    }
 
-        // Construct rotated filename
-        let stem = log_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("log");
-        let ext = log_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("log");
-        let parent = log_path.parent().unwrap_or_else(|| Path::new("."));
+    /// Bytes of log output accepted from a plugin today
+    pub fn log_usage(&self, plugin_id: &str) -> u64 {
+        let mut volumes = self.volumes.lock().unwrap_or_else(|e| e.into_inner());
+        match volumes.get_mut(plugin_id) {
+            Some(volume) => {
+                volume.roll_over(Utc::now().date_naive());
+                volume.bytes
+            }
+            None => 0,
+        }
+    }
 
-        // Rotate existing files (archive -> archive.1 -> archive.2 -> ...)
-        let rotated_path = parent.join(format!("{}-{}.{}", stem, timestamp, ext));
+    /// Count output against the plugin's daily log quota
+    ///
+    /// Writes a single notice the first time the quota is hit on a day.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin the output came from
+    /// * `pid` - Process the output came from
+    /// * `bytes` - Size of the output
+    ///
+    /// # Returns
+    /// false if the output must be dropped
+    pub async fn within_log_quota(&self, plugin_id: &str, pid: Option<u32>, bytes: u64) -> bool {
+        let quota = {
+            let mut volumes = self.volumes.lock().unwrap_or_else(|e| e.into_inner());
+            let volume = volumes.entry(plugin_id.to_string()).or_default();
+            volume.roll_over(Utc::now().date_naive());
+            let quota = volume.quota.unwrap_or(self.config.max_bytes_per_day);
+
+            if volume.bytes + bytes <= quota {
+                volume.bytes += bytes;
+                return true;
+            }
+            if std::mem::replace(&mut volume.notified, true) {
+                return false;
+            }
+            quota
+        };
 
-        // Rename current log file to rotated name
-        fs::rename(log_path, &rotated_path).context("Failed to rename log file for rotation")?;
+        let mut entry = LogEntry::new(
+            LogLevel::Warn,
+            &format!(
+                "Daily log quota of {} bytes exceeded, dropping output until tomorrow",
+                quota
+            ),
+        )
+        .with_plugin(plugin_id);
+        entry.pid = pid;
+        let _ = self.log_plugin(entry).await;
+        false
+    }
 
-        // Clean up old rotated files
-        self.cleanup_old_rotated_logs(parent, stem, ext)?;
+    /// Read logs for a plugin with optional filtering and pagination
+    ///
+    /// Pages are counted from the newest entry, across rotated files; see
+    /// `search_plugin_logs` for how far back a single read looks.
+    pub async fn read_plugin_logs(
+        &self,
+        plugin_id: &str,
+        filter_level: Option<LogLevel>,
+        page: usize,
+        page_size: usize,
+    ) -> Result<Vec<LogEntry>> {
+        let query = LogQuery {
+            filter: LogFilter::new(filter_level, None),
+            skip: page * page_size,
+            limit: page_size,
+            ..Default::default()
+        };
+        Ok(self.search_plugin_logs(plugin_id, query).await?.entries)
+    }
 
-        Ok(())
+    /// Search a plugin's current and rotated logs, newest entries first
+    ///
+    /// At most `log_search::MAX_SCANNED_LINES` lines are examined; a search
+    /// stopping there returns a partial page and a cursor to continue from.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin whose logs to search
+    /// * `query` - Filters, time range and page position
+    pub async fn search_plugin_logs(&self, plugin_id: &str, query: LogQuery) -> Result<LogPage> {
+        let files = self.plugin_log_files(plugin_id);
+        tokio::task::spawn_blocking(move || log_search::search(&files, &query))
+            .await
+            .context("Log search task failed")?
     }
 
-    /// Remove old rotated log files beyond max_rotated_files limit
-    fn cleanup_old_rotated_logs(&self, dir: &Path, stem: &str, ext: &str) -> Result<()> {
-        // Read directory and find all rotated log files
-        let mut rotated_files: Vec<PathBuf> = fs::read_dir(dir)?
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .filter(|path| {
-                path.file_name()
-                    .and_then(|name| name.to_str())
-                    .map(|name| name.starts_with(stem) && name.ends_with(ext))
-                    .unwrap_or(false)
-            })
-            .collect();
-
-        // Sort by modification time (oldest first)
-        rotated_files.sort_by(|a, b| {
-            let a_time = a
-                .metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-            let b_time = b
-                .metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-            a_time.cmp(&b_time)
-        });
+    /// Check if log file needs rotation and rotate if necessary
+    async fn check_and_rotate(&self, log_path: &Path) -> Result<()> {
+        if !log_path.exists() {
+            return Ok(());
+        }
 
-        // Remove files beyond max_rotated_files limit
-        let keep_count = self.config.max_rotated_files;
-        if rotated_files.len() > keep_count {
-            for old_file in rotated_files.iter().take(rotated_files.len() - keep_count) {
-                fs::remove_file(old_file).ok(); // Ignore errors
-            }
+        let retention = self.retention();
+        let metadata = fs::metadata(log_path)?;
+        if metadata.len() >= retention.max_file_size {
+            log_rotation::rotate(log_path, &retention).await?;
         }
 
         Ok(())
@@ -328,7 +786,10 @@ impl PluginLogger {
 /// Supervisor logger for core plugin system logs
 #[derive(Debug)]
 pub struct SupervisorLogger {
+    log_path: PathBuf,
     log_file: Arc<Mutex<File>>,
+    retention: std::sync::Mutex<LogRetention>,
+    forwarder: Arc<LogForwarder>,
 }
 
 impl SupervisorLogger {
@@ -338,41 +799,77 @@ impl SupervisorLogger {
 
         let log_path = log_dir.join("plugin-supervisor.log");
 
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-            .context("Failed to open supervisor log file")?;
+        let file = Self::open(&log_path)?;
 
         Ok(Self {
+            log_path,
             log_file: Arc::new(Mutex::new(file)),
+            retention: std::sync::Mutex::new(LogRetention::default()),
+            forwarder: Arc::new(LogForwarder::default()),
         })
     }
 
-    /// Log a message
-    // TODO: Integrate in general supervisor logging
-    #[allow(dead_code)]
-    pub async fn log(&self, level: LogLevel, message: &str) -> Result<()> {
-        let entry = LogEntry::new(level, message);
+    /// Forward written entries through a shared forwarder
+    pub fn with_forwarder(mut self, forwarder: Arc<LogForwarder>) -> Self {
+        self.forwarder = forwarder;
+        self
+    }
+
+    fn open(log_path: &Path) -> Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)
+            .context("Failed to open supervisor log file")
+    }
+
+    /// Replace the rotation and retention policy
+    pub fn set_retention(&self, retention: LogRetention) {
+        *self.retention.lock().unwrap_or_else(|e| e.into_inner()) = retention;
+    }
+
+    fn retention(&self) -> LogRetention {
+        self.retention
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Delete rotated supervisor logs the retention policy no longer keeps
+    pub fn prune_rotated_logs(&self) -> Result<usize> {
+        log_rotation::prune(&self.log_path, &self.retention(), Utc::now())
+    }
+
+    /// Append an entry, rotating the file first if it is full
+    async fn write_entry(&self, entry: LogEntry) -> Result<()> {
         let json = entry.to_json()?;
+        let retention = self.retention();
 
         let mut file = self.log_file.lock().await;
+        if file.metadata()?.len() >= retention.max_file_size {
+            log_rotation::rotate(&self.log_path, &retention).await?;
+            *file = Self::open(&self.log_path)?;
+        }
         writeln!(file, "{}", json).context("Failed to write supervisor log")?;
+        drop(file);
 
+        self.forwarder.forward(LogSource::Supervisor, &entry);
         Ok(())
     }
 
+    /// Log a message
+    // TODO: Integrate in general supervisor logging
+    #[allow(dead_code)]
+    pub async fn log(&self, level: LogLevel, message: &str) -> Result<()> {
+        self.write_entry(LogEntry::new(level, message)).await
+    }
+
     /// Log error with details
     // TODO: Integrate in error handling paths
     #[allow(dead_code)]
     pub async fn log_error(&self, message: &str, error: &str) -> Result<()> {
-        let entry = LogEntry::new(LogLevel::Error, message).with_error(error);
-        let json = entry.to_json()?;
-
-        let mut file = self.log_file.lock().await;
-        writeln!(file, "{}", json).context("Failed to write supervisor log")?;
-
-        Ok(())
+        self.write_entry(LogEntry::new(LogLevel::Error, message).with_error(error))
+            .await
     }
 
     /// Log plugin event (spawn, kill, crash, restart, etc.)
@@ -389,16 +886,170 @@ impl SupervisorLogger {
             format!("Plugin {}: {}", plugin_id, event)
         };
 
-        let entry = LogEntry::new(level, &message).with_plugin(plugin_id);
-        let json = entry.to_json()?;
+        self.write_entry(LogEntry::new(level, &message).with_plugin(plugin_id))
+            .await
+    }
+}
 
-        let mut file = self.log_file.lock().await;
-        writeln!(file, "{}", json).context("Failed to write supervisor log")?;
+/// Settings key enabling the plugin access log
+pub const ACCESS_LOG_SETTING: &str = "plugin_access_log";
 
-        Ok(())
+/// One request forwarded to a plugin, as written to the access log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccessLogEntry {
+    pub timestamp: String,
+    pub plugin: String,
+    pub method: String,
+    /// Path below the plugin's route, without the query string
+    pub path: String,
+    pub status: u16,
+    pub duration_ms: f64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub user: String,
+}
+
+/// Entries queued for the access log writer before new ones are dropped
+const ACCESS_LOG_QUEUE: usize = 4096;
+
+/// Access logger writing one JSON line per forwarded plugin request
+///
+/// Disabled until the `plugin_access_log` setting is `true`. Entries are
+/// queued to a writer task, so a request never waits on the file; when the
+/// queue is full they are dropped and counted. The file is rotated and
+/// pruned with the same policy as the other logs.
+#[derive(Debug)]
+pub struct AccessLogger {
+    log_path: PathBuf,
+    // Queue of the writer task, None while disabled
+    queue: std::sync::Mutex<Option<mpsc::Sender<AccessLogEntry>>>,
+    retention: Arc<std::sync::Mutex<LogRetention>>,
+    // Entries dropped since the writer last reported them
+    dropped: Arc<AtomicU64>,
+}
+
+impl AccessLogger {
+    /// Create a disabled access logger writing to `plugin-access.log`
+    pub fn new(log_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(log_dir).context("Failed to create log directory")?;
+
+        Ok(Self {
+            log_path: log_dir.join("plugin-access.log"),
+            queue: std::sync::Mutex::new(None),
+            retention: Arc::new(std::sync::Mutex::new(LogRetention::default())),
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Turn the access log on or off
+    ///
+    /// Enabling starts the writer task; disabling lets it write what is
+    /// queued, then stop.
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if !enabled {
+            *queue = None;
+            return;
+        }
+        if queue.is_none() {
+            let (sender, entries) = mpsc::channel(ACCESS_LOG_QUEUE);
+            tokio::spawn(write_access_log(
+                self.log_path.clone(),
+                entries,
+                Arc::clone(&self.retention),
+                Arc::clone(&self.dropped),
+            ));
+            *queue = Some(sender);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Replace the rotation and retention policy
+    pub fn set_retention(&self, retention: LogRetention) {
+        *self.retention.lock().unwrap_or_else(|e| e.into_inner()) = retention;
+    }
+
+    fn retention(&self) -> LogRetention {
+        self.retention
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Delete rotated access logs the retention policy no longer keeps
+    pub fn prune_rotated_logs(&self) -> Result<usize> {
+        log_rotation::prune(&self.log_path, &self.retention(), Utc::now())
+    }
+
+    /// Queue an entry if the access log is enabled
+    pub fn log(&self, entry: AccessLogEntry) {
+        let queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(queue) = queue.as_ref() {
+            if queue.try_send(entry).is_err() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
     }
 }
 
+/// Write queued access log entries until the queue is closed
+async fn write_access_log(
+    log_path: PathBuf,
+    mut entries: mpsc::Receiver<AccessLogEntry>,
+    retention: Arc<std::sync::Mutex<LogRetention>>,
+    dropped: Arc<AtomicU64>,
+) {
+    let mut file = None;
+    while let Some(entry) = entries.recv().await {
+        let dropped = dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::warn!("Dropped {} access log entries (queue full)", dropped);
+        }
+
+        let retention = retention.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Err(e) = append_access_log(&log_path, &mut file, &entry, &retention).await {
+            tracing::warn!("Failed to write access log: {:#}", e);
+        }
+    }
+}
+
+/// Append one entry, rotating the file first if it is full
+async fn append_access_log(
+    log_path: &Path,
+    file: &mut Option<File>,
+    entry: &AccessLogEntry,
+    retention: &LogRetention,
+) -> Result<()> {
+    let json = serde_json::to_string(entry).context("Failed to serialize access log entry")?;
+
+    let full = match file.as_ref() {
+        Some(file) => file.metadata()?.len() >= retention.max_file_size,
+        None => false,
+    };
+    if full {
+        *file = None;
+        log_rotation::rotate(log_path, retention).await?;
+    }
+    if file.is_none() {
+        *file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)
+                .context("Failed to open access log file")?,
+        );
+    }
+    let file = file.as_mut().expect("access log file was just opened");
+    writeln!(file, "{}", json).context("Failed to write access log")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -428,4 +1079,177 @@ mod tests {
         assert_eq!(LogLevel::parse_level("ERROR"), Some(LogLevel::Error));
         assert_eq!(LogLevel::parse_level("invalid"), None);
     }
+
+    #[test]
+    fn test_parse_output_line_enforces_identity() {
+        let line = r#"{"level":"warn","message":"disk low","plugin":"other","pid":1}"#;
+        let entry = parse_output_line("real", Some(42), LogStream::Stderr, line);
+        assert_eq!(entry.level, "Warn");
+        assert_eq!(entry.message, "disk low");
+        assert_eq!(entry.plugin.as_deref(), Some("real"));
+        assert_eq!(entry.pid, Some(42));
+
+        let entry = parse_output_line("real", Some(42), LogStream::Stdout, "{not json");
+        assert_eq!(entry.level, "Info");
+        assert_eq!(entry.message, "{not json");
+        assert_eq!(entry.stream.as_deref(), Some("stdout"));
+    }
+
+    #[tokio::test]
+    async fn test_capture_output_frames_lines_and_rate_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = Arc::new(
+            PluginLogger::new(LogConfig {
+                log_dir: temp_dir.path().to_path_buf(),
+                max_line_length: 48,
+                rate_limit_per_sec: 1,
+                rate_limit_burst: 3,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        let input = format!(
+            "first\n{{\"level\":\"error\",\"message\":\"second\"}}\n{}\nfourth\nfifth\n",
+            "x".repeat(100)
+        );
+        logger
+            .capture_output(
+                "capture",
+                Some(7),
+                LogStream::Stdout,
+                std::io::Cursor::new(input),
+            )
+            .await
+            .unwrap();
+
+        let mut logs = logger
+            .read_plugin_logs("capture", None, 0, 100)
+            .await
+            .unwrap();
+        logs.reverse();
+
+        let messages: Vec<&str> = logs.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages[0], "first");
+        assert_eq!(messages[1], "second");
+        assert_eq!(logs[1].level, "Error");
+        assert_eq!(messages[2], format!("{} [truncated]", "x".repeat(48)));
+        assert_eq!(
+            messages[3],
+            "Dropped 2 output lines (log rate limit exceeded)"
+        );
+        assert!(logs.iter().all(|e| e.pid == Some(7)));
+    }
+
+    #[tokio::test]
+    async fn test_daily_log_quota() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = Arc::new(PluginLogger::from_directory(temp_dir.path()).unwrap());
+        logger.set_log_quota("quota", 10);
+
+        logger
+            .capture_output(
+                "quota",
+                None,
+                LogStream::Stdout,
+                std::io::Cursor::new("12345\n123456\nabc\n"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(logger.log_usage("quota"), 8);
+        assert!(!logger.within_log_quota("quota", None, 3).await);
+
+        let mut logs = logger
+            .read_plugin_logs("quota", None, 0, 100)
+            .await
+            .unwrap();
+        logs.reverse();
+        let messages: Vec<&str> = logs.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "12345",
+                "Daily log quota of 10 bytes exceeded, dropping output until tomorrow",
+                "abc",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_follows_plugin_log() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = PluginLogger::from_directory(temp_dir.path()).unwrap();
+        let mut live = logger.subscribe("tail");
+
+        logger
+            .log_plugin(LogEntry::new(LogLevel::Info, "other").with_plugin("other"))
+            .await
+            .unwrap();
+        logger
+            .log_plugin(LogEntry::new(LogLevel::Warn, "Disk LOW").with_plugin("tail"))
+            .await
+            .unwrap();
+        let entry = live.recv().await.unwrap();
+        assert_eq!(entry.message, "Disk LOW");
+        assert!(live.try_recv().is_err());
+
+        let filter = LogFilter::new(Some(LogLevel::Warn), Some("disk low"));
+        assert!(filter.matches(&entry));
+        assert!(!filter.matches(&LogEntry::new(LogLevel::Info, "disk low")));
+        assert!(
+            filter.matches(&LogEntry::new(LogLevel::Error, "write failed").with_error("Disk low"))
+        );
+
+        // The channel is dropped once nobody follows the plugin anymore
+        drop(live);
+        logger
+            .log_plugin(LogEntry::new(LogLevel::Info, "unwatched").with_plugin("tail"))
+            .await
+            .unwrap();
+        assert!(logger.live.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_access_log_enabled_and_rotated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = AccessLogger::new(temp_dir.path()).unwrap();
+        let log_path = temp_dir.path().join("plugin-access.log");
+        let entry = AccessLogEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            plugin: "weather".to_string(),
+            method: "GET".to_string(),
+            path: "/forecast".to_string(),
+            status: 200,
+            duration_ms: 1.5,
+            bytes_in: 0,
+            bytes_out: 42,
+            user: "admin".to_string(),
+        };
+
+        logger.log(entry.clone());
+        assert!(!logger.is_enabled(), "Disabled by default");
+
+        logger.set_enabled(true);
+        logger.set_retention(LogRetention {
+            max_file_size: 1,
+            compress: false,
+            ..Default::default()
+        });
+        logger.log(entry.clone());
+        logger.log(entry.clone());
+        // The writer task writes what is queued, then stops
+        logger.set_enabled(false);
+        for _ in 0..100 {
+            let written = fs::read_to_string(&log_path).is_ok_and(|log| log.ends_with('\n'));
+            if written && !log_rotation::rotated_logs(&log_path).is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let written: AccessLogEntry =
+            serde_json::from_str(fs::read_to_string(&log_path).unwrap().trim()).unwrap();
+        assert_eq!(written, entry);
+        assert_eq!(log_rotation::rotated_logs(&log_path).len(), 1);
+    }
 }
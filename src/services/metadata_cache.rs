@@ -0,0 +1,324 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing::{debug, warn};
+
+use toru_plugin_api::PluginMetadata;
+
+/// Hard limit for a `--metadata` invocation
+pub const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum output accepted from a `--metadata` invocation (per stream)
+pub const MAX_METADATA_OUTPUT: usize = 64 * 1024;
+
+/// Identity of a binary on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub size: u64,
+    /// Modification time in nanoseconds since the unix epoch
+    pub mtime_ns: i64,
+    /// Hex-encoded SHA-256 of the file contents
+    pub sha256: String,
+}
+
+/// Compute the fingerprint of a binary
+pub fn fingerprint(path: &Path) -> Result<Fingerprint> {
+    let metadata = fs::metadata(path).context("Failed to stat plugin binary")?;
+    let mtime_ns = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0);
+
+    let mut file = fs::File::open(path).context("Failed to open plugin binary")?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .context("Failed to read plugin binary")?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+
+    Ok(Fingerprint {
+        size: metadata.len(),
+        mtime_ns,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+/// Validate metadata fields to prevent injection attacks
+pub fn validate_metadata(metadata: &PluginMetadata) -> Result<()> {
+    if metadata.id.is_empty()
+        || !metadata
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(anyhow::anyhow!("Invalid plugin ID format"));
+    }
+    if !metadata.route.starts_with('/') || metadata.route.contains("..") {
+        return Err(anyhow::anyhow!("Invalid plugin route"));
+    }
+    if metadata.name.len() > 100 || metadata.author.as_ref().is_some_and(|a| a.len() > 100) {
+        return Err(anyhow::anyhow!("Metadata field too long"));
+    }
+    Ok(())
+}
+
+/// Read at most `limit + 1` bytes, so callers can tell the limit was exceeded
+async fn read_capped<R: AsyncRead + Unpin>(reader: Option<R>, limit: usize) -> Vec<u8> {
+    let mut output = Vec::new();
+    if let Some(reader) = reader {
+        let _ = reader.take(limit as u64 + 1).read_to_end(&mut output).await;
+    }
+    output
+}
+
+/// Run a binary with `--metadata` and parse its output
+///
+/// The process is killed if it does not exit within `timeout` or writes
+/// more than [`MAX_METADATA_OUTPUT`] bytes.
+///
+/// # Arguments
+/// * `binary_path` - Path to the plugin binary
+/// * `timeout` - Hard limit for the whole invocation
+pub async fn run_metadata_command(binary_path: &Path, timeout: Duration) -> Result<PluginMetadata> {
+    let mut child = tokio::process::Command::new(binary_path)
+        .arg("--metadata")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to execute plugin binary")?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let result = tokio::time::timeout(timeout, async {
+        let (stdout, stderr) = tokio::join!(
+            read_capped(stdout, MAX_METADATA_OUTPUT),
+            read_capped(stderr, MAX_METADATA_OUTPUT)
+        );
+        if stdout.len() > MAX_METADATA_OUTPUT {
+            return Err(anyhow::anyhow!(
+                "Plugin --metadata output exceeds {} bytes",
+                MAX_METADATA_OUTPUT
+            ));
+        }
+        let status = child.wait().await?;
+        Ok((status, stdout, stderr))
+    })
+    .await;
+
+    let (status, stdout, stderr) = match result {
+        Ok(output) => output?,
+        Err(_) => {
+            return Err(anyhow::anyhow!(
+                "Plugin --metadata did not finish within {:?}",
+                timeout
+            ))
+        }
+    };
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(anyhow::anyhow!(
+            "Plugin --metadata command failed: {}",
+            stderr
+        ));
+    }
+
+    let stdout = String::from_utf8(stdout).context("Plugin metadata output is not valid UTF-8")?;
+
+    let metadata: PluginMetadata =
+        serde_json::from_str(&stdout).context("Failed to parse plugin metadata JSON")?;
+    validate_metadata(&metadata)?;
+
+    Ok(metadata)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    metadata: PluginMetadata,
+}
+
+/// Metadata of plugin binaries, persisted in the metadata directory
+///
+/// Entries are keyed by path and only reused while the binary's size,
+/// modification time and content hash are unchanged, so a replaced binary
+/// is always executed again.
+#[derive(Debug)]
+pub struct MetadataCache {
+    file: PathBuf,
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+}
+
+impl MetadataCache {
+    /// Load the cache from `file`, starting empty if it is missing or unreadable
+    pub fn load(file: PathBuf) -> Self {
+        let entries = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            file,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Get the metadata of a binary, executing it only on a cache miss
+    ///
+    /// # Arguments
+    /// * `binary_path` - Path to the plugin binary
+    pub async fn read_metadata(&self, binary_path: &Path) -> Result<PluginMetadata> {
+        let fingerprint = fingerprint(binary_path)?;
+
+        if let Some(metadata) = self.get(binary_path, &fingerprint) {
+            debug!("Using cached metadata for {:?}", binary_path);
+            return Ok(metadata);
+        }
+
+        let metadata = run_metadata_command(binary_path, METADATA_TIMEOUT).await?;
+        self.insert(binary_path, fingerprint, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Look up a cached entry that matches the fingerprint
+    fn get(&self, binary_path: &Path, fingerprint: &Fingerprint) -> Option<PluginMetadata> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(binary_path)
+            .filter(|entry| &entry.fingerprint == fingerprint)
+            .map(|entry| entry.metadata.clone())
+    }
+
+    /// Store an entry and persist the cache
+    fn insert(&self, binary_path: &Path, fingerprint: Fingerprint, metadata: PluginMetadata) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(
+            binary_path.to_path_buf(),
+            CacheEntry {
+                fingerprint,
+                metadata,
+            },
+        );
+        self.save(&entries);
+    }
+
+    /// Drop entries for binaries that are no longer present
+    ///
+    /// # Arguments
+    /// * `paths` - Binaries found by the latest scan
+    pub fn retain_paths(&self, paths: &HashSet<PathBuf>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        entries.retain(|path, _| paths.contains(path));
+        if entries.len() != before {
+            self.save(&entries);
+        }
+    }
+
+    /// Write the cache atomically; failures only cost a re-execution later
+    fn save(&self, entries: &HashMap<PathBuf, CacheEntry>) {
+        let tmp = self.file.with_extension("json.tmp");
+        let result = serde_json::to_string_pretty(entries)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| fs::write(&tmp, json + "\n").map_err(Into::into))
+            .and_then(|_| fs::rename(&tmp, &self.file).map_err(Into::into));
+
+        if let Err(e) = result {
+            warn!("Failed to save plugin metadata cache: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Plugin script that counts its --metadata invocations
+    fn write_plugin(path: &Path, counter: &Path, version: &str) {
+        let script = format!(
+            "#!/bin/bash\necho x >> \"{}\"\necho '{{\"id\": \"cached\", \"name\": \"Cached\", \"version\": \"{}\", \"icon\": \"x\", \"route\": \"/cached\"}}'\n",
+            counter.display(),
+            version
+        );
+        fs::write(path, script).unwrap();
+
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn invocations(counter: &Path) -> usize {
+        fs::read_to_string(counter)
+            .map(|c| c.lines().count())
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_and_invalidation() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = temp_dir.path().join("cached.binary");
+        let counter = temp_dir.path().join("count");
+        let cache_file = temp_dir.path().join("metadata-cache.json");
+        write_plugin(&binary, &counter, "1.0.0");
+
+        let cache = MetadataCache::load(cache_file.clone());
+        assert_eq!(cache.read_metadata(&binary).await.unwrap().version, "1.0.0");
+        assert_eq!(cache.read_metadata(&binary).await.unwrap().version, "1.0.0");
+        assert_eq!(invocations(&counter), 1);
+
+        // Persisted across restarts
+        let cache = MetadataCache::load(cache_file);
+        cache.read_metadata(&binary).await.unwrap();
+        assert_eq!(invocations(&counter), 1);
+
+        // Replacing the binary invalidates the entry
+        write_plugin(&binary, &counter, "2.0.0");
+        assert_eq!(cache.read_metadata(&binary).await.unwrap().version, "2.0.0");
+        assert_eq!(invocations(&counter), 2);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_command_limits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+
+        let hanging = temp_dir.path().join("hanging.binary");
+        fs::write(&hanging, "#!/bin/bash\nsleep 30\n").unwrap();
+        fs::set_permissions(&hanging, fs::Permissions::from_mode(0o755)).unwrap();
+        let err = run_metadata_command(&hanging, Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("did not finish"));
+
+        let noisy = temp_dir.path().join("noisy.binary");
+        fs::write(
+            &noisy,
+            "#!/bin/bash\nhead -c 200000 /dev/zero | tr '\\0' 'a'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&noisy, fs::Permissions::from_mode(0o755)).unwrap();
+        let err = run_metadata_command(&noisy, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeds"));
+    }
+}
@@ -1,6 +1,22 @@
 pub mod auth;
 pub mod executor;
+pub mod host_socket;
 pub mod kv_store;
+pub mod log_rotation;
+pub mod log_search;
+pub mod log_sinks;
 pub mod logging;
+pub mod metadata_cache;
+pub mod plugin_data;
+pub mod plugin_database;
+pub mod plugin_events;
+pub mod plugin_http;
+pub mod plugin_package;
+pub mod plugin_versions;
+pub mod plugin_watcher;
 pub mod plugins;
+pub mod request_metrics;
 pub mod system;
+pub mod timeseries;
+#[cfg(feature = "wasm")]
+pub mod wasm_runtime;
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings key enabling removal of a plugin's data directory when the
+/// plugin is uninstalled (its binary or package removed)
+pub const REMOVE_ON_UNINSTALL_SETTING: &str = "plugin_data_remove_on_uninstall";
+
+/// A file in a plugin's data directory
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DataFile {
+    /// Path relative to the data directory, with `/` separators
+    pub path: String,
+    pub size: u64,
+    /// Last modification time (RFC 3339)
+    pub modified: Option<String>,
+}
+
+/// List all files below a data directory, sorted by path
+///
+/// Symlinks are listed with their own size and never followed, so a plugin
+/// cannot make the listing (or its quota) reach outside the directory.
+///
+/// # Arguments
+/// * `data_dir` - The plugin's data directory; missing means empty
+pub fn list_files(data_dir: &Path) -> Result<Vec<DataFile>> {
+    let mut files = Vec::new();
+    if data_dir.exists() {
+        collect_files(data_dir, data_dir, &mut files)?;
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<DataFile>) -> Result<()> {
+    for entry in fs::read_dir(dir).context("Failed to read data directory")? {
+        let entry = entry?;
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.is_dir() {
+            collect_files(root, &entry.path(), files)?;
+            continue;
+        }
+
+        let relative = entry.path();
+        let relative = relative.strip_prefix(root).unwrap_or(&relative);
+        files.push(DataFile {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        });
+    }
+    Ok(())
+}
+
+/// Total size of the files below a data directory in bytes
+pub fn dir_size(data_dir: &Path) -> Result<u64> {
+    Ok(list_files(data_dir)?.iter().map(|f| f.size).sum())
+}
+
+/// [`dir_size`] on a blocking thread, so walking a large directory does not
+/// stall the async runtime
+pub async fn measure_dir(data_dir: PathBuf) -> Result<u64> {
+    tokio::task::spawn_blocking(move || dir_size(&data_dir))
+        .await
+        .context("Failed to measure data directory")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_files_and_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        assert!(list_files(&data_dir).unwrap().is_empty());
+
+        fs::create_dir_all(data_dir.join("reports/2024")).unwrap();
+        fs::write(data_dir.join("cache.bin"), [0u8; 10]).unwrap();
+        fs::write(data_dir.join("reports/2024/jan.csv"), "a,b\n").unwrap();
+        fs::write(temp_dir.path().join("outside"), [0u8; 100]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(temp_dir.path().join("outside"), data_dir.join("link")).unwrap();
+
+        let files = list_files(&data_dir).unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths[0], "cache.bin");
+        assert_eq!(*paths.last().unwrap(), "reports/2024/jan.csv");
+        assert!(dir_size(&data_dir).unwrap() < 100);
+    }
+}
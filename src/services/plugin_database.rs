@@ -0,0 +1,489 @@
+use anyhow::{Context, Result};
+use rusqlite::limits::Limit;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use toru_plugin_api::{
+    PluginError, PluginResult, SqlExecuted, SqlMigration, SqlRows, SqlStatement, SqlValue,
+};
+
+/// Largest number of rows a single query may return
+///
+/// Results travel as one protocol message, so unbounded queries must page
+/// with `LIMIT`/`OFFSET` instead.
+pub const MAX_QUERY_ROWS: usize = 10_000;
+
+/// Longest a single SQL operation may run before it is interrupted
+///
+/// Shorter than the plugin's own wait, so the plugin gets a timeout error
+/// instead of giving up while the query keeps running in the core.
+pub const SQL_DEADLINE: Duration = Duration::from_secs(10);
+
+/// SQLite virtual machine instructions between deadline checks
+const PROGRESS_CHECK_OPS: i32 = 10_000;
+
+/// Table recording applied migrations
+const MIGRATIONS_TABLE: &str = "_toru_migrations";
+
+/// Sqlite database owned by a single plugin
+///
+/// Every plugin gets its own file under `plugins/.databases/`, opened by the
+/// core and reached by the plugin through `sql` messages (or the `sql` host
+/// function of WebAssembly plugins). The connection refuses `ATTACH`, so a
+/// plugin cannot open another plugin's file (or the core database) through it.
+/// This implements the PluginDatabase trait from toru-plugin-api.
+#[derive(Debug, Clone)]
+pub struct SqlitePluginDatabase {
+    conn: Arc<Mutex<Connection>>,
+    deadline: Duration,
+}
+
+impl SqlitePluginDatabase {
+    /// Open (creating if needed) the database of a plugin
+    ///
+    /// # Arguments
+    /// * `databases_dir` - Directory holding all plugin databases
+    /// * `plugin_id` - Plugin owning the database
+    pub fn open(databases_dir: &Path, plugin_id: &str) -> Result<Self> {
+        fs::create_dir_all(databases_dir).context("Failed to create databases directory")?;
+        let path = database_path(databases_dir, plugin_id)?;
+        let conn = Connection::open(&path)
+            .with_context(|| format!("Failed to open database of plugin {}", plugin_id))?;
+
+        // Also rules out `VACUUM INTO`, which attaches its target internally
+        conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(&format!(
+            "PRAGMA foreign_keys = ON;
+             CREATE TABLE IF NOT EXISTS {} (
+                version TEXT PRIMARY KEY,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+             );",
+            MIGRATIONS_TABLE
+        ))?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            deadline: SQL_DEADLINE,
+        })
+    }
+
+    #[cfg(test)]
+    fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Run an operation on a blocking thread, interrupting it at the deadline
+    ///
+    /// SQLite calls block, so they must not run on the async workers. A
+    /// statement still running at the deadline fails with `Timeout`; a
+    /// transaction it was part of is rolled back.
+    async fn run<T, F>(&self, operation: F) -> PluginResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> PluginResult<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        let deadline = self.deadline;
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            // Waiting for the connection does not count against the deadline
+            let expires_at = Instant::now() + deadline;
+            let expired = Arc::new(AtomicBool::new(false));
+            let flag = Arc::clone(&expired);
+            conn.progress_handler(
+                PROGRESS_CHECK_OPS,
+                Some(move || {
+                    let over = Instant::now() >= expires_at;
+                    flag.fetch_or(over, Ordering::Relaxed);
+                    over
+                }),
+            );
+
+            let result = operation(&mut conn);
+            conn.progress_handler(0, None::<fn() -> bool>);
+            if expired.load(Ordering::Relaxed) {
+                return Err(PluginError::Timeout);
+            }
+            result
+        })
+        .await
+        .map_err(|e| PluginError::Internal(format!("SQL task failed: {}", e)))?
+    }
+
+    /// Apply the `.sql` migrations shipped in a plugin package
+    ///
+    /// Each file is one migration, versioned by its file name without the
+    /// extension, and applied in the order given.
+    ///
+    /// # Returns
+    /// Versions applied by this call
+    pub async fn apply_migration_files(&self, files: &[PathBuf]) -> Result<Vec<String>> {
+        let mut migrations = Vec::with_capacity(files.len());
+        for file in files {
+            let version = file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .with_context(|| format!("Invalid migration file name {:?}", file))?;
+            migrations.push(SqlMigration {
+                version: version.to_string(),
+                sql: fs::read_to_string(file)
+                    .with_context(|| format!("Failed to read migration {:?}", file))?,
+            });
+        }
+
+        toru_plugin_api::PluginDatabase::migrate(self, &migrations)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
+/// Database file of a plugin
+///
+/// # Arguments
+/// * `databases_dir` - Directory holding all plugin databases
+/// * `plugin_id` - Plugin owning the database; must be a valid plugin ID
+pub fn database_path(databases_dir: &Path, plugin_id: &str) -> Result<PathBuf> {
+    if plugin_id.is_empty()
+        || !plugin_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(anyhow::anyhow!("Invalid plugin ID {:?}", plugin_id));
+    }
+    Ok(databases_dir.join(format!("{}.sqlite", plugin_id)))
+}
+
+/// Errors of plugin-supplied SQL are the plugin's to fix
+fn sql_error(error: rusqlite::Error) -> PluginError {
+    PluginError::InvalidRequest(error.to_string())
+}
+
+fn to_value(value: &SqlValue) -> Value {
+    match value {
+        SqlValue::Null => Value::Null,
+        SqlValue::Integer(i) => Value::Integer(*i),
+        SqlValue::Real(f) => Value::Real(*f),
+        SqlValue::Text(s) => Value::Text(s.clone()),
+    }
+}
+
+fn from_value(value: ValueRef<'_>) -> SqlValue {
+    match value {
+        ValueRef::Null => SqlValue::Null,
+        ValueRef::Integer(i) => SqlValue::Integer(i),
+        ValueRef::Real(f) => SqlValue::Real(f),
+        // Blobs written by other tools come back as (lossy) text
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+            SqlValue::Text(String::from_utf8_lossy(bytes).into_owned())
+        }
+    }
+}
+
+fn execute_statement(conn: &Connection, sql: &str, params: &[SqlValue]) -> PluginResult<u64> {
+    let rows = conn
+        .execute(sql, params_from_iter(params.iter().map(to_value)))
+        .map_err(sql_error)?;
+    Ok(rows as u64)
+}
+
+/// Collect the rows of a query, up to `MAX_QUERY_ROWS`
+fn query_rows(conn: &Connection, sql: &str, params: &[SqlValue]) -> PluginResult<SqlRows> {
+    let mut stmt = conn.prepare(sql).map_err(sql_error)?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    let mut result = SqlRows {
+        columns,
+        rows: Vec::new(),
+    };
+    let mut rows = stmt
+        .query(params_from_iter(params.iter().map(to_value)))
+        .map_err(sql_error)?;
+    while let Some(row) = rows.next().map_err(sql_error)? {
+        if result.rows.len() == MAX_QUERY_ROWS {
+            return Err(PluginError::InvalidRequest(format!(
+                "Query returned more than {} rows, page through the results with LIMIT",
+                MAX_QUERY_ROWS
+            )));
+        }
+        let values = (0..result.columns.len())
+            .map(|i| row.get_ref(i).map(from_value))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)?;
+        result.rows.push(values);
+    }
+    Ok(result)
+}
+
+/// Apply the migrations that were not applied yet, see `migrate`
+fn apply_migrations(
+    conn: &mut Connection,
+    migrations: &[SqlMigration],
+) -> PluginResult<Vec<String>> {
+    let mut applied = Vec::new();
+    for migration in migrations {
+        let tx = conn.transaction().map_err(sql_error)?;
+        let done: Option<i64> = tx
+            .query_row(
+                &format!("SELECT 1 FROM {} WHERE version = ?1", MIGRATIONS_TABLE),
+                params![migration.version],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        if done.is_some() {
+            continue;
+        }
+
+        tx.execute_batch(&migration.sql).map_err(|e| {
+            PluginError::InvalidRequest(format!("Migration {} failed: {}", migration.version, e))
+        })?;
+        tx.execute(
+            &format!("INSERT INTO {} (version) VALUES (?1)", MIGRATIONS_TABLE),
+            params![migration.version],
+        )
+        .map_err(sql_error)?;
+        tx.commit().map_err(sql_error)?;
+        applied.push(migration.version.clone());
+    }
+    Ok(applied)
+}
+
+#[async_trait::async_trait]
+impl toru_plugin_api::PluginDatabase for SqlitePluginDatabase {
+    /// Run a statement returning rows
+    ///
+    /// # Arguments
+    /// * `sql` - Statement with `?` placeholders
+    /// * `params` - Values bound to the placeholders
+    async fn query(&self, sql: &str, params: &[SqlValue]) -> PluginResult<SqlRows> {
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        self.run(move |conn| query_rows(conn, &sql, &params)).await
+    }
+
+    /// Run a statement that changes data
+    async fn execute(&self, sql: &str, params: &[SqlValue]) -> PluginResult<SqlExecuted> {
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        self.run(move |conn| {
+            let rows_affected = execute_statement(conn, &sql, &params)?;
+            Ok(SqlExecuted {
+                rows_affected,
+                last_insert_id: conn.last_insert_rowid(),
+            })
+        })
+        .await
+    }
+
+    /// Run statements in one transaction; any failure rolls all of them back
+    async fn batch(&self, statements: &[SqlStatement]) -> PluginResult<Vec<u64>> {
+        let statements = statements.to_vec();
+        self.run(move |conn| {
+            let tx = conn.transaction().map_err(sql_error)?;
+            let mut rows_affected = Vec::with_capacity(statements.len());
+            for statement in &statements {
+                rows_affected.push(execute_statement(&tx, &statement.sql, &statement.params)?);
+            }
+            tx.commit().map_err(sql_error)?;
+            Ok(rows_affected)
+        })
+        .await
+    }
+
+    /// Apply the migrations that were not applied yet
+    ///
+    /// Each migration runs in its own transaction together with its entry in
+    /// the migrations table, so a failing migration leaves no partial schema
+    /// and is retried on the next call. The deadline covers all of them.
+    async fn migrate(&self, migrations: &[SqlMigration]) -> PluginResult<Vec<String>> {
+        let migrations = migrations.to_vec();
+        self.run(move |conn| apply_migrations(conn, &migrations))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use toru_plugin_api::PluginDatabase;
+
+    fn migration(version: &str, sql: &str) -> SqlMigration {
+        SqlMigration {
+            version: version.to_string(),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_execute_and_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = SqlitePluginDatabase::open(temp_dir.path(), "orders").unwrap();
+        db.execute(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer TEXT, total REAL)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let inserted = db
+            .execute(
+                "INSERT INTO orders (customer, total) VALUES (?, ?)",
+                &["ada".into(), 12.5.into()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted.rows_affected, 1);
+        assert_eq!(inserted.last_insert_id, 1);
+
+        let statements = vec![
+            SqlStatement {
+                sql: "INSERT INTO orders (customer, total) VALUES (?, ?)".to_string(),
+                params: vec!["bob".into(), SqlValue::Null],
+            },
+            SqlStatement {
+                sql: "UPDATE orders SET total = total * 2".to_string(),
+                params: vec![],
+            },
+        ];
+        assert_eq!(db.batch(&statements).await.unwrap(), [1, 2]);
+
+        // A failing batch leaves nothing behind
+        let failing = vec![
+            statements[0].clone(),
+            SqlStatement {
+                sql: "INSERT INTO missing VALUES (1)".to_string(),
+                params: vec![],
+            },
+        ];
+        assert!(matches!(
+            db.batch(&failing).await,
+            Err(PluginError::InvalidRequest(_))
+        ));
+
+        let rows = db
+            .query(
+                "SELECT customer, total FROM orders WHERE id >= ? ORDER BY id",
+                &[1.into()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.columns, ["customer", "total"]);
+        assert_eq!(
+            rows.rows,
+            [
+                vec![SqlValue::from("ada"), SqlValue::Real(25.0)],
+                vec![SqlValue::from("bob"), SqlValue::Null],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrations_apply_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = SqlitePluginDatabase::open(temp_dir.path(), "migrating").unwrap();
+        let migrations = vec![
+            migration("001_init", "CREATE TABLE items (name TEXT);"),
+            migration(
+                "002_price",
+                "ALTER TABLE items ADD COLUMN price INTEGER; CREATE INDEX items_name ON items(name);",
+            ),
+        ];
+
+        assert_eq!(
+            db.migrate(&migrations).await.unwrap(),
+            ["001_init", "002_price"]
+        );
+        assert!(db.migrate(&migrations).await.unwrap().is_empty());
+
+        // A broken migration is rolled back and reported
+        let broken = vec![migration("003_broken", "CREATE TABLE a (x); SELEC")];
+        assert!(db.migrate(&broken).await.is_err());
+        let tables = db
+            .query("SELECT name FROM sqlite_master WHERE name = 'a'", &[])
+            .await
+            .unwrap();
+        assert!(tables.rows.is_empty());
+
+        // Package migrations are versioned by file name
+        let dir = temp_dir.path().join("migrations");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("001_init.sql"), "CREATE TABLE items (name TEXT);").unwrap();
+        fs::write(dir.join("003_tags.sql"), "CREATE TABLE tags (name TEXT);").unwrap();
+        let applied = db
+            .apply_migration_files(&[dir.join("001_init.sql"), dir.join("003_tags.sql")])
+            .await
+            .unwrap();
+        assert_eq!(applied, ["003_tags"]);
+    }
+
+    #[tokio::test]
+    async fn test_other_databases_are_unreachable() {
+        let temp_dir = TempDir::new().unwrap();
+        let own = SqlitePluginDatabase::open(temp_dir.path(), "own").unwrap();
+        let other = SqlitePluginDatabase::open(temp_dir.path(), "other").unwrap();
+        other
+            .execute("CREATE TABLE secrets (value TEXT)", &[])
+            .await
+            .unwrap();
+
+        let other_path = database_path(temp_dir.path(), "other").unwrap();
+        let attach = format!("ATTACH DATABASE '{}' AS other", other_path.display());
+        assert!(own.execute(&attach, &[]).await.is_err());
+
+        let copy = temp_dir.path().join("copy.sqlite");
+        let vacuum = format!("VACUUM INTO '{}'", copy.display());
+        assert!(own.execute(&vacuum, &[]).await.is_err());
+        assert!(!copy.exists());
+
+        assert!(database_path(temp_dir.path(), "../own").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_runaway_query_interrupted_at_deadline() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = SqlitePluginDatabase::open(temp_dir.path(), "runaway")
+            .unwrap()
+            .with_deadline(Duration::from_millis(200));
+        db.execute("CREATE TABLE counter (n INTEGER)", &[])
+            .await
+            .unwrap();
+
+        let runaway =
+            "WITH RECURSIVE forever(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM forever) \
+                       SELECT count(*) FROM forever";
+        let started = Instant::now();
+        assert!(matches!(
+            db.query(runaway, &[]).await,
+            Err(PluginError::Timeout)
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A batch stopped at the deadline leaves nothing behind
+        let statements = vec![
+            SqlStatement {
+                sql: "INSERT INTO counter VALUES (1)".to_string(),
+                params: Vec::new(),
+            },
+            SqlStatement {
+                sql: format!("INSERT INTO counter {}", runaway),
+                params: Vec::new(),
+            },
+        ];
+        assert!(matches!(
+            db.batch(&statements).await,
+            Err(PluginError::Timeout)
+        ));
+
+        // The connection is usable again with a fresh deadline
+        let rows = db.query("SELECT count(*) FROM counter", &[]).await.unwrap();
+        assert_eq!(rows.rows, vec![vec![SqlValue::Integer(0)]]);
+    }
+}
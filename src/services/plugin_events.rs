@@ -0,0 +1,208 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::db::{self, DbPool, PluginEventFilter};
+
+/// Events after which a plugin is running
+const UP_EVENTS: [&str; 3] = ["started", "upgraded", "rolled_back"];
+
+/// Events after which a plugin is not running
+const DOWN_EVENTS: [&str; 7] = [
+    "killed",
+    "disabled",
+    "removed",
+    "crashed",
+    "restarting_with_backoff",
+    "disabled_after_max_restarts",
+    "data_quota_exceeded",
+];
+
+/// Events recorded when a plugin crashed (one per crash)
+const CRASH_EVENTS: [&str; 3] = [
+    "crashed",
+    "restarting_with_backoff",
+    "disabled_after_max_restarts",
+];
+
+/// Most events read to compute a summary; events are kept for 7 days
+const MAX_SUMMARY_EVENTS: usize = 10_000;
+
+/// Uptime and crash counts computed from a plugin's event history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventSummary {
+    /// Percentage of the last 24 hours the plugin was running, None when
+    /// no event tells whether it was
+    pub uptime_24h: Option<f64>,
+    /// Same over the last 7 days
+    pub uptime_7d: Option<f64>,
+    pub crashes_24h: u64,
+    pub crashes_7d: u64,
+}
+
+/// Whether a plugin runs after an event, None for events that don't say
+fn state_after(event_type: &str) -> Option<bool> {
+    if UP_EVENTS.contains(&event_type) {
+        Some(true)
+    } else if DOWN_EVENTS.contains(&event_type) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Percentage of `[start, end]` during which the plugin was running
+///
+/// Time before the first event that tells the state is not counted, so a
+/// plugin installed an hour ago and running since has 100% uptime.
+///
+/// # Arguments
+/// * `initial` - State before the first of `events`, if known
+/// * `events` - Event times and types, oldest first; may start before `start`
+fn uptime(
+    initial: Option<bool>,
+    events: &[(DateTime<Utc>, &str)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<f64> {
+    let mut state = initial;
+    let mut tracked_from = initial.map(|_| start);
+    let mut last = start;
+    let mut up = Duration::zero();
+
+    for (at, event_type) in events {
+        let Some(next) = state_after(event_type) else {
+            continue;
+        };
+        if *at <= start {
+            state = Some(next);
+            tracked_from = Some(start);
+            continue;
+        }
+        if *at > end {
+            break;
+        }
+        match state {
+            Some(true) => up += *at - last,
+            Some(false) => {}
+            None => tracked_from = Some(*at),
+        }
+        state = Some(next);
+        last = *at;
+    }
+    if state == Some(true) {
+        up += end - last;
+    }
+
+    let tracked = end - tracked_from?;
+    if tracked <= Duration::zero() {
+        return None;
+    }
+    let percent = up.num_milliseconds() as f64 * 100.0 / tracked.num_milliseconds() as f64;
+    Some((percent * 100.0).round() / 100.0)
+}
+
+/// Summarize a plugin's uptime and crashes over the last day and week
+///
+/// # Arguments
+/// * `pool` - Database holding the events
+/// * `plugin_id` - Plugin to summarize
+/// * `now` - End of both periods
+pub async fn summarize(pool: &DbPool, plugin_id: &str, now: DateTime<Utc>) -> Result<EventSummary> {
+    let week_start = now - Duration::days(7);
+    let day_start = now - Duration::days(1);
+
+    let mut events = db::plugin_event_query(
+        pool,
+        &PluginEventFilter {
+            plugin_id: Some(plugin_id.to_string()),
+            since: Some(week_start.to_rfc3339()),
+            limit: MAX_SUMMARY_EVENTS,
+            ..Default::default()
+        },
+    )
+    .await?;
+    events.reverse();
+
+    // The state the plugin was in when the week started
+    let before = db::plugin_event_query(
+        pool,
+        &PluginEventFilter {
+            plugin_id: Some(plugin_id.to_string()),
+            event_types: UP_EVENTS
+                .iter()
+                .chain(DOWN_EVENTS.iter())
+                .map(|t| t.to_string())
+                .collect(),
+            until: Some(week_start.to_rfc3339()),
+            limit: 1,
+            ..Default::default()
+        },
+    )
+    .await?;
+    let initial = before.first().and_then(|e| state_after(&e.event_type));
+
+    let timeline: Vec<(DateTime<Utc>, &str)> = events
+        .iter()
+        .filter_map(|e| {
+            let at = DateTime::parse_from_rfc3339(&e.timestamp).ok()?;
+            Some((at.with_timezone(&Utc), e.event_type.as_str()))
+        })
+        .collect();
+    let crashes_since = |start: DateTime<Utc>| {
+        timeline
+            .iter()
+            .filter(|(at, event_type)| *at >= start && CRASH_EVENTS.contains(event_type))
+            .count() as u64
+    };
+
+    Ok(EventSummary {
+        uptime_24h: uptime(initial, &timeline, day_start, now),
+        uptime_7d: uptime(initial, &timeline, week_start, now),
+        crashes_24h: crashes_since(day_start),
+        crashes_7d: crashes_since(week_start),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-01-10T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::hours(hours)
+    }
+
+    #[test]
+    fn test_uptime() {
+        let start = at(0);
+        let end = at(10);
+
+        // Up from the start, down for 2 of 10 hours
+        let events = [
+            (at(-5), "started"),
+            (at(4), "restarting_with_backoff"),
+            (at(6), "started"),
+            (at(7), "migrated"),
+        ];
+        assert_eq!(uptime(None, &events, start, end), Some(80.0));
+
+        // Known to be down before the period, started half way
+        assert_eq!(
+            uptime(Some(false), &[(at(5), "started")], start, end),
+            Some(50.0)
+        );
+
+        // Unknown before the first event: only the time since counts
+        assert_eq!(uptime(None, &[(at(8), "started")], start, end), Some(100.0));
+        assert_eq!(
+            uptime(None, &[(at(2), "started"), (at(6), "killed")], start, end),
+            Some(50.0)
+        );
+
+        assert_eq!(uptime(None, &[], start, end), None);
+        assert_eq!(uptime(Some(true), &[], start, end), Some(100.0));
+    }
+}
@@ -0,0 +1,459 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use toru_plugin_api::protocol::MAX_MESSAGE_SIZE;
+
+use crate::db::{self, DbPool};
+
+/// Settings holding the limits of forwarded requests
+///
+/// A setting named `<setting>.<plugin_id>` overrides the default for one
+/// plugin and `<setting>.<plugin_id>.<segment>` for the requests under one
+/// top-level path segment of its route.
+pub const TIMEOUT_SETTING: &str = "plugin_http_timeout_ms";
+pub const MAX_REQUEST_SETTING: &str = "plugin_http_max_request_bytes";
+pub const MAX_RESPONSE_SETTING: &str = "plugin_http_max_response_bytes";
+
+/// Largest body size limit that takes effect
+///
+/// Bodies travel inside one message on the plugin socket, which also carries
+/// the headers and the JSON envelope, so a larger setting would let through
+/// requests and responses the protocol rejects.
+pub const MAX_BODY_BYTES: usize = MAX_MESSAGE_SIZE - 1024 * 1024;
+
+/// Settings of the circuit breaker, overridable per plugin only
+pub const BREAKER_FAILURES_SETTING: &str = "plugin_circuit_failures";
+pub const BREAKER_OPEN_SETTING: &str = "plugin_circuit_open_secs";
+
+/// When a plugin's circuit opens and how long it stays open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakerPolicy {
+    /// Consecutive failed or timed out requests that open the circuit
+    pub failure_threshold: u32,
+    /// How long requests are rejected before a probe is let through
+    pub open_for: Duration,
+}
+
+impl Default for BreakerPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Limits applied to a request forwarded to a plugin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpLimits {
+    /// Time the plugin has to answer
+    pub timeout: Duration,
+    /// Largest request body accepted
+    pub max_request_bytes: usize,
+    /// Largest response body passed on
+    pub max_response_bytes: usize,
+    pub breaker: BreakerPolicy,
+}
+
+impl Default for HttpLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_request_bytes: 10 * 1024 * 1024,
+            max_response_bytes: 10 * 1024 * 1024,
+            breaker: BreakerPolicy::default(),
+        }
+    }
+}
+
+impl HttpLimits {
+    /// Load the limits of a request from settings
+    ///
+    /// Body limits are capped at [`MAX_BODY_BYTES`].
+    ///
+    /// # Arguments
+    /// * `pool` - Database holding the settings
+    /// * `plugin_id` - Plugin the request is forwarded to
+    /// * `segment` - First segment of the path below the plugin's route,
+    ///   empty for the route itself
+    pub async fn load(pool: &DbPool, plugin_id: &str, segment: &str) -> Self {
+        let defaults = Self::default();
+        let mut route_scopes = Vec::with_capacity(2);
+        if !segment.is_empty() {
+            route_scopes.push(format!("{}.{}", plugin_id, segment));
+        }
+        route_scopes.push(plugin_id.to_string());
+        let plugin_scopes = [plugin_id.to_string()];
+
+        let timeout = setting(pool, TIMEOUT_SETTING, &route_scopes)
+            .await
+            .map_or(defaults.timeout, Duration::from_millis);
+        let max_request_bytes = setting(pool, MAX_REQUEST_SETTING, &route_scopes)
+            .await
+            .map_or(defaults.max_request_bytes, |v| v as usize)
+            .min(MAX_BODY_BYTES);
+        let max_response_bytes = setting(pool, MAX_RESPONSE_SETTING, &route_scopes)
+            .await
+            .map_or(defaults.max_response_bytes, |v| v as usize)
+            .min(MAX_BODY_BYTES);
+        let failure_threshold = setting(pool, BREAKER_FAILURES_SETTING, &plugin_scopes)
+            .await
+            .map_or(defaults.breaker.failure_threshold, |v| {
+                v.min(u32::MAX as u64) as u32
+            });
+        let open_for = setting(pool, BREAKER_OPEN_SETTING, &plugin_scopes)
+            .await
+            .map_or(defaults.breaker.open_for, Duration::from_secs);
+
+        Self {
+            timeout,
+            max_request_bytes,
+            max_response_bytes,
+            breaker: BreakerPolicy {
+                failure_threshold,
+                open_for,
+            },
+        }
+    }
+}
+
+/// Read a positive integer setting, most specific scope first
+async fn setting(pool: &DbPool, name: &str, scopes: &[String]) -> Option<u64> {
+    let keys = scopes
+        .iter()
+        .map(|scope| format!("{}.{}", name, scope))
+        .chain(std::iter::once(name.to_string()));
+    for key in keys {
+        let value = db::get_setting(pool, &key).await.ok().flatten();
+        if let Some(value) = value.and_then(|v| v.trim().parse::<u64>().ok()) {
+            if value > 0 {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+/// Why a forwarded request was not answered by the plugin
+#[derive(Debug)]
+pub enum ForwardError {
+    /// The plugin's circuit is open
+    CircuitOpen { retry_after: Duration },
+    /// The plugin did not answer in time
+    Timeout(Duration),
+    /// The plugin's response body exceeds the limit
+    ResponseTooLarge { size: usize, limit: usize },
+}
+
+impl std::fmt::Display for ForwardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForwardError::CircuitOpen { retry_after } => write!(
+                f,
+                "Circuit open, retry after {}s",
+                retry_after_secs(*retry_after)
+            ),
+            ForwardError::Timeout(timeout) => {
+                write!(f, "Plugin response timeout after {}ms", timeout.as_millis())
+            }
+            ForwardError::ResponseTooLarge { size, limit } => write!(
+                f,
+                "Plugin response of {} bytes exceeds the limit of {} bytes",
+                size, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ForwardError {}
+
+/// Whole seconds to put in a `Retry-After` header, at least 1
+pub fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_millis().div_ceil(1000).max(1) as u64
+}
+
+/// State of a plugin's circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are forwarded
+    Closed,
+    /// Requests are rejected without reaching the plugin
+    Open,
+    /// A single probe request decides whether the circuit closes
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Plugin event recorded when the circuit enters this state
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "circuit_closed",
+            CircuitState::Open => "circuit_opened",
+            CircuitState::HalfOpen => "circuit_half_open",
+        }
+    }
+}
+
+/// Change of a circuit's state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub state: CircuitState,
+    /// Consecutive failures when the transition happened
+    pub failures: u32,
+}
+
+/// Whether a request may be forwarded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Admission {
+    Allowed,
+    Rejected { retry_after: Duration },
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: CircuitState,
+    failures: u32,
+    opened_at: Instant,
+    // Start of the probe in flight while half-open
+    probe_started: Option<Instant>,
+}
+
+/// Circuit breaker guarding a plugin's HTTP requests
+///
+/// The circuit opens after `failure_threshold` consecutive failures. While
+/// open, requests are rejected until `open_for` has passed; then one probe
+/// is let through, and its outcome closes or reopens the circuit.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    inner: Mutex<BreakerInner>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(BreakerInner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: Instant::now(),
+                probe_started: None,
+            }),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Current state of the circuit
+    #[cfg(test)]
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).state
+    }
+
+    /// Decide whether a request may be forwarded
+    ///
+    /// # Returns
+    /// The decision, and the transition to half-open when the request is
+    /// the probe
+    pub fn admit(&self, policy: &BreakerPolicy, now: Instant) -> (Admission, Option<Transition>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match inner.state {
+            CircuitState::Closed => (Admission::Allowed, None),
+            CircuitState::Open => {
+                let reopens = inner.opened_at + policy.open_for;
+                if now < reopens {
+                    return (
+                        Admission::Rejected {
+                            retry_after: reopens - now,
+                        },
+                        None,
+                    );
+                }
+                inner.state = CircuitState::HalfOpen;
+                inner.probe_started = Some(now);
+                let transition = Transition {
+                    state: CircuitState::HalfOpen,
+                    failures: inner.failures,
+                };
+                (Admission::Allowed, Some(transition))
+            }
+            CircuitState::HalfOpen => {
+                // A probe abandoned by its client never reports back
+                let probing = inner
+                    .probe_started
+                    .is_some_and(|started| now < started + policy.open_for);
+                if probing {
+                    return (
+                        Admission::Rejected {
+                            retry_after: Duration::from_secs(1),
+                        },
+                        None,
+                    );
+                }
+                inner.probe_started = Some(now);
+                (Admission::Allowed, None)
+            }
+        }
+    }
+
+    /// Record the outcome of a forwarded request
+    ///
+    /// # Returns
+    /// The transition it caused, if any
+    pub fn record(
+        &self,
+        policy: &BreakerPolicy,
+        success: bool,
+        now: Instant,
+    ) -> Option<Transition> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if success {
+            inner.failures = 0;
+            if inner.state == CircuitState::Closed {
+                return None;
+            }
+            inner.state = CircuitState::Closed;
+            inner.probe_started = None;
+            return Some(Transition {
+                state: CircuitState::Closed,
+                failures: 0,
+            });
+        }
+
+        inner.failures = inner.failures.saturating_add(1);
+        let opens = match inner.state {
+            CircuitState::Closed => inner.failures >= policy.failure_threshold,
+            CircuitState::HalfOpen => true,
+            // Requests admitted before the circuit opened
+            CircuitState::Open => false,
+        };
+        if !opens {
+            return None;
+        }
+        inner.state = CircuitState::Open;
+        inner.opened_at = now;
+        inner.probe_started = None;
+        Some(Transition {
+            state: CircuitState::Open,
+            failures: inner.failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let policy = BreakerPolicy {
+            failure_threshold: 3,
+            open_for: Duration::from_secs(10),
+        };
+        let breaker = CircuitBreaker::default();
+        let start = Instant::now();
+
+        // Successes reset the count of consecutive failures
+        assert_eq!(breaker.record(&policy, false, start), None);
+        assert_eq!(breaker.record(&policy, false, start), None);
+        assert_eq!(breaker.record(&policy, true, start), None);
+        assert_eq!(breaker.record(&policy, false, start), None);
+        assert_eq!(breaker.record(&policy, false, start), None);
+        let opened = breaker.record(&policy, false, start).unwrap();
+        assert_eq!(opened.state, CircuitState::Open);
+        assert_eq!(opened.failures, 3);
+
+        // Rejected while open, with the time left
+        let (admission, transition) = breaker.admit(&policy, start + Duration::from_secs(4));
+        assert_eq!(
+            admission,
+            Admission::Rejected {
+                retry_after: Duration::from_secs(6)
+            }
+        );
+        assert_eq!(transition, None);
+
+        // One probe once the open period is over; a failed probe reopens
+        let probe_at = start + Duration::from_secs(10);
+        let (admission, transition) = breaker.admit(&policy, probe_at);
+        assert_eq!(admission, Admission::Allowed);
+        assert_eq!(transition.unwrap().state, CircuitState::HalfOpen);
+        assert!(matches!(
+            breaker.admit(&policy, probe_at).0,
+            Admission::Rejected { .. }
+        ));
+        let reopened = breaker.record(&policy, false, probe_at).unwrap();
+        assert_eq!(reopened.state, CircuitState::Open);
+        assert!(matches!(
+            breaker.admit(&policy, probe_at + Duration::from_secs(9)).0,
+            Admission::Rejected { .. }
+        ));
+
+        // A successful probe closes the circuit
+        let probe_at = probe_at + Duration::from_secs(10);
+        assert_eq!(breaker.admit(&policy, probe_at).0, Admission::Allowed);
+        let closed = breaker.record(&policy, true, probe_at).unwrap();
+        assert_eq!(closed.state, CircuitState::Closed);
+        assert_eq!(breaker.admit(&policy, probe_at).0, Admission::Allowed);
+
+        // An abandoned probe is replaced after the open period
+        for _ in 0..3 {
+            breaker.record(&policy, false, probe_at);
+        }
+        let probe_at = probe_at + Duration::from_secs(10);
+        assert_eq!(breaker.admit(&policy, probe_at).0, Admission::Allowed);
+        assert_eq!(
+            breaker.admit(&policy, probe_at + Duration::from_secs(10)).0,
+            Admission::Allowed
+        );
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert_eq!(retry_after_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_secs(Duration::ZERO), 1);
+    }
+
+    #[tokio::test]
+    async fn test_limits_from_settings() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT)",
+            [],
+        )
+        .unwrap();
+        let pool: DbPool = Arc::new(tokio::sync::Mutex::new(conn));
+        assert_eq!(
+            HttpLimits::load(&pool, "weather", "upload").await,
+            HttpLimits::default()
+        );
+
+        for (key, value) in [
+            (TIMEOUT_SETTING, "5000"),
+            ("plugin_http_timeout_ms.weather", "10000"),
+            ("plugin_http_timeout_ms.weather.upload", "60000"),
+            ("plugin_http_max_request_bytes.weather.upload", "104857600"),
+            ("plugin_http_max_response_bytes.weather", "0"),
+            ("plugin_circuit_failures.weather", "2"),
+            ("plugin_circuit_failures.weather.upload", "9"),
+        ] {
+            db::set_setting(&pool, key, value).await.unwrap();
+        }
+
+        let upload = HttpLimits::load(&pool, "weather", "upload").await;
+        assert_eq!(upload.timeout, Duration::from_secs(60));
+        // More than a socket message can carry is capped
+        assert_eq!(upload.max_request_bytes, MAX_BODY_BYTES);
+        // 0 falls back to the default, route scopes don't apply to the breaker
+        assert_eq!(upload.max_response_bytes, 10 * 1024 * 1024);
+        assert_eq!(upload.breaker.failure_threshold, 2);
+
+        let other = HttpLimits::load(&pool, "weather", "").await;
+        assert_eq!(other.timeout, Duration::from_secs(10));
+        assert_eq!(other.max_request_bytes, 10 * 1024 * 1024);
+
+        let other_plugin = HttpLimits::load(&pool, "clock", "upload").await;
+        assert_eq!(other_plugin.timeout, Duration::from_secs(5));
+        assert_eq!(other_plugin.breaker, BreakerPolicy::default());
+    }
+}
@@ -0,0 +1,355 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use toru_plugin_api::PluginMetadata;
+
+use super::metadata_cache::{self, validate_metadata};
+
+/// Manifest file names, in lookup order
+const MANIFEST_FILES: [&str; 2] = ["plugin.toml", "manifest.json"];
+
+/// Records which archive a package directory was extracted from
+const SOURCE_MARKER: &str = ".source-sha256";
+
+/// Declarative description of a plugin package
+///
+/// Read from `plugin.toml` or `manifest.json` at the package root, so
+/// metadata is known without executing any plugin code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub icon: String,
+    pub route: String,
+    /// Executable, relative to the package root
+    pub entrypoint: String,
+    /// Frontend assets directory, relative to the package root
+    #[serde(default = "default_assets")]
+    pub assets: String,
+    /// SQL migrations directory, relative to the package root
+    #[serde(default = "default_migrations")]
+    pub migrations: String,
+}
+
+fn default_assets() -> String {
+    "assets".to_string()
+}
+
+fn default_migrations() -> String {
+    "migrations".to_string()
+}
+
+impl PluginManifest {
+    /// Metadata equivalent to what a binary would report on `--metadata`
+    pub fn metadata(&self) -> PluginMetadata {
+        PluginMetadata {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            author: self.author.clone(),
+            icon: self.icon.clone(),
+            route: self.route.clone(),
+        }
+    }
+}
+
+/// A plugin package loaded from disk
+#[derive(Debug, Clone)]
+pub struct PluginPackage {
+    pub manifest: PluginManifest,
+    pub entrypoint: PathBuf,
+    /// Assets directory, if the package ships one
+    pub assets_dir: Option<PathBuf>,
+    /// SQL migration files in the order they should be applied
+    pub migrations: Vec<PathBuf>,
+}
+
+/// Whether a directory contains a package manifest
+pub fn is_package_dir(dir: &Path) -> bool {
+    MANIFEST_FILES.iter().any(|name| dir.join(name).is_file())
+}
+
+/// Whether a file is a package archive (`.tar`, `.tar.gz` or `.tgz`)
+pub fn is_package_archive(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name.ends_with(".tar") || name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Resolve a manifest-relative path, rejecting anything that leaves the package
+fn package_path(root: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(anyhow::anyhow!(
+            "Path {:?} must stay inside the package",
+            relative
+        ));
+    }
+    Ok(root.join(relative))
+}
+
+/// Load and validate a package directory
+///
+/// # Arguments
+/// * `root` - Package directory containing the manifest
+pub fn load_package(root: &Path) -> Result<PluginPackage> {
+    let manifest: PluginManifest = if root.join("plugin.toml").is_file() {
+        let content =
+            fs::read_to_string(root.join("plugin.toml")).context("Failed to read plugin.toml")?;
+        toml::from_str(&content).context("Failed to parse plugin.toml")?
+    } else {
+        let content = fs::read_to_string(root.join("manifest.json"))
+            .context("Failed to read manifest.json")?;
+        serde_json::from_str(&content).context("Failed to parse manifest.json")?
+    };
+
+    validate_metadata(&manifest.metadata())?;
+
+    let entrypoint = package_path(root, &manifest.entrypoint)?;
+    let canonical_root = root.canonicalize()?;
+    let canonical_entrypoint = entrypoint
+        .canonicalize()
+        .with_context(|| format!("Entrypoint {:?} not found", manifest.entrypoint))?;
+    if !canonical_entrypoint.starts_with(&canonical_root) || !canonical_entrypoint.is_file() {
+        return Err(anyhow::anyhow!(
+            "Entrypoint {:?} must be a file inside the package",
+            manifest.entrypoint
+        ));
+    }
+
+    let assets_dir = Some(package_path(root, &manifest.assets)?).filter(|dir| dir.is_dir());
+
+    let migrations_dir = package_path(root, &manifest.migrations)?;
+    let mut migrations: Vec<PathBuf> = fs::read_dir(&migrations_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("sql"))
+                .collect()
+        })
+        .unwrap_or_default();
+    migrations.sort();
+
+    Ok(PluginPackage {
+        manifest,
+        entrypoint,
+        assets_dir,
+        migrations,
+    })
+}
+
+/// Extract a package archive into `packages_dir/<archive stem>`
+///
+/// The archive is only unpacked again when its content changes.
+///
+/// # Arguments
+/// * `archive` - `.tar`, `.tar.gz` or `.tgz` file
+/// * `packages_dir` - Directory holding extracted packages
+///
+/// # Returns
+/// The package root (the extracted directory, or its single top-level
+/// directory if the archive wraps everything in one)
+pub fn extract_archive(archive: &Path, packages_dir: &Path) -> Result<PathBuf> {
+    let name = archive
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid archive name")?;
+    let stem = name
+        .trim_end_matches(".tar.gz")
+        .trim_end_matches(".tgz")
+        .trim_end_matches(".tar");
+    let target = packages_dir.join(stem);
+
+    let hash = metadata_cache::fingerprint(archive)?.sha256;
+    let up_to_date = fs::read_to_string(target.join(SOURCE_MARKER))
+        .map(|marker| marker.trim() == hash)
+        .unwrap_or(false);
+
+    if !up_to_date {
+        debug!("Extracting plugin package {:?}", archive);
+
+        // Unpack next to the target and swap, so a failed extraction
+        // never leaves a half-written package behind
+        let staging = packages_dir.join(format!(".{}.extracting", stem));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+
+        let file = fs::File::open(archive).context("Failed to open package archive")?;
+        let result = if name.ends_with(".tar") {
+            unpack(tar::Archive::new(file), &staging)
+        } else {
+            unpack(
+                tar::Archive::new(flate2::read::GzDecoder::new(file)),
+                &staging,
+            )
+        };
+        if let Err(e) = result {
+            fs::remove_dir_all(&staging).ok();
+            return Err(e);
+        }
+
+        fs::write(staging.join(SOURCE_MARKER), &hash)?;
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::rename(&staging, &target)?;
+    }
+
+    if is_package_dir(&target) {
+        return Ok(target);
+    }
+
+    // Archives created with `tar czf pkg.tgz my-plugin/` have one top-level directory
+    let entries: Vec<PathBuf> = fs::read_dir(&target)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name() != Some(SOURCE_MARKER.as_ref()))
+        .collect();
+    match entries.as_slice() {
+        [single] if single.is_dir() && is_package_dir(single) => Ok(single.clone()),
+        _ => Err(anyhow::anyhow!(
+            "Archive {:?} contains no manifest",
+            archive
+        )),
+    }
+}
+
+/// Unpack an archive, refusing links and entries that escape the target
+fn unpack<R: std::io::Read>(mut archive: tar::Archive<R>, target: &Path) -> Result<()> {
+    archive.set_preserve_permissions(true);
+
+    for entry in archive
+        .entries()
+        .context("Failed to read package archive")?
+    {
+        let mut entry = entry.context("Failed to read package archive")?;
+        let kind = entry.header().entry_type();
+        if kind.is_symlink() || kind.is_hard_link() {
+            return Err(anyhow::anyhow!("Package archives may not contain links"));
+        }
+        // unpack_in skips entries that would land outside the target
+        if !entry.unpack_in(target)? {
+            return Err(anyhow::anyhow!(
+                "Archive entry {:?} escapes the package",
+                entry.path().unwrap_or_default()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a requested asset path inside an assets directory
+///
+/// # Returns
+/// None if the path is invalid, escapes the directory or does not exist
+pub fn resolve_asset(assets_dir: &Path, requested: &str) -> Option<PathBuf> {
+    let path = package_path(assets_dir, requested.trim_start_matches('/')).ok()?;
+    let canonical = path.canonicalize().ok()?;
+    if canonical.starts_with(assets_dir.canonicalize().ok()?) && canonical.is_file() {
+        Some(canonical)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_package(root: &Path) {
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::create_dir_all(root.join("migrations")).unwrap();
+        fs::write(
+            root.join("plugin.toml"),
+            r#"
+id = "packaged"
+name = "Packaged"
+version = "1.2.0"
+icon = "📦"
+route = "/packaged"
+entrypoint = "bin/packaged"
+"#,
+        )
+        .unwrap();
+        fs::write(root.join("bin/packaged"), "#!/bin/bash\nsleep 3600\n").unwrap();
+        fs::write(root.join("assets/bundle.js"), "console.log(1)").unwrap();
+        fs::write(root.join("migrations/002_more.sql"), "").unwrap();
+        fs::write(root.join("migrations/001_init.sql"), "").unwrap();
+    }
+
+    #[test]
+    fn test_load_package() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("packaged");
+        write_package(&root);
+
+        let package = load_package(&root).unwrap();
+        assert_eq!(package.manifest.metadata().version, "1.2.0");
+        assert_eq!(package.entrypoint, root.join("bin/packaged"));
+        assert_eq!(package.assets_dir, Some(root.join("assets")));
+        assert_eq!(
+            package.migrations,
+            vec![
+                root.join("migrations/001_init.sql"),
+                root.join("migrations/002_more.sql")
+            ]
+        );
+
+        let assets = package.assets_dir.unwrap();
+        assert!(resolve_asset(&assets, "bundle.js").is_some());
+        assert!(resolve_asset(&assets, "../plugin.toml").is_none());
+        assert!(resolve_asset(&assets, "missing.js").is_none());
+    }
+
+    #[test]
+    fn test_entrypoint_must_stay_inside_package() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("packaged");
+        write_package(&root);
+        let manifest = fs::read_to_string(root.join("plugin.toml"))
+            .unwrap()
+            .replace("bin/packaged", "../outside");
+        fs::write(root.join("plugin.toml"), manifest).unwrap();
+
+        assert!(load_package(&root).is_err());
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("src").join("packaged");
+        write_package(&source);
+
+        let archive = temp_dir.path().join("packaged.tgz");
+        let file = fs::File::create(&archive).unwrap();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        ));
+        builder.append_dir_all("packaged", &source).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let packages_dir = temp_dir.path().join(".packages");
+        let root = extract_archive(&archive, &packages_dir).unwrap();
+        assert_eq!(root, packages_dir.join("packaged").join("packaged"));
+        assert_eq!(load_package(&root).unwrap().manifest.id, "packaged");
+
+        // Unchanged archives are not unpacked again
+        fs::write(root.join("marker"), "").unwrap();
+        extract_archive(&archive, &packages_dir).unwrap();
+        assert!(root.join("marker").exists());
+    }
+}
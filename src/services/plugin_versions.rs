@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use toru_plugin_api::PluginMetadata;
+
+use crate::db::{self, DbPool};
+
+/// Number of previous versions kept per plugin when the setting is missing
+pub const DEFAULT_RETAINED_VERSIONS: usize = 3;
+
+/// Settings key overriding the number of retained versions
+const RETAINED_VERSIONS_SETTING: &str = "plugin_versions_retained";
+
+/// A previous plugin binary kept for rollback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginVersion {
+    /// Identifier of the archived copy (unix milliseconds at archive time)
+    pub version_id: String,
+    pub archived_at: DateTime<Utc>,
+    /// Metadata reported by the binary when it was installed
+    pub metadata: PluginMetadata,
+    /// Size of the archived binary in bytes
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// Read the number of versions to retain from settings
+pub async fn retained_versions(pool: &DbPool) -> usize {
+    db::get_setting(pool, RETAINED_VERSIONS_SETTING)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_RETAINED_VERSIONS)
+}
+
+/// Directory holding the archived versions of one plugin
+fn plugin_dir(versions_dir: &Path, plugin_id: &str) -> PathBuf {
+    versions_dir.join(plugin_id)
+}
+
+/// Version IDs are generated by us, so anything else is rejected
+fn is_valid_version_id(version_id: &str) -> bool {
+    !version_id.is_empty()
+        && version_id.len() <= 20
+        && version_id.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Archive the currently installed binary of a plugin
+///
+/// # Arguments
+/// * `versions_dir` - Root of the version archive (`plugins/.versions`)
+/// * `plugin_id` - Plugin identifier
+/// * `binary_path` - Binary to archive (copied, not moved)
+/// * `metadata` - Metadata of the binary being archived
+/// * `retain` - Number of versions to keep; older ones are removed (0 disables archiving)
+///
+/// # Returns
+/// The archived version, or None if archiving is disabled
+pub fn archive(
+    versions_dir: &Path,
+    plugin_id: &str,
+    binary_path: &Path,
+    metadata: &PluginMetadata,
+    retain: usize,
+) -> Result<Option<PluginVersion>> {
+    if retain == 0 {
+        return Ok(None);
+    }
+
+    let dir = plugin_dir(versions_dir, plugin_id);
+    fs::create_dir_all(&dir).context("Failed to create versions directory")?;
+
+    // Millisecond IDs can collide when archiving in quick succession
+    let archived_at = Utc::now();
+    let mut millis = archived_at.timestamp_millis();
+    while dir.join(format!("{}.binary", millis)).exists() {
+        millis += 1;
+    }
+    let version_id = millis.to_string();
+
+    let archived_binary = dir.join(format!("{}.binary", version_id));
+    let size = fs::copy(binary_path, &archived_binary).context("Failed to archive binary")?;
+
+    let version = PluginVersion {
+        version_id: version_id.clone(),
+        archived_at,
+        metadata: metadata.clone(),
+        size,
+    };
+    fs::write(
+        dir.join(format!("{}.json", version_id)),
+        serde_json::to_string_pretty(&version)? + "\n",
+    )
+    .context("Failed to write version metadata")?;
+
+    debug!(
+        "Archived plugin {} v{} as {}",
+        plugin_id, metadata.version, version_id
+    );
+
+    prune(versions_dir, plugin_id, retain)?;
+    Ok(Some(version))
+}
+
+/// List archived versions of a plugin, newest first
+pub fn list(versions_dir: &Path, plugin_id: &str) -> Result<Vec<PluginVersion>> {
+    let dir = plugin_dir(versions_dir, plugin_id);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read versions directory"),
+    };
+
+    let mut versions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+
+        let version: PluginVersion = match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| serde_json::from_str(&content).map_err(Into::into))
+        {
+            Ok(version) => version,
+            Err(e) => {
+                warn!("Skipping unreadable version entry {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        // Ignore entries whose binary is gone
+        if dir.join(format!("{}.binary", version.version_id)).exists() {
+            versions.push(version);
+        }
+    }
+
+    // IDs increase monotonically even when archived within the same millisecond
+    versions.sort_by_key(|v| std::cmp::Reverse(v.version_id.parse::<u64>().unwrap_or(0)));
+    Ok(versions)
+}
+
+/// Find an archived version, defaulting to the most recent one
+///
+/// # Returns
+/// The version and the path of its archived binary
+pub fn find(
+    versions_dir: &Path,
+    plugin_id: &str,
+    version_id: Option<&str>,
+) -> Result<(PluginVersion, PathBuf)> {
+    if let Some(version_id) = version_id {
+        if !is_valid_version_id(version_id) {
+            return Err(anyhow::anyhow!("Invalid version ID"));
+        }
+    }
+
+    let version = list(versions_dir, plugin_id)?
+        .into_iter()
+        .find(|v| version_id.is_none_or(|id| v.version_id == id))
+        .context("No archived version found")?;
+
+    let binary = plugin_dir(versions_dir, plugin_id).join(format!("{}.binary", version.version_id));
+    Ok((version, binary))
+}
+
+/// Remove an archived version
+pub fn remove(versions_dir: &Path, plugin_id: &str, version_id: &str) -> Result<()> {
+    if !is_valid_version_id(version_id) {
+        return Err(anyhow::anyhow!("Invalid version ID"));
+    }
+
+    let dir = plugin_dir(versions_dir, plugin_id);
+    fs::remove_file(dir.join(format!("{}.binary", version_id))).ok();
+    fs::remove_file(dir.join(format!("{}.json", version_id))).ok();
+    Ok(())
+}
+
+/// Remove all but the `retain` most recent versions of a plugin
+fn prune(versions_dir: &Path, plugin_id: &str, retain: usize) -> Result<()> {
+    for version in list(versions_dir, plugin_id)?.iter().skip(retain) {
+        debug!(
+            "Removing old version {} of plugin {}",
+            version.version_id, plugin_id
+        );
+        remove(versions_dir, plugin_id, &version.version_id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn metadata(version: &str) -> PluginMetadata {
+        PluginMetadata {
+            id: "versioned".to_string(),
+            name: "Versioned".to_string(),
+            version: version.to_string(),
+            author: None,
+            icon: "📦".to_string(),
+            route: "/versioned".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_archive_prunes_oldest() {
+        let temp_dir = TempDir::new().unwrap();
+        let versions_dir = temp_dir.path().join(".versions");
+        let binary = temp_dir.path().join("versioned.binary");
+
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            fs::write(&binary, version).unwrap();
+            archive(&versions_dir, "versioned", &binary, &metadata(version), 2)
+                .unwrap()
+                .unwrap();
+        }
+
+        let versions = list(&versions_dir, "versioned").unwrap();
+        let numbers: Vec<&str> = versions
+            .iter()
+            .map(|v| v.metadata.version.as_str())
+            .collect();
+        assert_eq!(numbers, vec!["1.2.0", "1.1.0"]);
+
+        let (latest, path) = find(&versions_dir, "versioned", None).unwrap();
+        assert_eq!(latest.metadata.version, "1.2.0");
+        assert_eq!(fs::read_to_string(path).unwrap(), "1.2.0");
+
+        let older = &versions[1].version_id;
+        let (found, _) = find(&versions_dir, "versioned", Some(older)).unwrap();
+        assert_eq!(found.metadata.version, "1.1.0");
+        assert!(find(&versions_dir, "versioned", Some("../x")).is_err());
+    }
+
+    #[test]
+    fn test_archive_disabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = temp_dir.path().join("versioned.binary");
+        fs::write(&binary, "x").unwrap();
+
+        let versions_dir = temp_dir.path().join(".versions");
+        assert!(
+            archive(&versions_dir, "versioned", &binary, &metadata("1.0.0"), 0)
+                .unwrap()
+                .is_none()
+        );
+        assert!(list(&versions_dir, "versioned").unwrap().is_empty());
+    }
+}
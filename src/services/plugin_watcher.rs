@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, error, info, warn};
+
+use super::plugin_package;
+use super::plugins::PluginSupervisor;
+
+/// Quiet period after the last filesystem event before rescanning,
+/// so a binary being copied in is only picked up once it is complete
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Whether a changed path can affect the set of plugins
+///
+/// `.binary` and `.wasm` files and package archives directly inside the plugins
+/// directory count, as does anything inside a package directory. Hidden
+/// entries (staging, version archives, extracted packages, metadata) are
+/// managed by the supervisor itself.
+fn is_plugin_change(plugins_dir: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(plugins_dir) else {
+        return false;
+    };
+    let mut components = relative.components();
+    let Some(first) = components.next() else {
+        return false;
+    };
+    let first = first.as_os_str().to_string_lossy();
+    if first.starts_with('.') {
+        return false;
+    }
+
+    if components.next().is_some() {
+        // Inside a top-level directory, which may be a package
+        return true;
+    }
+    first.ends_with(".binary")
+        || first.ends_with(".wasm")
+        || plugin_package::is_package_archive(Path::new(&*first))
+}
+
+/// Watch the plugins directory and rescan when plugin binaries change
+///
+/// # Arguments
+/// * `supervisor` - Supervisor to apply changes to
+/// * `plugins_dir` - Directory to watch
+///
+/// # Returns
+/// The watcher; dropping it stops watching
+pub fn spawn_plugin_watcher(
+    supervisor: Arc<Mutex<PluginSupervisor>>,
+    plugins_dir: PathBuf,
+) -> Result<RecommendedWatcher> {
+    // Events report absolute paths, so compare against the canonical directory
+    let plugins_dir = plugins_dir
+        .canonicalize()
+        .context("Failed to resolve plugins directory")?;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let watched_dir = plugins_dir.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                if event
+                    .paths
+                    .iter()
+                    .any(|path| is_plugin_change(&watched_dir, path))
+                {
+                    let _ = tx.send(());
+                }
+            }
+            Err(e) => warn!("Plugin directory watch error: {}", e),
+        })
+        .context("Failed to create plugin directory watcher")?;
+
+    watcher
+        .watch(&plugins_dir, RecursiveMode::Recursive)
+        .context("Failed to watch plugins directory")?;
+
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            // Wait until events stop arriving
+            while let Ok(Some(())) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {}
+
+            debug!("Plugin binaries changed, rescanning");
+            let mut supervisor = supervisor.lock().await;
+            match supervisor.rescan().await {
+                Ok(report) => {
+                    if !report.added.is_empty()
+                        || !report.removed.is_empty()
+                        || !report.replaced.is_empty()
+                    {
+                        info!(
+                            "Plugin rescan: added {:?}, removed {:?}, replaced {:?}",
+                            report.added, report.removed, report.replaced
+                        );
+                    }
+                }
+                Err(e) => error!("Plugin rescan failed: {}", e),
+            }
+        }
+    });
+
+    info!("Watching {:?} for plugin changes", plugins_dir);
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_plugin_change() {
+        let dir = Path::new("/srv/plugins");
+        assert!(is_plugin_change(dir, Path::new("/srv/plugins/a.binary")));
+        assert!(is_plugin_change(dir, Path::new("/srv/plugins/a.wasm")));
+        assert!(is_plugin_change(dir, Path::new("/srv/plugins/a.tgz")));
+        assert!(is_plugin_change(
+            dir,
+            Path::new("/srv/plugins/a/plugin.toml")
+        ));
+        assert!(!is_plugin_change(dir, Path::new("/srv/plugins/a.sock")));
+        assert!(!is_plugin_change(
+            dir,
+            Path::new("/srv/plugins/.staging/a.binary")
+        ));
+        assert!(!is_plugin_change(
+            dir,
+            Path::new("/srv/plugins/.versions/a/1.binary")
+        ));
+    }
+}
@@ -1,27 +1,116 @@
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::net::UnixStream;
 use tokio::process::Child;
+use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
 use toru_plugin_api::{HttpMessageResponse, HttpRequest, Message, PluginMetadata};
 
-use super::logging::{LogLevel, PluginLogger, SupervisorLogger};
-use crate::db::DbPool;
+use super::log_rotation::LogRetention;
+use super::log_sinks::{self, LogForwarder, SinkStatus};
+use super::logging::{self, AccessLogger, LogLevel, LogStream, PluginLogger, SupervisorLogger};
+use super::metadata_cache::{self, MetadataCache};
+use super::plugin_data;
+use super::plugin_database::{self, SqlitePluginDatabase};
+use super::plugin_http::{Admission, CircuitBreaker, CircuitState, ForwardError, HttpLimits};
+use super::plugin_package::{self, PluginPackage};
+use super::plugin_versions::{self, PluginVersion};
+use super::request_metrics::RequestMetrics;
+#[cfg(feature = "wasm")]
+use super::wasm_runtime::{self, WasmLimits, WasmPlugin, WasmRuntime};
+use crate::db::{self, DbPool};
+
+/// How a plugin is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(not(feature = "wasm"), allow(dead_code))]
+pub enum PluginKind {
+    /// Separate process talking over a Unix socket
+    Process,
+    /// In-process WebAssembly instance (requires the `wasm` feature)
+    Wasm,
+}
 
 /// Represents a running plugin process
 #[derive(Debug)]
 pub struct PluginProcess {
     pub id: String,
+    pub kind: PluginKind,
     pub process: Option<Child>,
     pub socket_path: String,
     pub enabled: bool,
     pub metadata: Option<PluginMetadata>,
     pub pid: Option<u32>,
+    /// Socket the core listens on for messages initiated by the plugin
+    pub host_socket_path: String,
+    pub host_listener: Option<tokio::task::JoinHandle<()>>,
+    pub binary_path: PathBuf,
+    /// Private directory for the plugin's files, passed in `init`
+    pub data_dir: Option<PathBuf>,
+    /// Number of HTTP requests currently being forwarded to this process
+    pub in_flight: Arc<AtomicUsize>,
+    /// Sandboxed instance of a WebAssembly plugin
+    #[cfg(feature = "wasm")]
+    pub wasm: Option<Arc<WasmPlugin>>,
+}
+
+impl PluginProcess {
+    /// Whether the plugin is currently able to serve requests
+    pub fn is_running(&self) -> bool {
+        #[cfg(feature = "wasm")]
+        if self.wasm.is_some() {
+            return true;
+        }
+        self.process.is_some()
+    }
+}
+
+/// Handle for forwarding HTTP requests to a plugin without holding the supervisor lock
+///
+/// The handle pins the process that was routed to at lookup time, so a
+/// request started before an upgrade finishes on the old process.
+#[derive(Debug, Clone)]
+pub struct HttpTarget {
+    plugin_id: String,
+    endpoint: HttpEndpoint,
+    in_flight: Arc<AtomicUsize>,
+    breaker: Arc<CircuitBreaker>,
+    // Where circuit transitions are recorded
+    supervisor_logger: Arc<SupervisorLogger>,
+    db_pool: DbPool,
+}
+
+/// Where a plugin's HTTP requests are delivered
+#[derive(Debug, Clone)]
+enum HttpEndpoint {
+    Socket(String),
+    #[cfg(feature = "wasm")]
+    Wasm(Arc<WasmPlugin>),
+}
+
+/// Decrements the in-flight counter when a forwarded request ends
+struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Changes applied by a plugins directory rescan
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RescanReport {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub replaced: Vec<String>,
 }
 
 /// Manages plugin lifecycle, including spawning, monitoring, and restarting plugins
@@ -32,12 +121,35 @@ pub struct PluginSupervisor {
     restart_counts: HashMap<String, u32>,
     plugins_dir: PathBuf,
     metadata_dir: PathBuf,
+    // Previous plugin binaries kept for rollback
+    versions_dir: PathBuf,
+    metadata_cache: MetadataCache,
+    // Archives are extracted here before loading
+    packages_dir: PathBuf,
+    // Packages found by the latest scan, by plugin ID
+    packages: std::sync::Mutex<HashMap<String, PluginPackage>>,
+    // Content hash of each discovered plugin binary, used by rescans to spot replacements
+    binary_hashes: HashMap<String, String>,
+    // Binary, package directory or archive each plugin was last discovered in
+    plugin_sources: std::sync::Mutex<HashMap<String, PathBuf>>,
+    // Private per-plugin file storage
+    data_dir: PathBuf,
+    // Private per-plugin SQL databases
+    databases_dir: PathBuf,
     sockets_dir: PathBuf,
     // Used to determine when to disable plugins after repeated crashes
     max_restarts: u32,
     instance_id: String,
     plugin_logger: Arc<PluginLogger>,
     supervisor_logger: Arc<SupervisorLogger>,
+    /// Sinks plugin and supervisor logs are forwarded to
+    log_forwarder: Arc<LogForwarder>,
+    // Kept across restarts so a failing plugin stays fenced off
+    circuit_breakers: std::sync::Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+    request_metrics: Arc<RequestMetrics>,
+    access_logger: Arc<AccessLogger>,
+    #[cfg(feature = "wasm")]
+    wasm_runtime: WasmRuntime,
     db_pool: DbPool,
 }
 
@@ -59,32 +171,65 @@ impl PluginSupervisor {
     ) -> Result<Self> {
         let plugins_dir = plugins_dir.as_ref().to_path_buf();
         let metadata_dir = plugins_dir.join(".metadata");
+        let versions_dir = plugins_dir.join(".versions");
+        let packages_dir = plugins_dir.join(".packages");
+        let data_dir = plugins_dir.join(".data");
+        let databases_dir = plugins_dir.join(".databases");
         let sockets_dir = PathBuf::from("/tmp/toru-plugins");
         let log_dir = log_dir.as_ref().to_path_buf();
 
         // Create directories if they don't exist
         fs::create_dir_all(&plugins_dir).context("Failed to create plugins directory")?;
         fs::create_dir_all(&metadata_dir).context("Failed to create metadata directory")?;
+        fs::create_dir_all(&packages_dir).context("Failed to create packages directory")?;
+        fs::create_dir_all(&data_dir).context("Failed to create data directory")?;
+        fs::create_dir_all(&databases_dir).context("Failed to create databases directory")?;
         fs::create_dir_all(&sockets_dir).context("Failed to create sockets directory")?;
 
         // Initialize loggers
-        let plugin_logger = Arc::new(PluginLogger::new(super::logging::LogConfig {
-            log_dir: log_dir.clone(),
-            ..Default::default()
-        })?);
+        let log_forwarder = Arc::new(LogForwarder::default());
+        let plugin_logger = Arc::new(
+            PluginLogger::new(super::logging::LogConfig {
+                log_dir: log_dir.clone(),
+                ..Default::default()
+            })?
+            .with_forwarder(Arc::clone(&log_forwarder)),
+        );
+
+        let supervisor_logger =
+            Arc::new(SupervisorLogger::new(&log_dir)?.with_forwarder(Arc::clone(&log_forwarder)));
 
-        let supervisor_logger = Arc::new(SupervisorLogger::new(&log_dir)?);
+        let access_logger = Arc::new(AccessLogger::new(&log_dir)?);
+
+        let metadata_cache = MetadataCache::load(metadata_dir.join("metadata-cache.json"));
+
+        #[cfg(feature = "wasm")]
+        let wasm_runtime = WasmRuntime::new(db_pool.clone(), Arc::clone(&plugin_logger))?;
 
         Ok(Self {
             plugins: HashMap::new(),
             restart_counts: HashMap::new(),
             plugins_dir,
             metadata_dir,
+            versions_dir,
+            metadata_cache,
+            packages_dir,
+            packages: std::sync::Mutex::new(HashMap::new()),
+            binary_hashes: HashMap::new(),
+            plugin_sources: std::sync::Mutex::new(HashMap::new()),
+            data_dir,
+            databases_dir,
             sockets_dir,
             max_restarts,
             instance_id,
             plugin_logger,
             supervisor_logger,
+            log_forwarder,
+            circuit_breakers: std::sync::Mutex::new(HashMap::new()),
+            request_metrics: Arc::new(RequestMetrics::default()),
+            access_logger,
+            #[cfg(feature = "wasm")]
+            wasm_runtime,
             db_pool,
         })
     }
@@ -94,7 +239,69 @@ impl PluginSupervisor {
         Arc::clone(&self.plugin_logger)
     }
 
-    /// Scan the plugins directory for .binary files and load metadata
+    /// Load the log rotation and retention policy, the log sinks and the
+    /// access log switch from settings
+    pub async fn apply_log_settings(&self) {
+        let retention = LogRetention::load(&self.db_pool).await;
+        self.plugin_logger.set_retention(retention.clone());
+        self.supervisor_logger.set_retention(retention.clone());
+        self.access_logger.set_retention(retention);
+
+        let access_log = db::get_setting(&self.db_pool, logging::ACCESS_LOG_SETTING)
+            .await
+            .ok()
+            .flatten();
+        self.access_logger
+            .set_enabled(access_log.is_some_and(|v| v.trim() == "true"));
+
+        let sinks = db::get_setting(&self.db_pool, log_sinks::LOG_SINKS_SETTING)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "[]".to_string());
+        match log_sinks::parse_sinks(&sinks).and_then(|sinks| self.log_forwarder.configure(sinks)) {
+            Ok(()) => {}
+            Err(e) => warn!("Keeping previous log sinks: {:#}", e),
+        }
+    }
+
+    /// Delivery state of the configured log sinks
+    pub fn log_sink_status(&self) -> Vec<SinkStatus> {
+        self.log_forwarder.status()
+    }
+
+    /// Delete rotated plugin and supervisor logs the retention policy no
+    /// longer keeps
+    ///
+    /// # Returns
+    /// The number of files deleted
+    pub async fn prune_logs(&self) -> Result<usize> {
+        let plugin_logs = self.plugin_logger.prune_rotated_logs().await?;
+        Ok(plugin_logs
+            + self.supervisor_logger.prune_rotated_logs()?
+            + self.access_logger.prune_rotated_logs()?)
+    }
+
+    /// Get the request counters of all plugins
+    pub fn request_metrics(&self) -> Arc<RequestMetrics> {
+        Arc::clone(&self.request_metrics)
+    }
+
+    /// Get the access logger of forwarded requests
+    pub fn access_logger(&self) -> Arc<AccessLogger> {
+        Arc::clone(&self.access_logger)
+    }
+
+    /// Scan the plugins directory for plugins and load metadata
+    ///
+    /// Plugins are either single `.binary` files (metadata read by running
+    /// `--metadata`), `.wasm` modules (metadata read inside the sandbox, only
+    /// with the `wasm` feature) or packages: directories or archives with a
+    /// manifest, whose metadata is read without executing anything.
+    ///
+    /// When a package and a single-file plugin declare the same ID, the
+    /// package is loaded and the single file is ignored, whatever order the
+    /// directory lists them in.
     ///
     /// # Returns
     /// HashMap mapping plugin_id to (binary_path, metadata)
@@ -102,6 +309,10 @@ impl PluginSupervisor {
         &self,
     ) -> Result<HashMap<String, (PathBuf, PluginMetadata)>> {
         let mut discovered = HashMap::new();
+        let mut seen = std::collections::HashSet::new();
+        let mut packages = HashMap::new();
+        let mut sources = HashMap::new();
+        let mut extracted = std::collections::HashSet::new();
 
         let entries = match fs::read_dir(&self.plugins_dir) {
             Ok(entries) => entries,
@@ -111,6 +322,14 @@ impl PluginSupervisor {
             }
         };
 
+        let canonical_plugins_dir = match self.plugins_dir.canonicalize() {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Failed to canonicalize plugins directory: {}", e);
+                return Ok(discovered);
+            }
+        };
+
         for entry in entries {
             let entry = match entry {
                 Ok(e) => e,
@@ -122,12 +341,86 @@ impl PluginSupervisor {
 
             let path = entry.path();
 
-            // Skip directories and non-.binary files
+            // Hidden entries hold supervisor state (.metadata, .versions, .packages, ...)
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+
+            let is_package = !hidden
+                && ((path.is_dir() && plugin_package::is_package_dir(&path))
+                    || (path.is_file() && plugin_package::is_package_archive(&path)));
+            if is_package {
+                if !path
+                    .canonicalize()
+                    .is_ok_and(|p| p.starts_with(&canonical_plugins_dir))
+                {
+                    warn!(
+                        "Plugin package {:?} resolves outside plugins directory, skipping",
+                        path
+                    );
+                    continue;
+                }
+
+                let loaded = if path.is_dir() {
+                    plugin_package::load_package(&path)
+                } else {
+                    plugin_package::extract_archive(&path, &self.packages_dir).and_then(|root| {
+                        if let Ok(relative) = root.strip_prefix(&self.packages_dir) {
+                            extracted.extend(
+                                relative
+                                    .components()
+                                    .next()
+                                    .map(|c| c.as_os_str().to_os_string()),
+                            );
+                        }
+                        plugin_package::load_package(&root)
+                    })
+                };
+
+                match loaded {
+                    Ok(package) => {
+                        let metadata = package.manifest.metadata();
+                        debug!(
+                            "Discovered plugin package: {} v{}",
+                            metadata.name, metadata.version
+                        );
+                        if packages.contains_key(&metadata.id) {
+                            warn!("Plugin {} is provided more than once", metadata.id);
+                        } else if let Some(other) = sources.get(&metadata.id) {
+                            warn!(
+                                "Plugin {} is provided by package {:?} and {:?}, using the package",
+                                metadata.id, path, other
+                            );
+                        }
+                        discovered.insert(
+                            metadata.id.clone(),
+                            (package.entrypoint.clone(), metadata.clone()),
+                        );
+                        sources.insert(metadata.id.clone(), path.clone());
+                        packages.insert(metadata.id, package);
+                    }
+                    Err(e) => {
+                        error!("Failed to load plugin package {:?}: {:#}", path, e);
+                    }
+                }
+                continue;
+            }
+
+            // Skip directories and files that are not plugins
             if path.is_dir() {
                 continue;
             }
 
-            if path.extension().and_then(|ext| ext.to_str()) != Some("binary") {
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            if extension == Some("wasm") && !cfg!(feature = "wasm") {
+                warn!(
+                    "Skipping WebAssembly plugin {:?}: built without the `wasm` feature",
+                    path
+                );
+                continue;
+            }
+            if !matches!(extension, Some("binary") | Some("wasm")) {
                 continue;
             }
 
@@ -145,14 +438,6 @@ impl PluginSupervisor {
                 }
             };
 
-            let canonical_plugins_dir = match self.plugins_dir.canonicalize() {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("Failed to canonicalize plugins directory: {}", e);
-                    return Ok(discovered);
-                }
-            };
-
             if !canonical_path.starts_with(&canonical_plugins_dir) {
                 warn!(
                     "Plugin {:?} resolves outside plugins directory (symlink attack?), skipping",
@@ -161,10 +446,24 @@ impl PluginSupervisor {
                 continue;
             }
 
+            seen.insert(path.clone());
+
             // Read plugin metadata
             match self.read_plugin_metadata(&path).await {
                 Ok(metadata) => {
                     debug!("Discovered plugin: {} v{}", metadata.name, metadata.version);
+                    // Packages take precedence over single files
+                    if packages.contains_key(&metadata.id) {
+                        warn!(
+                            "Plugin {} is provided by package {:?} and {:?}, using the package",
+                            metadata.id, sources[&metadata.id], path
+                        );
+                        continue;
+                    }
+                    if discovered.contains_key(&metadata.id) {
+                        warn!("Plugin {} is provided more than once", metadata.id);
+                    }
+                    sources.insert(metadata.id.clone(), path.clone());
                     discovered.insert(metadata.id.clone(), (path, metadata));
                 }
                 Err(e) => {
@@ -174,58 +473,51 @@ impl PluginSupervisor {
             }
         }
 
+        // Forget cached metadata of binaries that were removed
+        self.metadata_cache.retain_paths(&seen);
+        #[cfg(feature = "wasm")]
+        self.wasm_runtime.retain_paths(&seen);
+
+        // Drop extracted copies of archives that were removed
+        if let Ok(entries) = fs::read_dir(&self.packages_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if !extracted.contains(&name) && !name.to_string_lossy().starts_with('.') {
+                    fs::remove_dir_all(entry.path()).ok();
+                }
+            }
+        }
+
+        *self.packages.lock().unwrap_or_else(|e| e.into_inner()) = packages;
+        // Sources of plugins that failed to load this time are kept, so a
+        // rescan can tell a broken binary from a deleted one
+        self.plugin_sources
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(sources);
+
         info!("Discovered {} plugins", discovered.len());
         Ok(discovered)
     }
 
     /// Read plugin metadata by running the binary with --metadata flag
     ///
+    /// Results are cached in the metadata directory and reused until the
+    /// binary changes, so unchanged plugins are not executed on every scan.
+    /// WebAssembly plugins report their metadata from the sandbox instead.
+    ///
     /// # Arguments
     /// * `binary_path` - Path to the plugin binary
     ///
     /// # Returns
     /// PluginMetadata parsed from JSON output
     async fn read_plugin_metadata(&self, binary_path: &Path) -> Result<PluginMetadata> {
-        use tokio::process::Command;
-
-        let output = Command::new(binary_path)
-            .arg("--metadata")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await
-            .context("Failed to execute plugin binary")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!(
-                "Plugin --metadata command failed: {}",
-                stderr
-            ));
-        }
-
-        let stdout = String::from_utf8(output.stdout)
-            .context("Plugin metadata output is not valid UTF-8")?;
-
-        let metadata: PluginMetadata =
-            serde_json::from_str(&stdout).context("Failed to parse plugin metadata JSON")?;
-
-        // Security: Validate metadata fields to prevent injection attacks
-        if !metadata
-            .id
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == '-')
-        {
-            return Err(anyhow::anyhow!("Invalid plugin ID format"));
-        }
-        if !metadata.route.starts_with('/') || metadata.route.contains("..") {
-            return Err(anyhow::anyhow!("Invalid plugin route"));
-        }
-        if metadata.name.len() > 100 || metadata.author.as_ref().is_some_and(|a| a.len() > 100) {
-            return Err(anyhow::anyhow!("Metadata field too long"));
+        #[cfg(feature = "wasm")]
+        if wasm_runtime::is_wasm_plugin(binary_path) {
+            return self.wasm_runtime.read_metadata(binary_path).await;
         }
 
-        Ok(metadata)
+        self.metadata_cache.read_metadata(binary_path).await
     }
 
     /// Spawn a plugin process
@@ -243,7 +535,82 @@ impl PluginSupervisor {
         binary_path: &Path,
         metadata: PluginMetadata,
     ) -> Result<()> {
-        let socket_path = self.sockets_dir.join(format!("{}.sock", plugin_id));
+        let process = self
+            .start_process(plugin_id, binary_path, metadata, None)
+            .await?;
+        let pid = process.pid;
+
+        self.plugins.insert(plugin_id.to_string(), process);
+        info!("Spawned plugin: {} (PID: {:?})", plugin_id, pid);
+
+        // Notify plugin event via notification hooks
+        self.notify_plugin_event(
+            plugin_id,
+            "started",
+            LogLevel::Info,
+            Some(
+                &serde_json::json!({
+                    "pid": pid,
+                })
+                .to_string(),
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Start a plugin process without registering it
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier for the plugin
+    /// * `binary_path` - Path to the plugin binary
+    /// * `metadata` - Plugin metadata
+    /// * `socket_suffix` - Suffix for fresh socket names, so a second instance
+    ///   can run next to the current one (used by upgrades)
+    async fn start_process(
+        &self,
+        plugin_id: &str,
+        binary_path: &Path,
+        metadata: PluginMetadata,
+        socket_suffix: Option<&str>,
+    ) -> Result<PluginProcess> {
+        // Quotas may have changed since the plugin last started
+        let quota = db::plugin_quota(&self.db_pool, plugin_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to load quota of plugin {}: {}", plugin_id, e);
+                db::PluginQuota::default()
+            });
+        self.plugin_logger
+            .set_log_quota(plugin_id, quota.log_max_bytes_per_day);
+
+        let database = self.open_database(plugin_id).await?;
+
+        #[cfg(feature = "wasm")]
+        if wasm_runtime::is_wasm_plugin(binary_path) {
+            return self
+                .start_wasm(plugin_id, binary_path, metadata, database)
+                .await;
+        }
+
+        let data_dir = self.plugin_data_dir(plugin_id);
+        fs::create_dir_all(&data_dir).context("Failed to create plugin data directory")?;
+        let data_size = plugin_data::measure_dir(data_dir.clone()).await?;
+        if data_size > quota.data_max_bytes {
+            return Err(anyhow::anyhow!(
+                "Data directory of plugin {} uses {} bytes, the quota allows {}",
+                plugin_id,
+                data_size,
+                quota.data_max_bytes
+            ));
+        }
+
+        let socket_name = match socket_suffix {
+            Some(suffix) => format!("{}-{}", plugin_id, suffix),
+            None => plugin_id.to_string(),
+        };
+        let socket_path = self.sockets_dir.join(format!("{}.sock", socket_name));
         let socket_path_str = socket_path.to_string_lossy().to_string();
 
         // Clean up existing socket if present
@@ -251,77 +618,136 @@ impl PluginSupervisor {
             fs::remove_file(&socket_path).ok();
         }
 
-        let mut child = tokio::process::Command::new(binary_path)
+        // Start listening for plugin-initiated messages before the plugin runs
+        let host_socket_path = self.sockets_dir.join(format!("{}.host.sock", socket_name));
+        let host_socket_path_str = host_socket_path.to_string_lossy().to_string();
+        let host_listener = super::host_socket::spawn_host_listener(
+            plugin_id,
+            &host_socket_path,
+            self.db_pool.clone(),
+            Some(database),
+        )?;
+
+        let mut child = match tokio::process::Command::new(binary_path)
             .env("TORU_PLUGIN_SOCKET", &socket_path_str)
+            .env("TORU_HOST_SOCKET", &host_socket_path_str)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .context("Failed to spawn plugin process")?;
+        {
+            Ok(child) => child,
+            Err(e) => {
+                host_listener.abort();
+                fs::remove_file(&host_socket_path).ok();
+                return Err(e).context("Failed to spawn plugin process");
+            }
+        };
 
         let pid = child.id();
 
-        // Capture stderr to plugin log file
-        if let Some(mut stderr) = child.stderr.take() {
-            let plugin_logger = Arc::clone(&self.plugin_logger);
-            let plugin_id_clone = plugin_id.to_string();
-
-            tokio::spawn(async move {
-                use tokio::io::AsyncReadExt;
-                let mut buffer = [0u8; 4096];
-
-                loop {
-                    match stderr.read(&mut buffer).await {
-                        Ok(0) => break, // EOF
-                        Ok(n) => {
-                            let output = String::from_utf8_lossy(&buffer[..n]).to_string();
-                            // Parse structured JSON logs or write as plain text
-                            if let Ok(log_entry) =
-                                serde_json::from_str::<crate::services::logging::LogEntry>(&output)
-                            {
-                                let _ = plugin_logger.log_plugin(log_entry).await;
-                            } else {
-                                // Write plain text as Info log
-                                let log_entry = crate::services::logging::LogEntry::new(
-                                    crate::services::logging::LogLevel::Info,
-                                    output.trim(),
-                                )
-                                .with_plugin(&plugin_id_clone);
-                                let _ = plugin_logger.log_plugin(log_entry).await;
-                            }
-                        }
-                        Err(_) => break, // Error
-                    }
-                }
-            });
+        // Capture both output streams line by line into the plugin log.
+        // Both must be drained, or the plugin blocks once a pipe fills up.
+        if let Some(stdout) = child.stdout.take() {
+            self.plugin_logger
+                .capture_output(plugin_id, pid, LogStream::Stdout, stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.plugin_logger
+                .capture_output(plugin_id, pid, LogStream::Stderr, stderr);
         }
 
-        let process = PluginProcess {
+        Ok(PluginProcess {
             id: plugin_id.to_string(),
+            kind: PluginKind::Process,
             process: Some(child),
             socket_path: socket_path_str,
             enabled: true,
             metadata: Some(metadata),
             pid,
-        };
+            host_socket_path: host_socket_path_str,
+            host_listener: Some(host_listener),
+            binary_path: binary_path.to_path_buf(),
+            data_dir: Some(data_dir),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            #[cfg(feature = "wasm")]
+            wasm: None,
+        })
+    }
 
-        self.plugins.insert(plugin_id.to_string(), process);
-        info!("Spawned plugin: {} (PID: {:?})", plugin_id, pid);
+    /// Open a plugin's SQL database and apply the migrations of its package
+    ///
+    /// A failing migration keeps the plugin from starting, so it never runs
+    /// against a schema it does not expect.
+    async fn open_database(&self, plugin_id: &str) -> Result<SqlitePluginDatabase> {
+        let database = SqlitePluginDatabase::open(&self.databases_dir, plugin_id)?;
+        let migrations = self
+            .packages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(plugin_id)
+            .map(|package| package.migrations.clone())
+            .unwrap_or_default();
+
+        let applied = database
+            .apply_migration_files(&migrations)
+            .await
+            .with_context(|| format!("Failed to migrate database of plugin {}", plugin_id))?;
+        if !applied.is_empty() {
+            info!("Applied migrations {:?} of plugin {}", applied, plugin_id);
+            let details = serde_json::json!({ "versions": applied }).to_string();
+            self.notify_plugin_event(plugin_id, "migrated", LogLevel::Info, Some(&details))
+                .await;
+        }
+        Ok(database)
+    }
 
-        // Notify plugin event via notification hooks
-        self.notify_plugin_event(
-            plugin_id,
-            "started",
-            LogLevel::Info,
-            Some(
-                &serde_json::json!({
-                    "pid": pid,
-                })
-                .to_string(),
-            ),
-        )
-        .await;
+    /// Instantiate a WebAssembly plugin and run its init export
+    ///
+    /// The instance runs inside the core with the fuel and memory limits
+    /// from settings, and reaches the KV store through host functions.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Unique identifier for the plugin
+    /// * `binary_path` - Path to the `.wasm` module
+    /// * `metadata` - Plugin metadata
+    #[cfg(feature = "wasm")]
+    async fn start_wasm(
+        &self,
+        plugin_id: &str,
+        binary_path: &Path,
+        metadata: PluginMetadata,
+        database: SqlitePluginDatabase,
+    ) -> Result<PluginProcess> {
+        let limits = WasmLimits::from_settings(&self.db_pool).await;
+        let instance = self
+            .wasm_runtime
+            .instantiate(plugin_id, binary_path, limits, Some(database))
+            .await?;
+        if instance.metadata.id != plugin_id {
+            return Err(anyhow::anyhow!(
+                "WebAssembly plugin reports ID {}, expected {}",
+                instance.metadata.id,
+                plugin_id
+            ));
+        }
+        instance.init(&self.instance_id).await?;
 
-        Ok(())
+        Ok(PluginProcess {
+            id: plugin_id.to_string(),
+            kind: PluginKind::Wasm,
+            process: None,
+            socket_path: String::new(),
+            enabled: true,
+            metadata: Some(metadata),
+            pid: None,
+            host_socket_path: String::new(),
+            host_listener: None,
+            binary_path: binary_path.to_path_buf(),
+            // Sandboxed instances have no file system access
+            data_dir: None,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            wasm: Some(instance),
+        })
     }
 
     /// Kill a plugin process gracefully (with shutdown message)
@@ -363,6 +789,20 @@ impl PluginSupervisor {
             }
         }
 
+        // Stop serving the host socket
+        if let Some(host_listener) = process.host_listener.take() {
+            host_listener.abort();
+        }
+        if !process.host_socket_path.is_empty() {
+            fs::remove_file(&process.host_socket_path).ok();
+        }
+
+        // Drop the WebAssembly instance
+        #[cfg(feature = "wasm")]
+        {
+            process.wasm = None;
+        }
+
         process.enabled = false;
         info!("Plugin {} killed and disabled", plugin_id);
 
@@ -373,6 +813,89 @@ impl PluginSupervisor {
         Ok(())
     }
 
+    /// Stop running plugins whose data directory grew beyond their quota
+    ///
+    /// Plugins are only measured periodically, so a directory can overshoot
+    /// its quota until the next check. A stopped plugin is refused on start
+    /// until an admin frees space.
+    ///
+    /// Directories are measured without holding the supervisor lock, which
+    /// is only taken again to stop the plugins over their quota.
+    ///
+    /// # Arguments
+    /// * `supervisor` - Shared supervisor
+    ///
+    /// # Returns
+    /// IDs of the plugins that were stopped
+    pub async fn enforce_data_quotas(supervisor: &Arc<Mutex<Self>>) -> Vec<String> {
+        let (running, db_pool) = {
+            let sup = supervisor.lock().await;
+            let running: Vec<(String, PathBuf)> = sup
+                .plugins
+                .values()
+                .filter(|p| p.enabled && p.is_running())
+                .filter_map(|p| Some((p.id.clone(), p.data_dir.clone()?)))
+                .collect();
+            (running, sup.db_pool.clone())
+        };
+
+        let mut over_quota = Vec::new();
+        for (plugin_id, data_dir) in running {
+            let size = match plugin_data::measure_dir(data_dir).await {
+                Ok(size) => size,
+                Err(e) => {
+                    warn!("Failed to measure data directory of {}: {}", plugin_id, e);
+                    continue;
+                }
+            };
+            let quota = match db::plugin_quota(&db_pool, &plugin_id).await {
+                Ok(quota) => quota.data_max_bytes,
+                Err(e) => {
+                    warn!("Failed to load quota of plugin {}: {}", plugin_id, e);
+                    continue;
+                }
+            };
+            if size > quota {
+                over_quota.push((plugin_id, size, quota));
+            }
+        }
+        if over_quota.is_empty() {
+            return Vec::new();
+        }
+
+        let mut sup = supervisor.lock().await;
+        let mut stopped = Vec::new();
+        for (plugin_id, size, quota) in over_quota {
+            // It may have been stopped while its directory was measured
+            let running = sup
+                .plugins
+                .get(&plugin_id)
+                .is_some_and(|p| p.enabled && p.is_running());
+            if !running {
+                continue;
+            }
+
+            warn!(
+                "Plugin {} uses {} bytes of data, quota is {}; stopping it",
+                plugin_id, size, quota
+            );
+            if let Err(e) = sup.kill_plugin(&plugin_id).await {
+                warn!("Failed to stop plugin {}: {}", plugin_id, e);
+                continue;
+            }
+            let details = serde_json::json!({ "bytes": size, "quota_bytes": quota }).to_string();
+            sup.notify_plugin_event(
+                &plugin_id,
+                "data_quota_exceeded",
+                LogLevel::Warn,
+                Some(&details),
+            )
+            .await;
+            stopped.push(plugin_id);
+        }
+        stopped
+    }
+
     /// Check if a plugin is healthy (socket exists and process is running)
     ///
     /// # Arguments
@@ -392,6 +915,11 @@ impl PluginSupervisor {
             return false;
         }
 
+        // WebAssembly plugins have no socket or process to probe
+        if process.kind == PluginKind::Wasm {
+            return process.is_running();
+        }
+
         // Check if socket file exists
         let socket_path = std::path::Path::new(&process.socket_path);
         if !socket_path.exists() {
@@ -444,6 +972,45 @@ impl PluginSupervisor {
         self.plugins_dir.clone()
     }
 
+    /// Get the frontend assets directory of a packaged plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    ///
+    /// # Returns
+    /// None for single-binary plugins and packages without assets
+    pub fn plugin_assets_dir(&self, plugin_id: &str) -> Option<PathBuf> {
+        self.packages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(plugin_id)
+            .and_then(|package| package.assets_dir.clone())
+    }
+
+    /// Private data directory of a plugin
+    ///
+    /// Lives under `plugins/.data/`, so it is part of any backup of the
+    /// plugins directory. It is created when a process plugin starts.
+    pub fn plugin_data_dir(&self, plugin_id: &str) -> PathBuf {
+        self.data_dir.join(plugin_id)
+    }
+
+    /// Whether a plugin was installed as a package
+    pub fn is_packaged(&self, plugin_id: &str) -> bool {
+        self.packages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(plugin_id)
+    }
+
+    /// List the archived versions of a plugin, newest first
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    pub fn list_plugin_versions(&self, plugin_id: &str) -> Result<Vec<PluginVersion>> {
+        plugin_versions::list(&self.versions_dir, plugin_id)
+    }
+
     /// Notify plugin event through all configured notification hooks
     ///
     /// This is the unified entry point for plugin event notifications.
@@ -465,18 +1032,15 @@ impl PluginSupervisor {
         log_level: LogLevel,
         details: Option<&str>,
     ) {
-        // Hook 1: Log to file
-        let _ = self
-            .supervisor_logger
-            .log_plugin_event(log_level, plugin_id, event_type, details)
-            .await;
-
-        // Hook 2: Log to database
-        let _ = crate::db::plugin_event_log(&self.db_pool, plugin_id, event_type, details).await;
-
-        // Future: Hook 3 - Email notifications
-        // Future: Hook 4 - Webhook calls
-        // Future: Hook 5 - Plugin-specific callbacks
+        record_plugin_event(
+            &self.supervisor_logger,
+            &self.db_pool,
+            plugin_id,
+            event_type,
+            log_level,
+            details,
+        )
+        .await;
     }
 
     /// Increment restart counter for a plugin
@@ -599,9 +1163,8 @@ impl PluginSupervisor {
 
         if let Some(process) = self.plugins.get_mut(plugin_id) {
             // If plugin is disabled or not running, spawn it
-            if !process.enabled || process.process.is_none() {
-                // Get binary path from plugins directory
-                let binary_path = self.plugins_dir.join(format!("{}.binary", plugin_id));
+            if !process.enabled || !process.is_running() {
+                let binary_path = process.binary_path.clone();
                 if let Some(metadata) = process.metadata.clone() {
                     // Spawn the plugin (process reference is dropped automatically at end of scope)
                     let _ = process; // Explicitly indicate we're done with the mutable borrow
@@ -622,10 +1185,14 @@ impl PluginSupervisor {
         }
 
         // Wait for socket to be ready after spawning (similar to send_init_message retry logic)
+        let has_socket = self
+            .plugins
+            .get(plugin_id)
+            .is_some_and(|p| p.kind == PluginKind::Process);
         let socket_path = self.sockets_dir.join(format!("{}.sock", plugin_id));
         for _ in 0..20 {
             // 20 retries * 100ms = 2 seconds max
-            if socket_path.exists() {
+            if !has_socket || socket_path.exists() {
                 break;
             }
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -660,12 +1227,16 @@ impl PluginSupervisor {
     /// # Returns
     /// Number of plugins that were successfully spawned
     pub async fn initialize(&mut self) -> Result<usize> {
+        self.apply_log_settings().await;
+
         let discovered = self.scan_plugins_directory().await?;
         let total_plugins = discovered.len();
 
         let mut spawned_count = 0;
 
         for (plugin_id, (binary_path, metadata)) in discovered {
+            self.record_binary_hash(&plugin_id, &binary_path);
+
             // Check if plugin is enabled
             if self.is_plugin_enabled(&plugin_id) {
                 match self.spawn_plugin(&plugin_id, &binary_path, metadata).await {
@@ -693,50 +1264,238 @@ impl PluginSupervisor {
         Ok(spawned_count)
     }
 
-    /// Send lifecycle init message to a plugin via Unix socket
+    /// Remember the content hash of a plugin's binary
+    fn record_binary_hash(&mut self, plugin_id: &str, binary_path: &Path) {
+        match metadata_cache::fingerprint(binary_path) {
+            Ok(fingerprint) => {
+                self.binary_hashes
+                    .insert(plugin_id.to_string(), fingerprint.sha256);
+            }
+            Err(e) => warn!("Failed to fingerprint plugin {}: {}", plugin_id, e),
+        }
+    }
+
+    /// Rescan the plugins directory and apply changes
     ///
-    /// # Arguments
-    /// * `plugin_id` - Plugin identifier
-    async fn send_init_message(&self, plugin_id: &str) -> Result<()> {
-        use toru_plugin_api::{LifecycleInitPayload, PluginProtocol};
+    /// New plugins are spawned (if enabled), plugins whose binary content
+    /// changed are restarted, and plugins whose binary disappeared are
+    /// stopped. A plugin whose binary is still present but fails to report
+    /// metadata (e.g. while it is being copied) keeps running. A plugin that
+    /// fails to stop or start is skipped and retried on the next rescan.
+    /// Each transition is recorded as a plugin event.
+    ///
+    /// # Returns
+    /// The plugin IDs affected by each kind of change
+    pub async fn rescan(&mut self) -> Result<RescanReport> {
+        let discovered = self.scan_plugins_directory().await?;
+        let mut report = RescanReport::default();
+
+        let missing: Vec<String> = self
+            .binary_hashes
+            .keys()
+            .filter(|id| !discovered.contains_key(*id))
+            .cloned()
+            .collect();
+
+        for plugin_id in missing {
+            let source = self
+                .plugin_sources
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&plugin_id)
+                .cloned();
+            if let Some(source) = source.as_ref().filter(|source| source.exists()) {
+                warn!(
+                    "Plugin {} at {:?} reported no valid metadata, keeping the current version",
+                    plugin_id, source
+                );
+                self.notify_plugin_event(
+                    &plugin_id,
+                    "metadata_failed",
+                    LogLevel::Warn,
+                    Some(&serde_json::json!({ "source": source }).to_string()),
+                )
+                .await;
+                continue;
+            }
 
-        let process = self
-            .get_plugin_status(plugin_id)
-            .context("Plugin not found")?;
+            self.binary_hashes.remove(&plugin_id);
+            self.plugin_sources
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&plugin_id);
+            self.circuit_breakers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&plugin_id);
+            self.request_metrics.remove(&plugin_id);
+            if let Some(process) = self.plugins.get(&plugin_id) {
+                if process.is_running() {
+                    if let Err(e) = self.kill_plugin(&plugin_id).await {
+                        warn!("Failed to stop removed plugin {}: {}", plugin_id, e);
+                    }
+                }
+                self.plugins.remove(&plugin_id);
+            }
 
-        // Wait for socket to be available (with timeout)
-        let socket_path = std::path::Path::new(&process.socket_path);
-        let mut retries = 10;
+            info!("Plugin {} binary removed", plugin_id);
+            // Data is only deleted when the plugin's source is known and still
+            // gone after stopping it, never on a mere failure to load it
+            let uninstalled = source.is_some_and(|source| !source.exists());
+            let remove_data =
+                db::get_setting(&self.db_pool, plugin_data::REMOVE_ON_UNINSTALL_SETTING)
+                    .await
+                    .ok()
+                    .flatten()
+                    .is_some_and(|v| v == "true");
+            if uninstalled && remove_data {
+                self.remove_plugin_data(&plugin_id);
+            }
+            self.notify_plugin_event(&plugin_id, "removed", LogLevel::Info, None)
+                .await;
+            report.removed.push(plugin_id);
+        }
 
-        while !socket_path.exists() && retries > 0 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            retries -= 1;
+        for (plugin_id, (binary_path, metadata)) in discovered {
+            let hash = match metadata_cache::fingerprint(&binary_path) {
+                Ok(fingerprint) => fingerprint.sha256,
+                Err(e) => {
+                    warn!("Failed to fingerprint plugin {}: {}", plugin_id, e);
+                    continue;
+                }
+            };
+
+            let event = match self.binary_hashes.get(&plugin_id) {
+                None => "discovered",
+                Some(known) if *known != hash => "binary_replaced",
+                Some(_) => continue,
+            };
+
+            let was_running = self.plugins.get(&plugin_id).is_some_and(|p| p.is_running());
+            if was_running {
+                if let Err(e) = self.kill_plugin(&plugin_id).await {
+                    self.notify_rescan_failure(&plugin_id, "stop", &e).await;
+                    continue;
+                }
+            }
+
+            let version = metadata.version.clone();
+            let enabled = self.is_plugin_enabled(&plugin_id);
+            if enabled {
+                if let Err(e) = self.spawn_plugin(&plugin_id, &binary_path, metadata).await {
+                    self.notify_rescan_failure(&plugin_id, "start", &e).await;
+                    continue;
+                }
+                if let Err(e) = self.send_init_message(&plugin_id).await {
+                    error!("Failed to send init message to {}: {}", plugin_id, e);
+                }
+            } else if let Some(process) = self.plugins.get_mut(&plugin_id) {
+                process.metadata = Some(metadata);
+                process.binary_path = binary_path.clone();
+            }
+            // Only now is the change applied; a failure above is retried
+            self.binary_hashes.insert(plugin_id.clone(), hash);
+
+            info!("Plugin {} {} (v{})", plugin_id, event, version);
+            self.notify_plugin_event(
+                &plugin_id,
+                event,
+                LogLevel::Info,
+                Some(
+                    &serde_json::json!({
+                        "version": version,
+                        "binary": binary_path,
+                        "spawned": enabled,
+                    })
+                    .to_string(),
+                ),
+            )
+            .await;
+
+            if event == "discovered" {
+                report.added.push(plugin_id);
+            } else {
+                report.replaced.push(plugin_id);
+            }
         }
 
-        if !socket_path.exists() {
-            return Err(anyhow::anyhow!("Plugin socket not available after waiting"));
+        Ok(report)
+    }
+
+    /// Delete the data directory and database of an uninstalled plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    fn remove_plugin_data(&self, plugin_id: &str) {
+        let data_dir = self.plugin_data_dir(plugin_id);
+        if data_dir.exists() {
+            match fs::remove_dir_all(&data_dir) {
+                Ok(()) => info!("Removed data directory of plugin {}", plugin_id),
+                Err(e) => warn!(
+                    "Failed to remove data directory of plugin {}: {}",
+                    plugin_id, e
+                ),
+            }
+        }
+        if let Ok(path) = plugin_database::database_path(&self.databases_dir, plugin_id) {
+            if path.exists() {
+                match fs::remove_file(&path) {
+                    Ok(()) => info!("Removed database of plugin {}", plugin_id),
+                    Err(e) => warn!("Failed to remove database of plugin {}: {}", plugin_id, e),
+                }
+            }
         }
+    }
 
-        // Connect to plugin socket
-        let mut stream = UnixStream::connect(&process.socket_path)
-            .await
-            .context("Failed to connect to plugin socket")?;
+    /// Record that a rescan could not apply a change to a plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    /// * `stage` - What failed: "stop" or "start"
+    /// * `error` - The failure
+    async fn notify_rescan_failure(&self, plugin_id: &str, stage: &str, error: &anyhow::Error) {
+        error!(
+            "Rescan failed to {} plugin {}: {:#}",
+            stage, plugin_id, error
+        );
+        self.notify_plugin_event(
+            plugin_id,
+            "rescan_failed",
+            LogLevel::Error,
+            Some(
+                &serde_json::json!({
+                    "stage": stage,
+                    "error": format!("{:#}", error),
+                })
+                .to_string(),
+            ),
+        )
+        .await;
+    }
 
-        // Create init message with instance_id
-        let init_payload = LifecycleInitPayload {
-            instance_id: self.instance_id.clone(),
-            plugin_socket: process.socket_path.clone(),
-            log_path: format!("/var/log/toru/plugins/{}.log", plugin_id),
-        };
+    /// Send lifecycle init message to a plugin via Unix socket
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    async fn send_init_message(&self, plugin_id: &str) -> Result<()> {
+        let process = self
+            .get_plugin_status(plugin_id)
+            .context("Plugin not found")?;
 
-        let message = Message::new_lifecycle("init", Some(init_payload));
+        // WebAssembly plugins are initialized when instantiated
+        if process.kind == PluginKind::Wasm {
+            return Ok(());
+        }
 
-        // Send message using length-prefixed protocol
-        let protocol = PluginProtocol::new();
-        protocol
-            .write_message(&mut stream, &message)
-            .await
-            .context("Failed to send init message")?;
+        send_init(
+            &self.instance_id,
+            plugin_id,
+            &process.socket_path,
+            &process.host_socket_path,
+            process.data_dir.as_deref(),
+            10,
+        )
+        .await?;
 
         debug!("Sent init message to plugin {}", plugin_id);
         Ok(())
@@ -749,39 +1508,66 @@ impl PluginSupervisor {
     // TODO: Integrate in graceful shutdown flow
     #[allow(dead_code)]
     async fn send_shutdown_message(&self, plugin_id: &str) -> Result<()> {
-        use toru_plugin_api::PluginProtocol;
-
         let process = self
             .get_plugin_status(plugin_id)
             .context("Plugin not found")?;
 
-        let socket_path = std::path::Path::new(&process.socket_path);
+        send_shutdown(plugin_id, &process.socket_path).await
+    }
 
-        if !socket_path.exists() {
-            debug!(
-                "Plugin {} socket not found, skipping shutdown message",
-                plugin_id
-            );
-            return Ok(());
-        }
+    /// Resolve the process currently serving HTTP requests for a plugin
+    ///
+    /// The returned target can be used after the supervisor lock is released,
+    /// which keeps slow plugins from blocking upgrades and other requests.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    pub fn http_target(&self, plugin_id: &str) -> Result<HttpTarget> {
+        let process = self
+            .get_plugin_status(plugin_id)
+            .context("Plugin not found")?;
 
-        // Connect to plugin socket
-        let mut stream = UnixStream::connect(&process.socket_path)
-            .await
-            .context("Failed to connect to plugin socket")?;
+        // Check if plugin is enabled and has a socket
+        if !process.enabled {
+            return Err(anyhow::anyhow!("Plugin {} is not enabled", plugin_id));
+        }
 
-        // Create shutdown message
-        let message = Message::new_lifecycle("shutdown", None);
+        let endpoint = match process.kind {
+            PluginKind::Process => {
+                if !Path::new(&process.socket_path).exists() {
+                    return Err(anyhow::anyhow!("Plugin {} socket not found", plugin_id));
+                }
+                HttpEndpoint::Socket(process.socket_path.clone())
+            }
+            #[cfg(feature = "wasm")]
+            PluginKind::Wasm => HttpEndpoint::Wasm(
+                process
+                    .wasm
+                    .clone()
+                    .with_context(|| format!("Plugin {} is not running", plugin_id))?,
+            ),
+            #[cfg(not(feature = "wasm"))]
+            PluginKind::Wasm => {
+                return Err(anyhow::anyhow!("WebAssembly plugins are not supported"));
+            }
+        };
 
-        // Send message using length-prefixed protocol
-        let protocol = PluginProtocol::new();
-        protocol
-            .write_message(&mut stream, &message)
-            .await
-            .context("Failed to send shutdown message")?;
+        let breaker = Arc::clone(
+            self.circuit_breakers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(plugin_id.to_string())
+                .or_default(),
+        );
 
-        debug!("Sent shutdown message to plugin {}", plugin_id);
-        Ok(())
+        Ok(HttpTarget {
+            plugin_id: plugin_id.to_string(),
+            endpoint,
+            in_flight: Arc::clone(&process.in_flight),
+            breaker,
+            supervisor_logger: Arc::clone(&self.supervisor_logger),
+            db_pool: self.db_pool.clone(),
+        })
     }
 
     /// Forward an HTTP request to a plugin
@@ -796,105 +1582,15 @@ impl PluginSupervisor {
     ///
     /// # Returns
     /// The plugin's HTTP response
+    // The HTTP router uses http_target() so it can release the lock first
+    #[allow(dead_code)]
     pub async fn forward_http_request(
         &self,
         plugin_id: &str,
         request: &HttpRequest,
     ) -> Result<HttpMessageResponse> {
-        let process = self
-            .get_plugin_status(plugin_id)
-            .context("Plugin not found")?;
-
-        // Check if plugin is enabled and has a socket
-        if !process.enabled {
-            return Err(anyhow::anyhow!("Plugin {} is not enabled", plugin_id));
-        }
-
-        let socket_path = std::path::Path::new(&process.socket_path);
-        if !socket_path.exists() {
-            return Err(anyhow::anyhow!("Plugin {} socket not found", plugin_id));
-        }
-
-        // Connect to plugin socket
-        let mut stream = UnixStream::connect(&process.socket_path)
-            .await
-            .context("Failed to connect to plugin socket")?;
-
-        // Generate a unique request ID
-        let request_id = uuid::Uuid::new_v4().to_string();
-
-        // Create HTTP request message
-        let message = Message::new_http(request_id.clone(), request.clone());
-
-        // Use the protocol to send the message
-        use toru_plugin_api::PluginProtocol;
-        let mut protocol = PluginProtocol::new();
-        protocol
-            .write_message(&mut stream, &message)
-            .await
-            .context("Failed to send HTTP request to plugin")?;
-
-        // Read the response with timeout to prevent hanging on unresponsive plugins
-        let response_msg = tokio::time::timeout(
-            tokio::time::Duration::from_secs(30),
-            protocol.read_message(&mut stream),
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("Plugin response timeout after 30s"))?
-        .context("Failed to read HTTP response from plugin")?;
-
-        // Extract the HTTP response - the plugin sends HttpRequest with body containing JSON response
-        // Message structure:
-        // {
-        //   "payload": {
-        //     "type": "http",
-        //     "request_id": "...",
-        //     "payload": {         // HttpRequest
-        //       "method": "RESPONSE",
-        //       "body": "{\"status\":200,\"headers\":{...},\"body\":\"...\"}"  // JSON string
-        //     }
-        //   }
-        // }
-        let response_value =
-            serde_json::to_value(&response_msg).context("Failed to serialize response message")?;
-
-        // Get the inner payload (HttpRequest) and extract the body JSON string
-        let body_json_str = response_value
-            .get("payload")
-            .and_then(|p| p.get("payload"))  // Get HttpRequest from MessagePayload::Http
-            .and_then(|req| req.get("body"))
-            .and_then(|b| b.as_str())
-            .unwrap_or("{}");
-
-        // Parse the body JSON string to get the actual response fields
-        let parsed_response: serde_json::Value =
-            serde_json::from_str(body_json_str).unwrap_or_else(|_| serde_json::json!({}));
-
-        let http_response = toru_plugin_api::HttpMessageResponse {
-            status: parsed_response
-                .get("status")
-                .and_then(|s| s.as_u64())
-                .unwrap_or(500) as u16,
-            headers: parsed_response
-                .get("headers")
-                .and_then(|h| serde_json::from_value(h.clone()).ok())
-                .unwrap_or_default(),
-            body: parsed_response
-                .get("body")
-                .and_then(|b| {
-                    // body can be either a string or null
-                    if b.is_string() {
-                        Some(b.as_str().unwrap().to_string())
-                    } else if b.is_null() {
-                        None
-                    } else {
-                        // If body is an object/array, serialize it
-                        Some(serde_json::to_string(b).unwrap_or_default())
-                    }
-                }),
-        };
-
-        Ok(http_response)
+        let limits = HttpLimits::load(&self.db_pool, plugin_id, "").await;
+        self.http_target(plugin_id)?.forward(request, &limits).await
     }
 
     /// Get the plugin ID that owns a given route path
@@ -1014,6 +1710,706 @@ impl PluginSupervisor {
 
         Ok(())
     }
+
+    /// Upgrade a running plugin to a new binary without dropping requests
+    ///
+    /// The new version is started on a fresh socket next to the current one
+    /// and only receives traffic once it has accepted its init message and
+    /// answered a probe request.
+    /// Requests already forwarded to the old process are drained before it is
+    /// sent `shutdown`. If the new version fails readiness, the old one keeps
+    /// serving and an `upgrade_failed` event is recorded.
+    ///
+    /// The supervisor lock is only held while starting the new process and
+    /// while switching routing, never while waiting on either process.
+    ///
+    /// # Arguments
+    /// * `supervisor` - Shared supervisor
+    /// * `plugin_id` - Plugin to upgrade
+    /// * `new_binary` - New plugin binary, moved into the plugins directory on success
+    pub async fn upgrade_plugin(
+        supervisor: &Arc<Mutex<Self>>,
+        plugin_id: &str,
+        new_binary: &Path,
+    ) -> Result<()> {
+        // Start the new version next to the current one
+        let (mut new_process, instance_id, from_version, retain) = {
+            let sup = supervisor.lock().await;
+            let current = sup.plugins.get(plugin_id).context("Plugin not found")?;
+            if !current.enabled || !current.is_running() {
+                return Err(anyhow::anyhow!(
+                    "Plugin {} is not running, enable it before upgrading",
+                    plugin_id
+                ));
+            }
+            if current.kind == PluginKind::Wasm {
+                return Err(anyhow::anyhow!(
+                    "Plugin {} is a WebAssembly plugin; replace its .wasm file to upgrade it",
+                    plugin_id
+                ));
+            }
+            if sup.is_packaged(plugin_id) {
+                return Err(anyhow::anyhow!(
+                    "Plugin {} is installed as a package; replace the package to upgrade it",
+                    plugin_id
+                ));
+            }
+            let from_version = current.metadata.as_ref().map(|m| m.version.clone());
+
+            let metadata = sup.read_plugin_metadata(new_binary).await?;
+            if metadata.id != plugin_id {
+                return Err(anyhow::anyhow!(
+                    "New binary reports plugin ID {}, expected {}",
+                    metadata.id,
+                    plugin_id
+                ));
+            }
+
+            let suffix = uuid::Uuid::new_v4().simple().to_string();
+            let process = sup
+                .start_process(plugin_id, new_binary, metadata, Some(&suffix[..8]))
+                .await?;
+            let retain = plugin_versions::retained_versions(&sup.db_pool).await;
+            (process, sup.instance_id.clone(), from_version, retain)
+        };
+        let to_version = new_process.metadata.as_ref().map(|m| m.version.clone());
+
+        info!(
+            "Upgrading plugin {} from {:?} to {:?} (new PID: {:?})",
+            plugin_id, from_version, to_version, new_process.pid
+        );
+
+        // Wait for the new version without holding the lock
+        let readiness = wait_until_ready(&instance_id, plugin_id, &mut new_process).await;
+
+        let mut sup = supervisor.lock().await;
+
+        // Switch routing to the new version
+        let switched = readiness.and_then(|_| {
+            let current = sup.plugins.get(plugin_id).context("Plugin not found")?;
+            if !current.enabled {
+                return Err(anyhow::anyhow!("Plugin was disabled during the upgrade"));
+            }
+
+            // Keep the current binary so the upgrade can be rolled back
+            if let Some(metadata) = &current.metadata {
+                plugin_versions::archive(
+                    &sup.versions_dir,
+                    plugin_id,
+                    &current.binary_path,
+                    metadata,
+                    retain,
+                )?;
+            }
+
+            let target = sup.plugins_dir.join(format!("{}.binary", plugin_id));
+            if new_binary != target {
+                fs::rename(new_binary, &target).context("Failed to install new binary")?;
+            }
+            new_process.binary_path = target;
+            Ok(())
+        });
+
+        if let Err(e) = switched {
+            drop(sup);
+            error!("Upgrade of plugin {} failed: {:#}", plugin_id, e);
+            stop_process(plugin_id, new_process, false).await;
+
+            let sup = supervisor.lock().await;
+            sup.notify_plugin_event(
+                plugin_id,
+                "upgrade_failed",
+                LogLevel::Error,
+                Some(
+                    &serde_json::json!({
+                        "from_version": from_version,
+                        "to_version": to_version,
+                        "error": format!("{:#}", e),
+                    })
+                    .to_string(),
+                ),
+            )
+            .await;
+
+            return Err(e);
+        }
+
+        let pid = new_process.pid;
+        let installed = new_process.binary_path.clone();
+        sup.record_binary_hash(plugin_id, &installed);
+        let old_process = sup
+            .plugins
+            .insert(plugin_id.to_string(), new_process)
+            .context("Plugin not found")?;
+        sup.reset_restart_count(plugin_id);
+
+        sup.notify_plugin_event(
+            plugin_id,
+            "upgraded",
+            LogLevel::Info,
+            Some(
+                &serde_json::json!({
+                    "from_version": from_version,
+                    "to_version": to_version,
+                    "pid": pid,
+                })
+                .to_string(),
+            ),
+        )
+        .await;
+        drop(sup);
+
+        // Drain and retire the old version
+        stop_process(plugin_id, old_process, true).await;
+
+        info!("Plugin {} upgraded (PID: {:?})", plugin_id, pid);
+        Ok(())
+    }
+
+    /// Roll a plugin back to an archived version
+    ///
+    /// The archived binary is reinstated as `<id>.binary` and started through
+    /// the normal spawn path (if the plugin is enabled). The binary being
+    /// replaced is archived in turn, so a rollback can itself be undone.
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin identifier
+    /// * `version_id` - Archived version to restore (defaults to the most recent)
+    ///
+    /// # Returns
+    /// The version that was restored
+    pub async fn rollback_plugin(
+        &mut self,
+        plugin_id: &str,
+        version_id: Option<&str>,
+    ) -> Result<PluginVersion> {
+        if self
+            .plugins
+            .get(plugin_id)
+            .is_some_and(|p| p.kind == PluginKind::Wasm)
+        {
+            return Err(anyhow::anyhow!(
+                "Plugin {} is a WebAssembly plugin; replace its .wasm file to roll it back",
+                plugin_id
+            ));
+        }
+        if self.is_packaged(plugin_id) {
+            return Err(anyhow::anyhow!(
+                "Plugin {} is installed as a package; replace the package to roll it back",
+                plugin_id
+            ));
+        }
+
+        let (version, archived_binary) =
+            plugin_versions::find(&self.versions_dir, plugin_id, version_id)?;
+
+        // Validate the archived binary before touching the running plugin
+        let metadata = self.read_plugin_metadata(&archived_binary).await?;
+        if metadata.id != plugin_id {
+            return Err(anyhow::anyhow!(
+                "Archived binary reports plugin ID {}, expected {}",
+                metadata.id,
+                plugin_id
+            ));
+        }
+
+        let target = self.plugins_dir.join(format!("{}.binary", plugin_id));
+        let current_metadata = self.plugins.get(plugin_id).and_then(|p| p.metadata.clone());
+        let from_version = current_metadata.as_ref().map(|m| m.version.clone());
+
+        // Stage the restored binary and archive the current one while the
+        // plugin keeps running, so a failure here leaves it untouched.
+        // Copy next to the target so the swap is a single rename.
+        let staged = self.plugins_dir.join(format!(".{}.rollback", plugin_id));
+        fs::copy(&archived_binary, &staged).context("Failed to restore archived binary")?;
+
+        let mut replaced = None;
+        if let Some(current_metadata) = &current_metadata {
+            if target.exists() {
+                let retain = plugin_versions::retained_versions(&self.db_pool).await;
+                // The restored entry is removed below, so keep room for it
+                let archived = plugin_versions::archive(
+                    &self.versions_dir,
+                    plugin_id,
+                    &target,
+                    current_metadata,
+                    retain + 1,
+                );
+                match archived {
+                    Ok(archived) => replaced = archived,
+                    Err(e) => {
+                        fs::remove_file(&staged).ok();
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        if let Some(process) = self.plugins.get(plugin_id) {
+            if process.is_running() {
+                if let Err(e) = self.kill_plugin(plugin_id).await {
+                    fs::remove_file(&staged).ok();
+                    if let Some(replaced) = replaced {
+                        plugin_versions::remove(
+                            &self.versions_dir,
+                            plugin_id,
+                            &replaced.version_id,
+                        )?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        fs::rename(&staged, &target).context("Failed to install archived binary")?;
+        self.record_binary_hash(plugin_id, &target);
+        plugin_versions::remove(&self.versions_dir, plugin_id, &version.version_id)?;
+
+        let mut pid = None;
+        if self.is_plugin_enabled(plugin_id) {
+            self.spawn_plugin(plugin_id, &target, metadata).await?;
+            if let Err(e) = self.send_init_message(plugin_id).await {
+                error!("Failed to send init message after rollback: {}", e);
+            }
+            pid = self.plugins.get(plugin_id).and_then(|p| p.pid);
+        } else if let Some(process) = self.plugins.get_mut(plugin_id) {
+            process.metadata = Some(metadata);
+            process.binary_path = target;
+        }
+        self.reset_restart_count(plugin_id);
+
+        info!(
+            "Plugin {} rolled back to v{} ({})",
+            plugin_id, version.metadata.version, version.version_id
+        );
+
+        self.notify_plugin_event(
+            plugin_id,
+            "rolled_back",
+            LogLevel::Warn,
+            Some(
+                &serde_json::json!({
+                    "from_version": from_version,
+                    "to_version": version.metadata.version,
+                    "version_id": version.version_id,
+                    "pid": pid,
+                })
+                .to_string(),
+            ),
+        )
+        .await;
+
+        Ok(version)
+    }
+}
+
+/// Record a plugin event in the supervisor log and the database
+///
+/// # Arguments
+/// * `supervisor_logger` - Logger of the supervisor log file
+/// * `db_pool` - Database holding plugin events
+/// * `plugin_id` - Plugin the event is about
+/// * `event_type` - Event name (e.g. "started")
+/// * `log_level` - Level of the supervisor log entry
+/// * `details` - Optional JSON details
+async fn record_plugin_event(
+    supervisor_logger: &SupervisorLogger,
+    db_pool: &DbPool,
+    plugin_id: &str,
+    event_type: &str,
+    log_level: LogLevel,
+    details: Option<&str>,
+) {
+    // Hook 1: Log to file
+    let _ = supervisor_logger
+        .log_plugin_event(log_level, plugin_id, event_type, details)
+        .await;
+
+    // Hook 2: Log to database
+    let _ = crate::db::plugin_event_log(db_pool, plugin_id, event_type, details).await;
+
+    // Future: Hook 3 - Email notifications
+    // Future: Hook 4 - Webhook calls
+    // Future: Hook 5 - Plugin-specific callbacks
+}
+
+impl HttpTarget {
+    /// Forward an HTTP request to the pinned plugin process
+    ///
+    /// Requests are rejected with `ForwardError::CircuitOpen` while the
+    /// plugin's circuit is open. Failures, timeouts and responses over the
+    /// size limit count towards opening it; other responses of any status
+    /// count as successes.
+    ///
+    /// # Arguments
+    /// * `request` - HTTP request to forward
+    /// * `limits` - Timeout, response size and circuit breaker policy
+    ///
+    /// # Returns
+    /// The plugin's HTTP response
+    pub async fn forward(
+        &self,
+        request: &HttpRequest,
+        limits: &HttpLimits,
+    ) -> Result<HttpMessageResponse> {
+        let (admission, transition) = self.breaker.admit(&limits.breaker, Instant::now());
+        if let Some(transition) = transition {
+            self.record_transition(transition.state, transition.failures, None)
+                .await;
+        }
+        if let Admission::Rejected { retry_after } = admission {
+            return Err(ForwardError::CircuitOpen { retry_after }.into());
+        }
+
+        let result = {
+            self.in_flight.fetch_add(1, Ordering::SeqCst);
+            let _guard = InFlightGuard(Arc::clone(&self.in_flight));
+
+            let send = async {
+                match &self.endpoint {
+                    HttpEndpoint::Socket(socket_path) => {
+                        forward_to_socket(socket_path, request).await
+                    }
+                    #[cfg(feature = "wasm")]
+                    HttpEndpoint::Wasm(plugin) => plugin.handle_http(request).await,
+                }
+            };
+            match tokio::time::timeout(limits.timeout, send).await {
+                Ok(result) => result,
+                Err(_) => Err(ForwardError::Timeout(limits.timeout).into()),
+            }
+        };
+        let result = result.and_then(|response| {
+            let size = response.body.as_ref().map_or(0, String::len);
+            if size > limits.max_response_bytes {
+                return Err(ForwardError::ResponseTooLarge {
+                    size,
+                    limit: limits.max_response_bytes,
+                }
+                .into());
+            }
+            Ok(response)
+        });
+
+        let transition = self
+            .breaker
+            .record(&limits.breaker, result.is_ok(), Instant::now());
+        if let Some(transition) = transition {
+            let error = result.as_ref().err().map(|e| format!("{:#}", e));
+            self.record_transition(transition.state, transition.failures, error)
+                .await;
+        }
+
+        result
+    }
+
+    /// Record a change of the plugin's circuit as a plugin event
+    async fn record_transition(&self, state: CircuitState, failures: u32, error: Option<String>) {
+        let level = match state {
+            CircuitState::Open => LogLevel::Warn,
+            CircuitState::HalfOpen | CircuitState::Closed => LogLevel::Info,
+        };
+        let details = serde_json::json!({
+            "state": state,
+            "consecutive_failures": failures,
+            "error": error,
+        })
+        .to_string();
+        record_plugin_event(
+            &self.supervisor_logger,
+            &self.db_pool,
+            &self.plugin_id,
+            state.event_type(),
+            level,
+            Some(&details),
+        )
+        .await;
+    }
+}
+
+/// Forward an HTTP request to a plugin process over its Unix socket
+///
+/// # Arguments
+/// * `socket_path` - Plugin socket
+/// * `request` - HTTP request to forward
+async fn forward_to_socket(
+    socket_path: &str,
+    request: &HttpRequest,
+) -> Result<HttpMessageResponse> {
+    use toru_plugin_api::PluginProtocol;
+
+    // Connect to plugin socket
+    let mut stream = UnixStream::connect(socket_path)
+        .await
+        .context("Failed to connect to plugin socket")?;
+
+    // Generate a unique request ID
+    let request_id = uuid::Uuid::new_v4().to_string();
+
+    // Create HTTP request message
+    let message = Message::new_http(request_id.clone(), request.clone());
+
+    // Use the protocol to send the message
+    let mut protocol = PluginProtocol::new();
+    protocol
+        .write_message(&mut stream, &message)
+        .await
+        .context("Failed to send HTTP request to plugin")?;
+
+    // The caller bounds the whole exchange with the plugin's timeout
+    let response_msg = protocol
+        .read_message(&mut stream)
+        .await
+        .context("Failed to read HTTP response from plugin")?;
+
+    // Extract the HTTP response - the plugin sends HttpRequest with body containing JSON response
+    // Message structure:
+    // {
+    //   "payload": {
+    //     "type": "http",
+    //     "request_id": "...",
+    //     "payload": {         // HttpRequest
+    //       "method": "RESPONSE",
+    //       "body": "{\"status\":200,\"headers\":{...},\"body\":\"...\"}"  // JSON string
+    //     }
+    //   }
+    // }
+    let response_value =
+        serde_json::to_value(&response_msg).context("Failed to serialize response message")?;
+
+    // Get the inner payload (HttpRequest) and extract the body JSON string
+    let body_json_str = response_value
+        .get("payload")
+        .and_then(|p| p.get("payload"))  // Get HttpRequest from MessagePayload::Http
+        .and_then(|req| req.get("body"))
+        .and_then(|b| b.as_str())
+        .unwrap_or("{}");
+
+    // Parse the body JSON string to get the actual response fields
+    let parsed_response: serde_json::Value =
+        serde_json::from_str(body_json_str).unwrap_or_else(|_| serde_json::json!({}));
+
+    let http_response = toru_plugin_api::HttpMessageResponse {
+        status: parsed_response
+            .get("status")
+            .and_then(|s| s.as_u64())
+            .unwrap_or(500) as u16,
+        headers: parsed_response
+            .get("headers")
+            .and_then(|h| serde_json::from_value(h.clone()).ok())
+            .unwrap_or_default(),
+        body: parsed_response
+            .get("body")
+            .and_then(|b| {
+                // body can be either a string or null
+                if b.is_string() {
+                    Some(b.as_str().unwrap().to_string())
+                } else if b.is_null() {
+                    None
+                } else {
+                    // If body is an object/array, serialize it
+                    Some(serde_json::to_string(b).unwrap_or_default())
+                }
+            }),
+    };
+
+    Ok(http_response)
+}
+
+/// How long a new version has to answer the readiness probe of an upgrade
+const READINESS_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Wait for a freshly started plugin to bind its socket, accept init and
+/// answer a request
+///
+/// The probe is a `GET /bundle.js`; a response of any status shows the
+/// plugin serves requests.
+///
+/// # Arguments
+/// * `instance_id` - Instance ID passed in the init payload
+/// * `plugin_id` - Plugin identifier
+/// * `process` - The new process; fails early if it exits while waiting
+async fn wait_until_ready(
+    instance_id: &str,
+    plugin_id: &str,
+    process: &mut PluginProcess,
+) -> Result<()> {
+    let socket_path = PathBuf::from(&process.socket_path);
+
+    for _ in 0..100 {
+        if let Some(child) = process.process.as_mut() {
+            if let Some(status) = child.try_wait()? {
+                return Err(anyhow::anyhow!("New version exited early ({})", status));
+            }
+        }
+        if socket_path.exists() {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+
+    send_init(
+        instance_id,
+        plugin_id,
+        &process.socket_path,
+        &process.host_socket_path,
+        process.data_dir.as_deref(),
+        0,
+    )
+    .await?;
+
+    // A process that took init may still never serve; only route traffic
+    // to it once it answers
+    let probe = HttpRequest {
+        method: "GET".to_string(),
+        path: "/bundle.js".to_string(),
+        headers: HashMap::new(),
+        body: None,
+    };
+    match tokio::time::timeout(
+        READINESS_PROBE_TIMEOUT,
+        forward_to_socket(&process.socket_path, &probe),
+    )
+    .await
+    {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.context("New version failed the readiness probe")),
+        Err(_) => Err(anyhow::anyhow!(
+            "New version did not answer the readiness probe within {:?}",
+            READINESS_PROBE_TIMEOUT
+        )),
+    }
+}
+
+/// Send the lifecycle init message to a plugin socket
+///
+/// # Arguments
+/// * `instance_id` - Instance ID passed to the plugin
+/// * `plugin_id` - Plugin identifier
+/// * `socket_path` - Plugin socket
+/// * `host_socket_path` - Host socket the plugin may push messages to
+/// * `data_dir` - Private directory for the plugin's files
+/// * `retries` - Number of 100ms waits for the socket to appear
+async fn send_init(
+    instance_id: &str,
+    plugin_id: &str,
+    socket_path: &str,
+    host_socket_path: &str,
+    data_dir: Option<&Path>,
+    mut retries: u32,
+) -> Result<()> {
+    use toru_plugin_api::{LifecycleInitPayload, PluginProtocol};
+
+    // Wait for socket to be available (with timeout)
+    let path = Path::new(socket_path);
+    while !path.exists() && retries > 0 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        retries -= 1;
+    }
+
+    if !path.exists() {
+        return Err(anyhow::anyhow!("Plugin socket not available after waiting"));
+    }
+
+    // Connect to plugin socket
+    let mut stream = UnixStream::connect(socket_path)
+        .await
+        .context("Failed to connect to plugin socket")?;
+
+    // Create init message with instance_id
+    let init_payload = LifecycleInitPayload {
+        instance_id: instance_id.to_string(),
+        plugin_socket: socket_path.to_string(),
+        log_path: format!("/var/log/toru/plugins/{}.log", plugin_id),
+        host_socket: Some(host_socket_path.to_string()),
+        data_dir: data_dir.map(|dir| dir.to_string_lossy().into_owned()),
+    };
+
+    let message = Message::new_lifecycle("init", Some(init_payload));
+
+    // Send message using length-prefixed protocol
+    let protocol = PluginProtocol::new();
+    protocol
+        .write_message(&mut stream, &message)
+        .await
+        .context("Failed to send init message")?;
+
+    Ok(())
+}
+
+/// Send the lifecycle shutdown message to a plugin socket
+///
+/// # Arguments
+/// * `plugin_id` - Plugin identifier
+/// * `socket_path` - Plugin socket
+async fn send_shutdown(plugin_id: &str, socket_path: &str) -> Result<()> {
+    use toru_plugin_api::PluginProtocol;
+
+    if !Path::new(socket_path).exists() {
+        debug!(
+            "Plugin {} socket not found, skipping shutdown message",
+            plugin_id
+        );
+        return Ok(());
+    }
+
+    // Connect to plugin socket
+    let mut stream = UnixStream::connect(socket_path)
+        .await
+        .context("Failed to connect to plugin socket")?;
+
+    // Create shutdown message
+    let message = Message::new_lifecycle("shutdown", None);
+
+    // Send message using length-prefixed protocol
+    let protocol = PluginProtocol::new();
+    protocol
+        .write_message(&mut stream, &message)
+        .await
+        .context("Failed to send shutdown message")?;
+
+    debug!("Sent shutdown message to plugin {}", plugin_id);
+    Ok(())
+}
+
+/// Stop a process that is no longer routed to and clean up its sockets
+///
+/// # Arguments
+/// * `plugin_id` - Plugin identifier
+/// * `process` - Process to stop
+/// * `graceful` - Drain in-flight requests and send `shutdown` before killing
+async fn stop_process(plugin_id: &str, mut process: PluginProcess, graceful: bool) {
+    if graceful {
+        // Drain requests that were forwarded before routing switched
+        for _ in 0..300 {
+            if process.in_flight.load(Ordering::SeqCst) == 0 {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+
+        if let Err(e) = send_shutdown(plugin_id, &process.socket_path).await {
+            debug!("Failed to send shutdown to plugin {}: {}", plugin_id, e);
+        }
+    }
+
+    if let Some(mut child) = process.process.take() {
+        if graceful
+            && tokio::time::timeout(tokio::time::Duration::from_secs(5), child.wait())
+                .await
+                .is_err()
+        {
+            warn!("Plugin {} did not exit within 5s, forcing", plugin_id);
+        }
+        child.start_kill().ok();
+        child.wait().await.ok();
+    }
+
+    if let Some(host_listener) = process.host_listener.take() {
+        host_listener.abort();
+    }
+    fs::remove_file(&process.socket_path).ok();
+    fs::remove_file(&process.host_socket_path).ok();
 }
 
 #[cfg(test)]
@@ -1083,4 +2479,58 @@ mod tests {
 
         assert!(supervisor.should_disable_plugin("test"));
     }
+
+    #[cfg(feature = "wasm")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_supervised() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let plugins_dir = temp_dir.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        fs::write(
+            plugins_dir.join("wasm-test.wasm"),
+            wasm_runtime::tests::guest_wat("wasm-supervised", ""),
+        )
+        .unwrap();
+
+        let db_pool = db::init_db().unwrap();
+        let mut supervisor = PluginSupervisor::new(
+            &plugins_dir,
+            10,
+            "test-instance-id".to_string(),
+            temp_dir.path().join("logs"),
+            db_pool,
+        )
+        .unwrap();
+
+        assert_eq!(supervisor.initialize().await.unwrap(), 1);
+        let plugin = supervisor.get_plugin_status("wasm-supervised").unwrap();
+        assert_eq!(plugin.kind, PluginKind::Wasm);
+        assert!(plugin.is_running());
+        assert!(supervisor.check_plugin_health("wasm-supervised"));
+        assert_eq!(
+            supervisor
+                .get_plugin_for_route("/wasm-supervised")
+                .as_deref(),
+            Some("wasm-supervised")
+        );
+
+        let request = HttpRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        };
+        let response = supervisor
+            .forward_http_request("wasm-supervised", &request)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        supervisor.kill_plugin("wasm-supervised").await.unwrap();
+        assert!(!supervisor
+            .get_plugin_status("wasm-supervised")
+            .unwrap()
+            .is_running());
+        assert!(supervisor.http_target("wasm-supervised").is_err());
+    }
 }
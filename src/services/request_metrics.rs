@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in milliseconds
+///
+/// Requests slower than the last bound fall in an overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 13] = [
+    1, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000,
+];
+
+/// What happened to one request forwarded to a plugin
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOutcome {
+    /// Status sent to the client, including the core's own 502/503/504
+    pub status: u16,
+    pub latency: Duration,
+    /// Size of the request body
+    pub bytes_in: u64,
+    /// Size of the response body
+    pub bytes_out: u64,
+    pub timed_out: bool,
+    /// Rejected because the plugin's circuit was open
+    pub rejected: bool,
+}
+
+/// Counters of one plugin
+#[derive(Debug, Clone)]
+struct PluginCounters {
+    since: DateTime<Utc>,
+    requests: u64,
+    timeouts: u64,
+    rejected: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    statuses: BTreeMap<u16, u64>,
+    // One count per bound in LATENCY_BUCKETS_MS, then the overflow bucket
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    latency_sum_us: u64,
+    latency_max_us: u64,
+}
+
+impl PluginCounters {
+    fn new(since: DateTime<Utc>) -> Self {
+        Self {
+            since,
+            requests: 0,
+            timeouts: 0,
+            rejected: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            statuses: BTreeMap::new(),
+            buckets: [0; LATENCY_BUCKETS_MS.len() + 1],
+            latency_sum_us: 0,
+            latency_max_us: 0,
+        }
+    }
+
+    fn record(&mut self, outcome: &RequestOutcome) {
+        self.requests += 1;
+        self.timeouts += outcome.timed_out as u64;
+        self.rejected += outcome.rejected as u64;
+        self.bytes_in += outcome.bytes_in;
+        self.bytes_out += outcome.bytes_out;
+        *self.statuses.entry(outcome.status).or_default() += 1;
+
+        let latency_us = outcome.latency.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_us <= bound * 1000)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.latency_sum_us = self.latency_sum_us.saturating_add(latency_us);
+        self.latency_max_us = self.latency_max_us.max(latency_us);
+    }
+
+    fn mean_ms(&self) -> Option<f64> {
+        (self.requests > 0).then(|| round_ms(self.latency_sum_us / self.requests))
+    }
+
+    /// Estimate a percentile as the upper bound of the bucket it falls in
+    fn percentile_ms(&self, percentile: f64) -> Option<f64> {
+        if self.requests == 0 {
+            return None;
+        }
+        let rank = ((self.requests as f64 * percentile / 100.0).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound_us = LATENCY_BUCKETS_MS.get(i).map_or(u64::MAX, |ms| ms * 1000);
+                return Some(round_ms(bound_us.min(self.latency_max_us)));
+            }
+        }
+        Some(round_ms(self.latency_max_us))
+    }
+
+    fn errors(&self) -> u64 {
+        self.statuses.range(500..).map(|(_, count)| count).sum()
+    }
+}
+
+/// Milliseconds with microsecond precision
+fn round_ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
+
+/// Request counters of a plugin, as reported by the metrics endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PluginRequestMetrics {
+    /// When counting started: the first request since the core started
+    pub since: DateTime<Utc>,
+    pub requests: u64,
+    pub timeouts: u64,
+    /// Requests rejected while the plugin's circuit was open
+    pub rejected: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Requests by status code
+    pub status: BTreeMap<u16, u64>,
+    /// Requests by status class (`2xx`, `4xx`, ...)
+    pub status_class: BTreeMap<String, u64>,
+    pub latency: LatencyHistogram,
+}
+
+/// Latency distribution of a plugin's requests
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyHistogram {
+    /// Cumulative counts; the last bucket has no bound and counts all requests
+    pub buckets: Vec<LatencyBucket>,
+    pub mean_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    /// Percentiles, estimated as the upper bound of their bucket
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+}
+
+/// Requests that took at most `le_ms`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyBucket {
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Headline request numbers of a plugin, included in its status
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequestSummary {
+    pub requests: u64,
+    /// Requests answered with a 5xx status
+    pub errors: u64,
+    pub timeouts: u64,
+    pub mean_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+/// In-memory request counters of all plugins
+///
+/// Counters start at the first request after the core starts and are
+/// dropped when a plugin is removed.
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    plugins: Mutex<HashMap<String, PluginCounters>>,
+}
+
+impl RequestMetrics {
+    /// Count a forwarded request
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Plugin the request was forwarded to
+    /// * `outcome` - Status, latency and sizes of the request
+    pub fn record(&self, plugin_id: &str, outcome: &RequestOutcome) {
+        let mut plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        plugins
+            .entry(plugin_id.to_string())
+            .or_insert_with(|| PluginCounters::new(Utc::now()))
+            .record(outcome);
+    }
+
+    /// Full counters of a plugin, None before its first request
+    pub fn metrics(&self, plugin_id: &str) -> Option<PluginRequestMetrics> {
+        let plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = plugins.get(plugin_id)?;
+
+        let mut status_class = BTreeMap::new();
+        for (status, count) in &counters.statuses {
+            *status_class
+                .entry(format!("{}xx", status / 100))
+                .or_default() += count;
+        }
+
+        let mut cumulative = 0;
+        let buckets = counters
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                cumulative += count;
+                LatencyBucket {
+                    le_ms: LATENCY_BUCKETS_MS.get(i).copied(),
+                    count: cumulative,
+                }
+            })
+            .collect();
+
+        Some(PluginRequestMetrics {
+            since: counters.since,
+            requests: counters.requests,
+            timeouts: counters.timeouts,
+            rejected: counters.rejected,
+            bytes_in: counters.bytes_in,
+            bytes_out: counters.bytes_out,
+            status: counters.statuses.clone(),
+            status_class,
+            latency: LatencyHistogram {
+                buckets,
+                mean_ms: counters.mean_ms(),
+                max_ms: Some(round_ms(counters.latency_max_us)),
+                p50_ms: counters.percentile_ms(50.0),
+                p95_ms: counters.percentile_ms(95.0),
+                p99_ms: counters.percentile_ms(99.0),
+            },
+        })
+    }
+
+    /// Headline numbers of a plugin, None before its first request
+    pub fn summary(&self, plugin_id: &str) -> Option<RequestSummary> {
+        let plugins = self.plugins.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = plugins.get(plugin_id)?;
+        Some(RequestSummary {
+            requests: counters.requests,
+            errors: counters.errors(),
+            timeouts: counters.timeouts,
+            mean_ms: counters.mean_ms(),
+            p95_ms: counters.percentile_ms(95.0),
+        })
+    }
+
+    /// Drop the counters of a removed plugin
+    pub fn remove(&self, plugin_id: &str) {
+        self.plugins
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(plugin_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(status: u16, latency_ms: u64) -> RequestOutcome {
+        RequestOutcome {
+            status,
+            latency: Duration::from_millis(latency_ms),
+            bytes_in: 10,
+            bytes_out: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_request_metrics() {
+        let metrics = RequestMetrics::default();
+        assert_eq!(metrics.metrics("weather"), None);
+
+        for _ in 0..90 {
+            metrics.record("weather", &outcome(200, 3));
+        }
+        for _ in 0..8 {
+            metrics.record("weather", &outcome(404, 40));
+        }
+        metrics.record("weather", &outcome(500, 800));
+        metrics.record(
+            "weather",
+            &RequestOutcome {
+                timed_out: true,
+                ..outcome(504, 45_000)
+            },
+        );
+        metrics.record("clock", &outcome(200, 1));
+
+        let weather = metrics.metrics("weather").unwrap();
+        assert_eq!(weather.requests, 100);
+        assert_eq!(weather.timeouts, 1);
+        assert_eq!(weather.bytes_in, 1000);
+        assert_eq!(weather.bytes_out, 10_000);
+        assert_eq!(weather.status[&200], 90);
+        assert_eq!(weather.status[&504], 1);
+        assert_eq!(weather.status_class["2xx"], 90);
+        assert_eq!(weather.status_class["4xx"], 8);
+        assert_eq!(weather.status_class["5xx"], 2);
+
+        let latency = &weather.latency;
+        assert_eq!(latency.buckets.len(), LATENCY_BUCKETS_MS.len() + 1);
+        assert_eq!(latency.buckets[1].le_ms, Some(5));
+        assert_eq!(latency.buckets[1].count, 90);
+        assert_eq!(latency.buckets[4].count, 98);
+        let overflow = latency.buckets.last().unwrap();
+        assert_eq!((overflow.le_ms, overflow.count), (None, 100));
+        assert_eq!(latency.p50_ms, Some(5.0));
+        assert_eq!(latency.p95_ms, Some(50.0));
+        assert_eq!(latency.p99_ms, Some(1000.0));
+        assert_eq!(latency.max_ms, Some(45_000.0));
+
+        let summary = metrics.summary("weather").unwrap();
+        assert_eq!(summary.requests, 100);
+        assert_eq!(summary.errors, 2);
+        assert_eq!(summary.timeouts, 1);
+        assert_eq!(summary.p95_ms, Some(50.0));
+
+        // A single fast request reports its own latency, not the bucket bound
+        assert_eq!(metrics.summary("clock").unwrap().p95_ms, Some(1.0));
+
+        metrics.remove("weather");
+        assert_eq!(metrics.summary("weather"), None);
+        assert!(metrics.summary("clock").is_some());
+    }
+}
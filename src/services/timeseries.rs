@@ -0,0 +1,385 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use toru_plugin_api::MetricSample;
+
+use crate::db::{self, DbPool, MetricRow};
+
+/// Maximum number of samples accepted in a single push
+pub const MAX_SAMPLES_PER_PUSH: usize = 1000;
+/// Maximum number of labels per sample
+const MAX_LABELS: usize = 16;
+/// Maximum number of buckets a single query may produce per series: a
+/// week at one-minute steps
+const MAX_POINTS: i64 = 7 * 24 * 60;
+/// How far ahead of the core's clock a sample timestamp may be (seconds)
+const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// Aggregation applied to samples falling into the same query step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+}
+
+impl Aggregation {
+    /// Convert string to Aggregation
+    pub fn parse(agg: &str) -> Option<Self> {
+        match agg.to_lowercase().as_str() {
+            "avg" | "mean" => Some(Aggregation::Avg),
+            "sum" => Some(Aggregation::Sum),
+            "min" => Some(Aggregation::Min),
+            "max" => Some(Aggregation::Max),
+            "count" => Some(Aggregation::Count),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, bucket: &db::MetricBucket) -> f64 {
+        match self {
+            Aggregation::Avg => bucket.sum / bucket.count.max(1) as f64,
+            Aggregation::Sum => bucket.sum,
+            Aggregation::Min => bucket.min,
+            Aggregation::Max => bucket.max,
+            Aggregation::Count => bucket.count as f64,
+        }
+    }
+}
+
+/// Retention and downsampling policy, read from the settings table
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// How long raw samples are kept before being rolled up (hours)
+    pub raw_retention_hours: i64,
+    /// Bucket size of rolled-up samples (seconds)
+    pub rollup_resolution_secs: i64,
+    /// How long rolled-up samples are kept (days)
+    pub rollup_retention_days: i64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_retention_hours: 48,
+            rollup_resolution_secs: 3600,
+            rollup_retention_days: 90,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Load the policy from settings, falling back to defaults for missing keys
+    pub async fn from_settings(pool: &DbPool) -> Self {
+        let defaults = Self::default();
+        let get = |key: &'static str, default: i64| async move {
+            db::get_setting(pool, key)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            raw_retention_hours: get(
+                "timeseries_raw_retention_hours",
+                defaults.raw_retention_hours,
+            )
+            .await,
+            rollup_resolution_secs: get(
+                "timeseries_rollup_resolution_secs",
+                defaults.rollup_resolution_secs,
+            )
+            .await,
+            rollup_retention_days: get(
+                "timeseries_rollup_retention_days",
+                defaults.rollup_retention_days,
+            )
+            .await,
+        }
+    }
+}
+
+/// A single labelled series in a query result
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub labels: BTreeMap<String, String>,
+    /// (bucket start as unix seconds, aggregated value)
+    pub points: Vec<(i64, f64)>,
+}
+
+/// Query parameters for a time-series range query
+#[derive(Debug, Clone)]
+pub struct RangeQuery {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub aggregation: Aggregation,
+}
+
+/// Metric names: ASCII alphanumerics, '_', '.', ':' and '-'
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-'))
+}
+
+/// Label keys end up in a JSON path, so they are restricted further
+pub fn is_valid_label_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 64 && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Validate a pushed sample
+pub fn validate_sample(sample: &MetricSample) -> Result<()> {
+    if !is_valid_name(&sample.name) {
+        return Err(anyhow::anyhow!("Invalid metric name: {:?}", sample.name));
+    }
+    if !sample.value.is_finite() {
+        return Err(anyhow::anyhow!(
+            "Metric {} has a non-finite value",
+            sample.name
+        ));
+    }
+    if sample.labels.len() > MAX_LABELS {
+        return Err(anyhow::anyhow!(
+            "Metric {} has more than {} labels",
+            sample.name,
+            MAX_LABELS
+        ));
+    }
+    for (key, value) in &sample.labels {
+        if !is_valid_label_key(key) || value.len() > 256 {
+            return Err(anyhow::anyhow!(
+                "Metric {} has an invalid label {:?}",
+                sample.name,
+                key
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate and store samples pushed by a plugin
+///
+/// The whole push is rejected if any sample is invalid, or timestamped more
+/// than `MAX_CLOCK_SKEW_SECS` ahead or older than the rollup retention.
+///
+/// # Returns
+/// Number of samples stored
+pub async fn record_samples(
+    pool: &DbPool,
+    plugin_id: &str,
+    samples: &[MetricSample],
+) -> Result<usize> {
+    if samples.len() > MAX_SAMPLES_PER_PUSH {
+        return Err(anyhow::anyhow!(
+            "Too many samples in one push ({} > {})",
+            samples.len(),
+            MAX_SAMPLES_PER_PUSH
+        ));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let config = RetentionConfig::from_settings(pool).await;
+    let oldest = now - config.rollup_retention_days * 24 * 3600;
+    let mut rows = Vec::with_capacity(samples.len());
+    for sample in samples {
+        validate_sample(sample)?;
+        let timestamp = sample.timestamp.map(|t| t.timestamp()).unwrap_or(now);
+        if timestamp > now + MAX_CLOCK_SKEW_SECS || timestamp < oldest {
+            return Err(anyhow::anyhow!(
+                "Metric {} has a timestamp outside the retention window",
+                sample.name
+            ));
+        }
+        rows.push(MetricRow {
+            name: sample.name.clone(),
+            // BTreeMap serializes with sorted keys, so identical label sets
+            // always produce the same string
+            labels: serde_json::to_string(&sample.labels)?,
+            timestamp,
+            value: sample.value,
+        });
+    }
+
+    db::plugin_metrics_insert(pool, plugin_id, &rows).await?;
+    Ok(rows.len())
+}
+
+/// Run a range query and group the result by label set
+pub async fn query_range(
+    pool: &DbPool,
+    plugin_id: &str,
+    query: &RangeQuery,
+) -> Result<Vec<Series>> {
+    if query.step <= 0 || query.end <= query.start {
+        return Err(anyhow::anyhow!("Invalid range or step"));
+    }
+    if (query.end - query.start) / query.step > MAX_POINTS {
+        return Err(anyhow::anyhow!(
+            "Query would return more than {} points per series, increase step",
+            MAX_POINTS
+        ));
+    }
+    if let Some((key, _)) = query.labels.iter().find(|(k, _)| !is_valid_label_key(k)) {
+        return Err(anyhow::anyhow!("Invalid label key: {:?}", key));
+    }
+
+    let buckets = db::plugin_metrics_query(
+        pool,
+        plugin_id,
+        &query.name,
+        &query.labels,
+        query.start,
+        query.end,
+        query.step,
+    )
+    .await?;
+
+    let mut series: Vec<Series> = Vec::new();
+    let mut current_labels: Option<String> = None;
+    for bucket in buckets {
+        if current_labels.as_deref() != Some(bucket.labels.as_str()) {
+            series.push(Series {
+                labels: serde_json::from_str(&bucket.labels).unwrap_or_default(),
+                points: Vec::new(),
+            });
+            current_labels = Some(bucket.labels.clone());
+        }
+        if let Some(s) = series.last_mut() {
+            s.points
+                .push((bucket.bucket, query.aggregation.apply(&bucket)));
+        }
+    }
+
+    Ok(series)
+}
+
+/// Downsample raw samples past retention and drop expired rollups
+pub async fn run_maintenance(pool: &DbPool) -> Result<usize> {
+    let config = RetentionConfig::from_settings(pool).await;
+    let now = chrono::Utc::now().timestamp();
+    let raw_cutoff = now - config.raw_retention_hours * 3600;
+    let rollup_cutoff = now - config.rollup_retention_days * 24 * 3600;
+
+    db::plugin_metrics_downsample(
+        pool,
+        raw_cutoff,
+        config.rollup_resolution_secs,
+        rollup_cutoff,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str, value: f64, labels: &[(&str, &str)], timestamp: i64) -> MetricSample {
+        MetricSample {
+            name: name.to_string(),
+            value,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            timestamp: chrono::DateTime::from_timestamp(timestamp, 0),
+        }
+    }
+
+    #[test]
+    fn test_validate_sample() {
+        assert!(validate_sample(&sample("queue.depth", 1.0, &[("queue", "a")], 0)).is_ok());
+        assert!(validate_sample(&sample("bad name", 1.0, &[], 0)).is_err());
+        assert!(validate_sample(&sample("x", f64::NAN, &[], 0)).is_err());
+        assert!(validate_sample(&sample("x", 1.0, &[("a.b", "c")], 0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_record_and_query_range() {
+        let pool = db::init_db().unwrap();
+        let plugin_id = format!("ts-test-{}", uuid::Uuid::new_v4());
+        let now = chrono::Utc::now().timestamp();
+        let base = (now - 3600) / 60 * 60;
+
+        let samples = vec![
+            sample("runs", 1.0, &[("agent", "a")], base),
+            sample("runs", 3.0, &[("agent", "a")], base + 30),
+            sample("runs", 5.0, &[("agent", "a")], base + 70),
+            sample("runs", 10.0, &[("agent", "b")], base + 10),
+        ];
+        assert_eq!(
+            record_samples(&pool, &plugin_id, &samples).await.unwrap(),
+            4
+        );
+
+        // Timestamps far from the core's clock are rejected with their push
+        for timestamp in [now + 3600, 0] {
+            let pushed = vec![
+                sample("runs", 1.0, &[("agent", "a")], base),
+                sample("runs", 1.0, &[("agent", "a")], timestamp),
+            ];
+            assert!(record_samples(&pool, &plugin_id, &pushed).await.is_err());
+        }
+
+        let query = RangeQuery {
+            name: "runs".to_string(),
+            labels: vec![("agent".to_string(), "a".to_string())],
+            start: base,
+            end: base + 120,
+            step: 60,
+            aggregation: Aggregation::Sum,
+        };
+        let series = query_range(&pool, &plugin_id, &query).await.unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].points, vec![(base, 4.0), (base + 60, 5.0)]);
+
+        let query = RangeQuery {
+            labels: Vec::new(),
+            aggregation: Aggregation::Count,
+            ..query
+        };
+        let series = query_range(&pool, &plugin_id, &query).await.unwrap();
+        assert_eq!(series.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_downsample_keeps_aggregates() {
+        let pool = db::init_db().unwrap();
+        let plugin_id = format!("ts-test-{}", uuid::Uuid::new_v4());
+        // Hour-aligned, and old enough that rolling up affects no other test
+        let base = (chrono::Utc::now().timestamp() / 3600 - 80 * 24) * 3600;
+
+        let samples = vec![
+            sample("depth", 2.0, &[], base),
+            sample("depth", 4.0, &[], base + 60),
+        ];
+        record_samples(&pool, &plugin_id, &samples).await.unwrap();
+
+        db::plugin_metrics_downsample(&pool, base + 3600, 3600, 0)
+            .await
+            .unwrap();
+
+        let query = RangeQuery {
+            name: "depth".to_string(),
+            labels: Vec::new(),
+            start: base,
+            end: base + 3600,
+            step: 3600,
+            aggregation: Aggregation::Avg,
+        };
+        let series = query_range(&pool, &plugin_id, &query).await.unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].points, vec![(base, 3.0)]);
+    }
+}
@@ -0,0 +1,842 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tracing::debug;
+use wasmtime::{
+    Caller, Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use toru_plugin_api::{
+    HttpMessageResponse, HttpRequest, PluginError, PluginMetadata, SqlMessageResponse, SqlOp,
+};
+
+use super::logging::{LogEntry, LogLevel, PluginLogger};
+use super::metadata_cache::{self, validate_metadata};
+use super::plugin_database::SqlitePluginDatabase;
+use crate::db::{self, DbPool};
+
+/// Module name of the host functions imported by WebAssembly plugins
+const HOST_MODULE: &str = "toru";
+
+/// Longest log message accepted from a WebAssembly plugin
+const MAX_LOG_MESSAGE: usize = 16 * 1024;
+
+/// Largest buffer exchanged with a plugin in either direction
+const MAX_GUEST_BUFFER: usize = 16 * 1024 * 1024;
+
+/// Whether a file is a WebAssembly plugin
+pub fn is_wasm_plugin(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("wasm")
+}
+
+/// Resource limits applied to each WebAssembly plugin instance
+#[derive(Debug, Clone)]
+pub struct WasmLimits {
+    /// Fuel available to each call into the plugin (roughly one unit per instruction)
+    pub fuel_per_call: u64,
+    /// Maximum linear memory of an instance in bytes
+    pub max_memory_bytes: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel_per_call: 500_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl WasmLimits {
+    /// Load the limits from settings, falling back to defaults for missing keys
+    pub async fn from_settings(pool: &DbPool) -> Self {
+        let defaults = Self::default();
+        let get = |key: &'static str, default: u64| async move {
+            db::get_setting(pool, key)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+
+        let max_memory_mb = get(
+            "wasm_max_memory_mb",
+            (defaults.max_memory_bytes / (1024 * 1024)) as u64,
+        )
+        .await;
+
+        Self {
+            fuel_per_call: get("wasm_fuel_per_call", defaults.fuel_per_call).await,
+            max_memory_bytes: max_memory_mb as usize * 1024 * 1024,
+        }
+    }
+}
+
+/// Per-instance state reachable from host functions
+struct HostState {
+    /// Empty while reading metadata, when KV and SQL are unavailable
+    plugin_id: String,
+    db_pool: DbPool,
+    plugin_logger: Arc<PluginLogger>,
+    database: Option<SqlitePluginDatabase>,
+    /// Guest code always runs on a blocking thread, so host functions
+    /// may block on this runtime to reach async services
+    handle: Handle,
+    limits: StoreLimits,
+}
+
+/// Compiles WebAssembly plugins and creates sandboxed instances
+///
+/// Plugins implement the same contract as process plugins through a small
+/// JSON ABI. The module exports `memory`, `toru_alloc(len) -> ptr`,
+/// `toru_metadata() -> packed`, `toru_init(ptr, len) -> packed` and
+/// `toru_handle_http(ptr, len) -> packed`, where `packed` is
+/// `(ptr << 32) | len` of a result in guest memory. Host functions
+/// `kv_get`, `kv_set`, `kv_delete`, `sql` and `log` are imported from `toru`.
+#[derive(Clone)]
+pub struct WasmRuntime {
+    engine: Engine,
+    // Compiled modules by path and content hash, so scans and spawns compile once
+    modules: Arc<Mutex<HashMap<PathBuf, (String, Module)>>>,
+    db_pool: DbPool,
+    plugin_logger: Arc<PluginLogger>,
+}
+
+impl std::fmt::Debug for WasmRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmRuntime").finish_non_exhaustive()
+    }
+}
+
+impl WasmRuntime {
+    /// Create a runtime with fuel metering enabled
+    ///
+    /// # Arguments
+    /// * `db_pool` - Database backing the plugins' KV storage
+    /// * `plugin_logger` - Logger receiving the plugins' log messages
+    pub fn new(db_pool: DbPool, plugin_logger: Arc<PluginLogger>) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).context("Failed to create WebAssembly engine")?;
+
+        Ok(Self {
+            engine,
+            modules: Arc::new(Mutex::new(HashMap::new())),
+            db_pool,
+            plugin_logger,
+        })
+    }
+
+    /// Compile a plugin module, reusing the previous compilation if unchanged
+    fn load_module(&self, path: &Path) -> Result<Module> {
+        let hash = metadata_cache::fingerprint(path)?.sha256;
+        if let Some((cached_hash, module)) = self
+            .modules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(path)
+        {
+            if *cached_hash == hash {
+                return Ok(module.clone());
+            }
+        }
+
+        debug!("Compiling WebAssembly plugin {:?}", path);
+        let module = Module::from_file(&self.engine, path)
+            .with_context(|| format!("Failed to compile WebAssembly plugin {:?}", path))?;
+
+        self.modules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf(), (hash, module.clone()));
+        Ok(module)
+    }
+
+    /// Compile and instantiate a plugin
+    ///
+    /// # Arguments
+    /// * `plugin_id` - Identity used for KV storage and logs
+    /// * `path` - Path to the `.wasm` file
+    /// * `limits` - Fuel and memory limits for the instance
+    /// * `database` - The plugin's SQL database, reached through `sql`
+    pub async fn instantiate(
+        &self,
+        plugin_id: &str,
+        path: &Path,
+        limits: WasmLimits,
+        database: Option<SqlitePluginDatabase>,
+    ) -> Result<Arc<WasmPlugin>> {
+        let state = HostState {
+            plugin_id: plugin_id.to_string(),
+            db_pool: self.db_pool.clone(),
+            plugin_logger: Arc::clone(&self.plugin_logger),
+            database,
+            handle: Handle::current(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory_bytes)
+                .instances(1)
+                .build(),
+        };
+
+        // Compilation and guest code are CPU-bound, keep them off the async threads
+        let runtime = self.clone();
+        let path = path.to_path_buf();
+        let plugin = tokio::task::spawn_blocking(move || {
+            let module = runtime.load_module(&path)?;
+            WasmPlugin::instantiate(&runtime.engine, &module, state, limits.fuel_per_call)
+        })
+        .await
+        .context("WebAssembly plugin task failed")??;
+
+        Ok(Arc::new(plugin))
+    }
+
+    /// Read a plugin's metadata by instantiating it and calling `toru_metadata`
+    ///
+    /// # Arguments
+    /// * `path` - Path to the `.wasm` file
+    pub async fn read_metadata(&self, path: &Path) -> Result<PluginMetadata> {
+        // The ID is not known yet, so KV and SQL calls trap
+        let plugin = self
+            .instantiate("", path, WasmLimits::default(), None)
+            .await?;
+        Ok(plugin.metadata.clone())
+    }
+
+    /// Forget compiled modules whose files are gone
+    ///
+    /// # Arguments
+    /// * `paths` - `.wasm` files found by the latest scan
+    pub fn retain_paths(&self, paths: &std::collections::HashSet<PathBuf>) {
+        self.modules
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|path, _| paths.contains(path));
+    }
+}
+
+/// A running WebAssembly plugin instance
+///
+/// Calls are serialized: an instance handles one request at a time, and
+/// each call gets a fresh fuel budget.
+pub struct WasmPlugin {
+    pub metadata: PluginMetadata,
+    store: Mutex<Store<HostState>>,
+    instance: Instance,
+    fuel_per_call: u64,
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("id", &self.metadata.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmPlugin {
+    fn instantiate(
+        engine: &Engine,
+        module: &Module,
+        state: HostState,
+        fuel_per_call: u64,
+    ) -> Result<Self> {
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(fuel_per_call)?;
+
+        let linker = host_linker(engine)?;
+        let instance = linker
+            .instantiate(&mut store, module)
+            .context("Failed to instantiate WebAssembly plugin")?;
+
+        let packed = instance
+            .get_typed_func::<(), i64>(&mut store, "toru_metadata")
+            .context("Plugin does not export toru_metadata")?
+            .call(&mut store, ())
+            .context("toru_metadata failed")?;
+        let output = read_packed(&instance, &mut store, packed)?;
+        let metadata: PluginMetadata =
+            serde_json::from_slice(&output).context("Failed to parse plugin metadata JSON")?;
+        validate_metadata(&metadata)?;
+
+        Ok(Self {
+            metadata,
+            store: Mutex::new(store),
+            instance,
+            fuel_per_call,
+        })
+    }
+
+    /// Call an export taking a JSON buffer and returning a packed buffer
+    fn call(&self, export: &str, input: &[u8]) -> Result<Vec<u8>> {
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.set_fuel(self.fuel_per_call)?;
+
+        let ptr = write_guest(&self.instance, &mut store, input)?;
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&mut *store, export)
+            .with_context(|| format!("Plugin does not export {}", export))?;
+        let packed = func
+            .call(&mut *store, (ptr, input.len() as i32))
+            .with_context(|| format!("{} failed", export))?;
+
+        read_packed(&self.instance, &mut store, packed)
+    }
+
+    /// Run `toru_init`; a non-empty result is an error message
+    ///
+    /// # Arguments
+    /// * `instance_id` - Instance ID passed in the init payload
+    pub async fn init(self: &Arc<Self>, instance_id: &str) -> Result<()> {
+        let payload = serde_json::to_vec(&serde_json::json!({ "instance_id": instance_id }))?;
+        let plugin = Arc::clone(self);
+        let output =
+            tokio::task::spawn_blocking(move || plugin.call("toru_init", &payload)).await??;
+
+        if output.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Plugin init failed: {}",
+                String::from_utf8_lossy(&output)
+            ))
+        }
+    }
+
+    /// Handle an HTTP request inside the sandbox
+    ///
+    /// # Arguments
+    /// * `request` - HTTP request to forward
+    pub async fn handle_http(
+        self: &Arc<Self>,
+        request: &HttpRequest,
+    ) -> Result<HttpMessageResponse> {
+        let payload = serde_json::to_vec(request)?;
+        let plugin = Arc::clone(self);
+        let output = tokio::task::spawn_blocking(move || plugin.call("toru_handle_http", &payload))
+            .await??;
+
+        serde_json::from_slice(&output).context("Failed to parse plugin HTTP response")
+    }
+}
+
+/// Split a packed `(ptr << 32) | len` value
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+fn pack(ptr: i32, len: usize) -> i64 {
+    (((ptr as u32 as u64) << 32) | len as u64) as i64
+}
+
+fn guest_memory(instance: &Instance, store: &mut Store<HostState>) -> Result<Memory> {
+    instance
+        .get_memory(&mut *store, "memory")
+        .context("Plugin does not export memory")
+}
+
+/// Copy a packed result out of guest memory
+fn read_packed(instance: &Instance, store: &mut Store<HostState>, packed: i64) -> Result<Vec<u8>> {
+    let (ptr, len) = unpack(packed);
+    if len > MAX_GUEST_BUFFER {
+        return Err(anyhow::anyhow!(
+            "Plugin result exceeds {} bytes",
+            MAX_GUEST_BUFFER
+        ));
+    }
+    let memory = guest_memory(instance, store)?;
+    let mut buffer = vec![0u8; len];
+    memory
+        .read(&*store, ptr, &mut buffer)
+        .context("Plugin result is out of bounds")?;
+    Ok(buffer)
+}
+
+/// Allocate a buffer in guest memory and copy `data` into it
+fn write_guest(instance: &Instance, store: &mut Store<HostState>, data: &[u8]) -> Result<i32> {
+    if data.len() > MAX_GUEST_BUFFER {
+        return Err(anyhow::anyhow!(
+            "Plugin input exceeds {} bytes",
+            MAX_GUEST_BUFFER
+        ));
+    }
+    let ptr = instance
+        .get_typed_func::<i32, i32>(&mut *store, "toru_alloc")
+        .context("Plugin does not export toru_alloc")?
+        .call(&mut *store, data.len() as i32)?;
+    guest_memory(instance, store)?
+        .write(&mut *store, ptr as u32 as usize, data)
+        .context("Plugin allocation is out of bounds")?;
+    Ok(ptr)
+}
+
+/// Plugin ID that KV and SQL calls are scoped to
+fn scoped_plugin_id(state: &HostState) -> Result<&str> {
+    if state.plugin_id.is_empty() {
+        return Err(anyhow::anyhow!(
+            "KV and SQL are not available while reading plugin metadata"
+        ));
+    }
+    Ok(&state.plugin_id)
+}
+
+/// Read a string argument passed to a host function
+fn read_caller_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String> {
+    let buffer = read_caller_bytes(caller, ptr, len)?;
+    String::from_utf8(buffer).context("Host call argument is not valid UTF-8")
+}
+
+/// Read a byte buffer argument passed to a host function
+fn read_caller_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let len = len as u32 as usize;
+    if len > MAX_GUEST_BUFFER {
+        return Err(anyhow::anyhow!(
+            "Host call argument exceeds {} bytes",
+            MAX_GUEST_BUFFER
+        ));
+    }
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .context("Plugin does not export memory")?;
+    let mut buffer = vec![0u8; len];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut buffer)
+        .context("Host call argument is out of bounds")?;
+    Ok(buffer)
+}
+
+/// Copy a host value into guest memory through the plugin's allocator
+fn write_caller_bytes(caller: &mut Caller<'_, HostState>, data: &[u8]) -> Result<i64> {
+    let alloc = caller
+        .get_export("toru_alloc")
+        .and_then(|export| export.into_func())
+        .context("Plugin does not export toru_alloc")?
+        .typed::<i32, i32>(&*caller)?;
+    let ptr = alloc.call(&mut *caller, data.len() as i32)?;
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .context("Plugin does not export memory")?;
+    memory
+        .write(&mut *caller, ptr as u32 as usize, data)
+        .context("Plugin allocation is out of bounds")?;
+    Ok(pack(ptr, data.len()))
+}
+
+/// Host functions available to plugins
+///
+/// KV functions are scoped to the instance's plugin ID, like the KV store
+/// of process plugins. `kv_get` returns 0 for missing keys; `kv_set` and
+/// `kv_delete` return 0 on success and -1 on failure. `sql` takes a JSON
+/// `SqlOp` and always returns a packed JSON `SqlMessageResponse`. KV and
+/// SQL calls trap while metadata is read, as no plugin ID is known yet.
+/// `log` messages are cut to `MAX_LOG_MESSAGE` bytes on a character boundary.
+fn host_linker(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "kv_get",
+        |mut caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32| -> Result<i64> {
+            let key = read_caller_string(&mut caller, key_ptr, key_len)?;
+            let state = caller.data();
+            let plugin_id = scoped_plugin_id(state)?;
+            let value =
+                state
+                    .handle
+                    .block_on(db::plugin_kv_get(&state.db_pool, plugin_id, &key))?;
+            match value {
+                Some(value) => write_caller_bytes(&mut caller, value.as_bytes()),
+                None => Ok(0),
+            }
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "kv_set",
+        |mut caller: Caller<'_, HostState>,
+         key_ptr: i32,
+         key_len: i32,
+         value_ptr: i32,
+         value_len: i32|
+         -> Result<i32> {
+            let key = read_caller_string(&mut caller, key_ptr, key_len)?;
+            let value = read_caller_string(&mut caller, value_ptr, value_len)?;
+            let state = caller.data();
+            let plugin_id = scoped_plugin_id(state)?;
+            let result =
+                state
+                    .handle
+                    .block_on(db::plugin_kv_set(&state.db_pool, plugin_id, &key, &value));
+            Ok(match result {
+                Ok(()) => 0,
+                Err(e) if e.is::<db::QuotaExceeded>() => -2,
+                Err(_) => -1,
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "kv_delete",
+        |mut caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32| -> Result<i32> {
+            let key = read_caller_string(&mut caller, key_ptr, key_len)?;
+            let state = caller.data();
+            let plugin_id = scoped_plugin_id(state)?;
+            let result =
+                state
+                    .handle
+                    .block_on(db::plugin_kv_delete(&state.db_pool, plugin_id, &key));
+            Ok(if result.is_ok() { 0 } else { -1 })
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "sql",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> Result<i64> {
+            let request = read_caller_string(&mut caller, ptr, len)?;
+            let state = caller.data();
+            scoped_plugin_id(state)?;
+            let response = match serde_json::from_str::<SqlOp>(&request) {
+                Ok(op) => match &state.database {
+                    Some(database) => state
+                        .handle
+                        .block_on(toru_plugin_api::sql::execute(database, op)),
+                    None => Err(PluginError::Internal(
+                        "No database is available to this plugin".to_string(),
+                    )),
+                },
+                Err(e) => Err(PluginError::InvalidRequest(format!(
+                    "Invalid SQL operation: {}",
+                    e
+                ))),
+            }
+            .unwrap_or_else(|e| SqlMessageResponse::failed(&e));
+            write_caller_bytes(&mut caller, &serde_json::to_vec(&response)?)
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| -> Result<()> {
+            let len = len.clamp(0, MAX_LOG_MESSAGE as i32);
+            let mut message = read_caller_bytes(&mut caller, ptr, len)?;
+            // The clamp may have split the last character
+            if let Err(e) = std::str::from_utf8(&message) {
+                if e.error_len().is_some() {
+                    return Err(anyhow::anyhow!("Log message is not valid UTF-8"));
+                }
+                message.truncate(e.valid_up_to());
+            }
+            let message = String::from_utf8(message)?;
+            let level = match level {
+                0 => LogLevel::Trace,
+                1 => LogLevel::Debug,
+                2 => LogLevel::Info,
+                3 => LogLevel::Warn,
+                _ => LogLevel::Error,
+            };
+            let state = caller.data();
+            state.handle.block_on(async {
+                if state
+                    .plugin_logger
+                    .within_log_quota(&state.plugin_id, None, message.len() as u64)
+                    .await
+                {
+                    let entry = LogEntry::new(level, &message).with_plugin(&state.plugin_id);
+                    let _ = state.plugin_logger.log_plugin(entry).await;
+                }
+            });
+            Ok(())
+        },
+    )?;
+
+    Ok(linker)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Guest that stores each request under `last_request` and answers with
+    /// the `greeting` KV value, or a fixed response when it is unset
+    const GUEST: &str = r#"
+(module
+  (import "toru" "kv_get" (func $kv_get (param i32 i32) (result i64)))
+  (import "toru" "kv_set" (func $kv_set (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 0) "METADATA")
+  (data (i32.const 256) "greeting")
+  (data (i32.const 272) "last_request")
+  (data (i32.const 512) "RESPONSE")
+  (func $pack (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+  (func (export "toru_alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "toru_metadata") (result i64)
+    (call $pack (i32.const 0) (i32.const METADATA_LEN)))
+  (func (export "toru_init") (param i32 i32) (result i64)
+    (i64.const 0))
+  (func (export "toru_handle_http") (param $ptr i32) (param $len i32) (result i64)
+    (local $value i64)
+    (drop (call $kv_set (i32.const 272) (i32.const 12) (local.get $ptr) (local.get $len)))
+    (local.set $value (call $kv_get (i32.const 256) (i32.const 8)))
+    (if (result i64) (i64.eqz (local.get $value))
+      (then (call $pack (i32.const 512) (i32.const RESPONSE_LEN)))
+      (else (local.get $value))))
+)
+"#;
+
+    pub(crate) fn guest_wat(plugin_id: &str, extra: &str) -> String {
+        let metadata = format!(
+            r#"{{"id":"{id}","name":"Wasm Test","version":"0.1.0","author":null,"icon":"W","route":"/{id}"}}"#,
+            id = plugin_id
+        );
+        let response = r#"{"status":200,"headers":{},"body":"hello from wasm"}"#;
+        GUEST
+            .replace("METADATA_LEN", &metadata.len().to_string())
+            .replace("RESPONSE_LEN", &response.len().to_string())
+            .replace("METADATA", &metadata.replace('"', "\\\""))
+            .replace("RESPONSE", &response.replace('"', "\\\""))
+            .replace("\n)\n", &format!("\n{}\n)\n", extra))
+    }
+
+    fn runtime(temp_dir: &TempDir) -> WasmRuntime {
+        let pool = crate::db::init_db().unwrap();
+        let logger = Arc::new(PluginLogger::from_directory(temp_dir.path().join("logs")).unwrap());
+        WasmRuntime::new(pool, logger).unwrap()
+    }
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_http_and_kv() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("wasm-test.wasm");
+        std::fs::write(&path, guest_wat("wasm-test", "")).unwrap();
+
+        let runtime = runtime(&temp_dir);
+        assert_eq!(runtime.read_metadata(&path).await.unwrap().id, "wasm-test");
+
+        let plugin = runtime
+            .instantiate("wasm-test", &path, WasmLimits::default(), None)
+            .await
+            .unwrap();
+        plugin.init("instance").await.unwrap();
+
+        db::plugin_kv_delete(&runtime.db_pool, "wasm-test", "greeting")
+            .await
+            .unwrap();
+        let response = plugin.handle_http(&request()).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.as_deref(), Some("hello from wasm"));
+
+        // Host KV functions are scoped to the plugin
+        let stored = db::plugin_kv_get(&runtime.db_pool, "wasm-test", "last_request")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<HttpRequest>(&stored).unwrap().method,
+            "GET"
+        );
+
+        db::plugin_kv_set(
+            &runtime.db_pool,
+            "wasm-test",
+            "greeting",
+            r#"{"status":201,"headers":{},"body":"from kv"}"#,
+        )
+        .await
+        .unwrap();
+        let response = plugin.handle_http(&request()).await.unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body.as_deref(), Some("from kv"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_metadata_has_no_kv_access() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("wasm-nosy.wasm");
+        std::fs::write(
+            &path,
+            guest_wat("wasm-test", "").replace(
+                "(call $pack (i32.const 0)",
+                "(drop (call $kv_get (i32.const 256) (i32.const 8)))\n    (call $pack (i32.const 0)",
+            ),
+        )
+        .unwrap();
+
+        // Without a plugin ID the KV call traps instead of using an empty namespace
+        let runtime = runtime(&temp_dir);
+        assert!(runtime.read_metadata(&path).await.is_err());
+        runtime
+            .instantiate("wasm-test", &path, WasmLimits::default(), None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_log_truncated_on_char_boundary() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("wasm-log.wasm");
+        // One ASCII byte followed by two-byte characters, so the cut at
+        // MAX_LOG_MESSAGE bytes falls inside a character
+        let message = format!("a{}", "é".repeat(MAX_LOG_MESSAGE / 2));
+        std::fs::write(
+            &path,
+            guest_wat(
+                "wasm-test",
+                &format!(
+                    r#"(data (i32.const 4096) "{}")
+  (func (export "toru_log") (param i32 i32) (result i64)
+    (call $log (i32.const 2) (i32.const 4096) (i32.const {}))
+    (i64.const 0))"#,
+                    message,
+                    message.len()
+                ),
+            )
+            .replace(
+                "(memory (export",
+                "(import \"toru\" \"log\" (func $log (param i32 i32 i32)))\n  (memory (export",
+            ),
+        )
+        .unwrap();
+
+        let runtime = runtime(&temp_dir);
+        let plugin = runtime
+            .instantiate("wasm-test", &path, WasmLimits::default(), None)
+            .await
+            .unwrap();
+        let call = tokio::task::spawn_blocking(move || plugin.call("toru_log", b"{}"));
+        call.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_sql() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("wasm-sql.wasm");
+        std::fs::write(
+            &path,
+            guest_wat(
+                "wasm-test",
+                r#"(func (export "toru_sql") (param i32 i32) (result i64) (call $sql (local.get 0) (local.get 1)))"#,
+            )
+            .replace(
+                "(memory (export",
+                "(import \"toru\" \"sql\" (func $sql (param i32 i32) (result i64)))\n  (memory (export",
+            ),
+        )
+        .unwrap();
+
+        let runtime = runtime(&temp_dir);
+        let database =
+            SqlitePluginDatabase::open(&temp_dir.path().join("databases"), "wasm-test").unwrap();
+        let plugin = runtime
+            .instantiate("wasm-test", &path, WasmLimits::default(), Some(database))
+            .await
+            .unwrap();
+
+        let sql = |op: serde_json::Value| {
+            let plugin = Arc::clone(&plugin);
+            tokio::task::spawn_blocking(move || {
+                let output = plugin.call("toru_sql", op.to_string().as_bytes()).unwrap();
+                serde_json::from_slice::<SqlMessageResponse>(&output).unwrap()
+            })
+        };
+
+        let created = sql(serde_json::json!({
+            "action": "Batch",
+            "statements": [
+                {"sql": "CREATE TABLE notes (body TEXT)"},
+                {"sql": "INSERT INTO notes VALUES (?)", "params": ["hi"]}
+            ]
+        }))
+        .await
+        .unwrap();
+        assert!(created.error.is_none());
+
+        let rows = sql(serde_json::json!({"action": "Query", "sql": "SELECT body FROM notes"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.result,
+            Some(toru_plugin_api::SqlResult::Rows(toru_plugin_api::SqlRows {
+                columns: vec!["body".to_string()],
+                rows: vec![vec!["hi".into()]],
+            }))
+        );
+
+        let invalid = sql(serde_json::json!({"action": "Drop"})).await.unwrap();
+        assert_eq!(invalid.error_code.as_deref(), Some("invalid_request"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wasm_plugin_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = runtime(&temp_dir);
+
+        // A guest stuck in a loop runs out of fuel instead of hanging
+        let spinning = temp_dir.path().join("spinning.wasm");
+        std::fs::write(
+            &spinning,
+            guest_wat("wasm-test", r#"(func (export "toru_spin") (param i32 i32) (result i64) (loop $l (br $l)) (i64.const 0))"#),
+        )
+        .unwrap();
+        let limits = WasmLimits {
+            fuel_per_call: 100_000,
+            ..Default::default()
+        };
+        let plugin = runtime
+            .instantiate("wasm-test", &spinning, limits, None)
+            .await
+            .unwrap();
+        let call = tokio::task::spawn_blocking(move || plugin.call("toru_spin", b"{}"));
+        assert!(call.await.unwrap().is_err());
+
+        // Memory beyond the limit cannot be reserved
+        let greedy = temp_dir.path().join("greedy.wasm");
+        std::fs::write(
+            &greedy,
+            guest_wat("wasm-test", "").replace(
+                "(memory (export \"memory\") 1)",
+                "(memory (export \"memory\") 32)",
+            ),
+        )
+        .unwrap();
+        let limits = WasmLimits {
+            max_memory_bytes: 1024 * 1024,
+            ..Default::default()
+        };
+        assert!(runtime
+            .instantiate("wasm-test", &greedy, limits, None)
+            .await
+            .is_err());
+    }
+}
@@ -9,11 +9,24 @@
 // - T12-T15: Plugin lifecycle (enable/disable, persistence, crash restart)
 // - T18-T19: KV/Socket tests (protocol and error handling)
 // - T23: Observability (plugin events written to database)
+// - T24: Time series (metrics pushed over the host socket)
+// - T25: Zero-downtime upgrades (switch on readiness, keep old on failure)
+// - T26: Version rollback (archived binaries reinstated through spawn)
+// - T27-T28: Hot discovery (rescan diff, directory watcher)
+// - T29: Plugin packages (manifest discovery, assets, precedence over binaries)
+// - T30: SDK runtime (host-socket KV, concurrent requests, embedded bundle, graceful shutdown)
+// - T31: Test harness (plugin binary driven by the SDK's mock core)
+// - T32: Plugin KV (prefix listing, TTLs, compare-and-swap, batches over the host socket)
+// - T33: Admin KV browser (search, export and import in merge/replace mode)
+// - T34: Data directories (created on start, quota enforced while running and on start)
+// - T35: Plugin databases (package migrations on start, SQL over the host socket)
+// - T36: Event timeline (filters, cursor pagination, uptime and crash summary)
+// - T37: Circuit breaker (opens on failures, probe closes it, response size limit)
 //
 // Run with: cargo test --test plugins_integration -- --nocapture
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 // Import PluginSupervisor for actual integration tests
@@ -732,3 +745,1415 @@ async fn test_t23_plugin_events_written_to_database() {
 
     println!("✅ T23: Plugin events written to database via notify_plugin_event()");
 }
+
+// ============ T24: Plugin Time Series ============
+
+/// Test T24: Metrics pushed over the host socket are stored for the owning plugin
+#[tokio::test]
+async fn test_t24_metrics_pushed_over_host_socket_are_stored() {
+    use toru_plugin_api::{Message, MetricSample, PluginProtocol};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let plugins_dir = supervisor.get_plugins_dir();
+
+    let plugin_id = format!("metrics-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let binary_path = create_test_plugin(&plugins_dir, &plugin_id);
+    let metadata = toru_plugin_api::PluginMetadata {
+        id: plugin_id.clone(),
+        name: "Metrics Plugin".to_string(),
+        version: "1.0.0".to_string(),
+        author: None,
+        icon: "📈".to_string(),
+        route: format!("/{}", plugin_id),
+    };
+
+    supervisor
+        .spawn_plugin(&plugin_id, &binary_path, metadata)
+        .await
+        .expect("Failed to spawn plugin");
+
+    let host_socket = supervisor
+        .get_plugin_status(&plugin_id)
+        .expect("Plugin should have status")
+        .host_socket_path
+        .clone();
+
+    // Act as the plugin: push two samples to the core
+    let mut stream = tokio::net::UnixStream::connect(&host_socket)
+        .await
+        .expect("Host socket should accept connections");
+    let samples = vec![
+        MetricSample {
+            name: "conversions".to_string(),
+            value: 2.0,
+            labels: Default::default(),
+            timestamp: None,
+        },
+        MetricSample {
+            name: "conversions".to_string(),
+            value: 3.0,
+            labels: Default::default(),
+            timestamp: None,
+        },
+    ];
+    PluginProtocol::new()
+        .write_message(&mut stream, &Message::new_metrics(samples))
+        .await
+        .expect("Failed to push metrics");
+    drop(stream);
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let mut names = Vec::new();
+    for _ in 0..20 {
+        names = db::plugin_metrics_names(&db_pool, &plugin_id)
+            .await
+            .expect("Failed to list metric names");
+        if !names.is_empty() {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(names, vec!["conversions".to_string()]);
+
+    supervisor
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+    assert!(
+        !std::path::Path::new(&host_socket).exists(),
+        "Host socket should be removed when the plugin is killed"
+    );
+
+    println!("✅ T24: Metrics pushed over host socket are stored");
+}
+
+// ============ T25: Zero-Downtime Upgrades ============
+
+/// Write a plugin that reports `version` in its metadata and runs the hello plugin
+///
+/// Wrapping the hello binary gives each test its own plugin ID (and socket)
+/// while still speaking the real protocol.
+fn create_wrapped_hello_plugin(path: &Path, hello: &Path, plugin_id: &str, version: &str) {
+    let script = format!(
+        r#"#!/bin/bash
+if [ "$1" = "--metadata" ]; then
+    echo '{{"id": "{id}", "name": "Upgrade Test", "version": "{version}", "icon": "🔧", "route": "/{id}"}}'
+    exit 0
+fi
+exec "{hello}"
+"#,
+        id = plugin_id,
+        version = version,
+        hello = hello.display()
+    );
+    fs::write(path, script).expect("Failed to write wrapper plugin");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .expect("Failed to set permissions");
+    }
+}
+
+/// Test T25: Upgrading swaps the process without interrupting routing,
+/// and a new version that exits or never answers leaves the old one serving
+#[tokio::test]
+async fn test_t25_upgrade_switches_process_and_keeps_old_on_failure() {
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+    use toru_plugin_api::{HttpRequest, PluginMetadata};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let hello = copy_test_binary(&temp_dir);
+    let plugins_dir = temp_dir.path().join("plugins");
+    let plugin_id = format!(
+        "upgrade-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    let binary_path = plugins_dir.join(format!("{}.binary", plugin_id));
+    create_wrapped_hello_plugin(&binary_path, &hello, &plugin_id, "1.0.0");
+
+    let supervisor = Arc::new(Mutex::new(create_test_supervisor(&temp_dir).await));
+    let metadata = PluginMetadata {
+        id: plugin_id.clone(),
+        name: "Upgrade Test".to_string(),
+        version: "1.0.0".to_string(),
+        author: None,
+        icon: "🔧".to_string(),
+        route: format!("/{}", plugin_id),
+    };
+    supervisor
+        .lock()
+        .await
+        .spawn_plugin(&plugin_id, &binary_path, metadata)
+        .await
+        .expect("Failed to spawn plugin");
+
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/".to_string(),
+        headers: Default::default(),
+        body: None,
+    };
+
+    let (old_pid, old_socket) = {
+        let sup = supervisor.lock().await;
+        let process = sup.get_plugin_status(&plugin_id).unwrap();
+        (process.pid, process.socket_path.clone())
+    };
+    for _ in 0..50 {
+        if Path::new(&old_socket).exists() {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+
+    // Successful upgrade
+    let new_binary = temp_dir.path().join("new.binary");
+    create_wrapped_hello_plugin(&new_binary, &hello, &plugin_id, "2.0.0");
+    PluginSupervisor::upgrade_plugin(&supervisor, &plugin_id, &new_binary)
+        .await
+        .expect("Upgrade should succeed");
+
+    let upgraded_pid = {
+        let sup = supervisor.lock().await;
+        let process = sup.get_plugin_status(&plugin_id).unwrap();
+        assert_ne!(
+            process.pid, old_pid,
+            "New version should run in a new process"
+        );
+        assert_eq!(process.metadata.as_ref().unwrap().version, "2.0.0");
+        assert_eq!(process.binary_path, binary_path);
+        assert_ne!(process.socket_path, old_socket);
+
+        let response = sup
+            .forward_http_request(&plugin_id, &request)
+            .await
+            .expect("Upgraded plugin should serve requests");
+        assert_eq!(response.status, 200);
+        process.pid
+    };
+    assert!(
+        fs::read_to_string(&binary_path).unwrap().contains("2.0.0"),
+        "New binary should replace the installed one"
+    );
+    assert!(!new_binary.exists(), "Staged binary should be moved");
+    assert!(
+        !Path::new(&old_socket).exists(),
+        "Old process socket should be removed"
+    );
+
+    // Failed upgrade: the new version exits before becoming ready
+    let broken_binary = temp_dir.path().join("broken.binary");
+    let script = format!(
+        "#!/bin/bash\nif [ \"$1\" = \"--metadata\" ]; then\n    echo '{{\"id\": \"{id}\", \"name\": \"Broken\", \"version\": \"3.0.0\", \"icon\": \"x\", \"route\": \"/{id}\"}}'\n    exit 0\nfi\nexit 1\n",
+        id = plugin_id
+    );
+    fs::write(&broken_binary, script).expect("Failed to write broken plugin");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&broken_binary, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let result = PluginSupervisor::upgrade_plugin(&supervisor, &plugin_id, &broken_binary).await;
+    assert!(result.is_err(), "Upgrade to a crashing binary should fail");
+
+    let sup = supervisor.lock().await;
+    let process = sup.get_plugin_status(&plugin_id).unwrap();
+    assert_eq!(process.pid, upgraded_pid, "Old version should keep serving");
+    assert_eq!(process.metadata.as_ref().unwrap().version, "2.0.0");
+    let response = sup
+        .forward_http_request(&plugin_id, &request)
+        .await
+        .expect("Old version should still serve requests");
+    assert_eq!(response.status, 200);
+
+    drop(sup);
+
+    // Failed upgrade: the new version accepts init but never answers
+    let socket_file = temp_dir.path().join("silent-socket");
+    let silent_binary = temp_dir.path().join("silent.binary");
+    let script = format!(
+        "#!/bin/bash\nif [ \"$1\" = \"--metadata\" ]; then\n    echo '{{\"id\": \"{id}\", \"name\": \"Silent\", \"version\": \"4.0.0\", \"icon\": \"x\", \"route\": \"/{id}\"}}'\n    exit 0\nfi\necho \"$TORU_PLUGIN_SOCKET\" > \"{file}\"\nexec sleep 3600\n",
+        id = plugin_id,
+        file = socket_file.display()
+    );
+    fs::write(&silent_binary, script).expect("Failed to write silent plugin");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&silent_binary, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    // Stand in for the plugin: take every message, never reply
+    let silent = tokio::spawn(async move {
+        let socket_path = loop {
+            match fs::read_to_string(&socket_file) {
+                Ok(path) if path.ends_with('\n') => break path.trim().to_string(),
+                _ => tokio::time::sleep(tokio::time::Duration::from_millis(20)).await,
+            }
+        };
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+
+    let error = PluginSupervisor::upgrade_plugin(&supervisor, &plugin_id, &silent_binary)
+        .await
+        .expect_err("Upgrade to a version that never serves should fail");
+    assert!(
+        format!("{:#}", error).contains("readiness probe"),
+        "{:#}",
+        error
+    );
+    silent.abort();
+
+    let mut sup = supervisor.lock().await;
+    let process = sup.get_plugin_status(&plugin_id).unwrap();
+    assert_eq!(process.pid, upgraded_pid, "Old version should keep serving");
+    let response = sup
+        .forward_http_request(&plugin_id, &request)
+        .await
+        .expect("Old version should still serve requests");
+    assert_eq!(response.status, 200);
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let events = db::plugin_event_get_recent(&db_pool, &plugin_id, 10)
+        .await
+        .expect("Failed to get recent events");
+    assert_eq!(events[0].event_type, "upgrade_failed");
+    assert_eq!(events[1].event_type, "upgrade_failed");
+    assert!(events.iter().any(|e| e.event_type == "upgraded"));
+
+    sup.kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    println!("✅ T25: Upgrade switches process and keeps old one on failure");
+}
+
+// ============ T26: Version Rollback ============
+
+/// Test T26: Upgrades archive the previous binary and rollback reinstates it
+#[tokio::test]
+async fn test_t26_rollback_restores_previous_version() {
+    use toru_plugin_api::{HttpRequest, PluginMetadata};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let hello = copy_test_binary(&temp_dir);
+    let plugins_dir = temp_dir.path().join("plugins");
+    let plugin_id = format!(
+        "rollback-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    let binary_path = plugins_dir.join(format!("{}.binary", plugin_id));
+    create_wrapped_hello_plugin(&binary_path, &hello, &plugin_id, "1.0.0");
+
+    let supervisor = std::sync::Arc::new(tokio::sync::Mutex::new(
+        create_test_supervisor(&temp_dir).await,
+    ));
+    let metadata = PluginMetadata {
+        id: plugin_id.clone(),
+        name: "Upgrade Test".to_string(),
+        version: "1.0.0".to_string(),
+        author: None,
+        icon: "🔧".to_string(),
+        route: format!("/{}", plugin_id),
+    };
+    supervisor
+        .lock()
+        .await
+        .spawn_plugin(&plugin_id, &binary_path, metadata)
+        .await
+        .expect("Failed to spawn plugin");
+
+    let new_binary = temp_dir.path().join("new.binary");
+    create_wrapped_hello_plugin(&new_binary, &hello, &plugin_id, "2.0.0");
+    PluginSupervisor::upgrade_plugin(&supervisor, &plugin_id, &new_binary)
+        .await
+        .expect("Upgrade should succeed");
+
+    let mut sup = supervisor.lock().await;
+    let versions = sup
+        .list_plugin_versions(&plugin_id)
+        .expect("Failed to list versions");
+    assert_eq!(versions.len(), 1, "Upgrade should archive the old binary");
+    assert_eq!(versions[0].metadata.version, "1.0.0");
+
+    let upgraded_pid = sup.get_plugin_status(&plugin_id).unwrap().pid;
+    let restored = sup
+        .rollback_plugin(&plugin_id, None)
+        .await
+        .expect("Rollback should succeed");
+    assert_eq!(restored.metadata.version, "1.0.0");
+
+    let process = sup.get_plugin_status(&plugin_id).unwrap();
+    assert_eq!(process.metadata.as_ref().unwrap().version, "1.0.0");
+    assert_ne!(process.pid, upgraded_pid);
+    assert!(fs::read_to_string(&binary_path).unwrap().contains("1.0.0"));
+
+    // The replaced version is archived so the rollback can be undone
+    let versions = sup
+        .list_plugin_versions(&plugin_id)
+        .expect("Failed to list versions");
+    let archived: Vec<&str> = versions
+        .iter()
+        .map(|v| v.metadata.version.as_str())
+        .collect();
+    assert_eq!(archived, vec!["2.0.0"]);
+
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/".to_string(),
+        headers: Default::default(),
+        body: None,
+    };
+    let response = sup
+        .forward_http_request(&plugin_id, &request)
+        .await
+        .expect("Restored version should serve requests");
+    assert_eq!(response.status, 200);
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let events = db::plugin_event_get_recent(&db_pool, &plugin_id, 10)
+        .await
+        .expect("Failed to get recent events");
+    assert!(events.iter().any(|e| e.event_type == "rolled_back"));
+
+    sup.kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    println!("✅ T26: Rollback restores previous version");
+}
+
+// ============ T27: Hot Discovery ============
+
+/// Test T27: Rescan spawns added plugins, restarts replaced ones and stops removed ones
+#[tokio::test]
+async fn test_t27_rescan_applies_directory_changes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    supervisor.initialize().await.expect("Failed to initialize");
+
+    let plugin_id = format!(
+        "rescan-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    // Added
+    let binary_path = create_test_plugin(&plugins_dir, &plugin_id);
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert_eq!(report.added, vec![plugin_id.clone()]);
+    let first_pid = supervisor
+        .get_plugin_status(&plugin_id)
+        .expect("Added plugin should be managed")
+        .pid;
+    assert!(first_pid.is_some(), "Added plugin should be spawned");
+
+    // Unchanged
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert!(report.added.is_empty() && report.replaced.is_empty() && report.removed.is_empty());
+
+    // Replaced
+    let mut content = fs::read_to_string(&binary_path).unwrap();
+    content.push_str("# v2\n");
+    fs::write(&binary_path, content).unwrap();
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert_eq!(report.replaced, vec![plugin_id.clone()]);
+    let second_pid = supervisor.get_plugin_status(&plugin_id).unwrap().pid;
+    assert_ne!(second_pid, first_pid, "Replaced plugin should be restarted");
+
+    // Present but without valid metadata (e.g. half copied): kept running
+    fs::write(&binary_path, "#!/bin/bash\nexit 1\n").unwrap();
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert!(report.added.is_empty() && report.replaced.is_empty() && report.removed.is_empty());
+    assert_eq!(
+        supervisor.get_plugin_status(&plugin_id).unwrap().pid,
+        second_pid,
+        "Plugin with broken metadata should keep running"
+    );
+
+    // Removed
+    fs::remove_file(&binary_path).unwrap();
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert_eq!(report.removed, vec![plugin_id.clone()]);
+    assert!(supervisor.get_plugin_status(&plugin_id).is_none());
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let events = db::plugin_event_get_recent(&db_pool, &plugin_id, 20)
+        .await
+        .expect("Failed to get recent events");
+    for event_type in [
+        "discovered",
+        "binary_replaced",
+        "metadata_failed",
+        "removed",
+    ] {
+        assert!(
+            events.iter().any(|e| e.event_type == event_type),
+            "Missing {} event",
+            event_type
+        );
+    }
+
+    println!("✅ T27: Rescan applies directory changes");
+}
+
+/// Test T28: The directory watcher picks up a new plugin binary without a manual rescan
+#[tokio::test]
+async fn test_t28_watcher_discovers_new_binary() {
+    use std::sync::Arc;
+    use steering_center::services::plugin_watcher::spawn_plugin_watcher;
+    use tokio::sync::Mutex;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    let supervisor = Arc::new(Mutex::new(create_test_supervisor(&temp_dir).await));
+    let _watcher = spawn_plugin_watcher(Arc::clone(&supervisor), plugins_dir.clone())
+        .expect("Failed to start watcher");
+
+    let plugin_id = format!(
+        "watch-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    create_test_plugin(&plugins_dir, &plugin_id);
+
+    let mut found = false;
+    for _ in 0..50 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        if supervisor
+            .lock()
+            .await
+            .get_plugin_status(&plugin_id)
+            .is_some()
+        {
+            found = true;
+            break;
+        }
+    }
+    assert!(found, "Watcher should discover the new plugin");
+
+    supervisor
+        .lock()
+        .await
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    println!("✅ T28: Watcher discovers new plugin binary");
+}
+
+// ============ T29: Plugin Packages ============
+
+/// Test T29: Package directories are discovered from their manifest without
+/// running the entrypoint, next to single-file binaries
+#[tokio::test]
+async fn test_t29_package_directory_discovered_from_manifest() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    let plugin_id = format!(
+        "package-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let binary_id = format!(
+        "binary-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    // The entrypoint fails on --metadata, so discovery must come from the manifest
+    let package_dir = plugins_dir.join(&plugin_id);
+    fs::create_dir_all(package_dir.join("bin")).unwrap();
+    fs::create_dir_all(package_dir.join("assets")).unwrap();
+    fs::write(
+        package_dir.join("plugin.toml"),
+        format!(
+            "id = \"{id}\"\nname = \"Package Test\"\nversion = \"0.3.0\"\nicon = \"📦\"\nroute = \"/{id}\"\nentrypoint = \"bin/run\"\n",
+            id = plugin_id
+        ),
+    )
+    .unwrap();
+    fs::write(
+        package_dir.join("bin/run"),
+        "#!/bin/bash\n[ \"$1\" = \"--metadata\" ] && exit 1\nsleep 3600\n",
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(
+            package_dir.join("bin/run"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+    fs::write(package_dir.join("assets/bundle.js"), "// bundle").unwrap();
+
+    // Legacy layout: single binary plus an asset directory without a manifest
+    create_test_plugin(&plugins_dir, &binary_id);
+    fs::create_dir_all(plugins_dir.join(&binary_id)).unwrap();
+    fs::write(plugins_dir.join(&binary_id).join("bundle.js"), "// legacy").unwrap();
+
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let discovered = supervisor
+        .scan_plugins_directory()
+        .await
+        .expect("Scan failed");
+
+    let (entrypoint, metadata) = discovered
+        .get(&plugin_id)
+        .expect("Package should be discovered");
+    assert_eq!(entrypoint, &package_dir.join("bin/run"));
+    assert_eq!(metadata.version, "0.3.0");
+    assert!(discovered.contains_key(&binary_id));
+    assert_eq!(discovered.len(), 2, "Manifest-less directories are ignored");
+
+    assert_eq!(
+        supervisor.plugin_assets_dir(&plugin_id),
+        Some(package_dir.join("assets"))
+    );
+    assert_eq!(supervisor.plugin_assets_dir(&binary_id), None);
+
+    // Packages are rolled back by replacing the package
+    let error = supervisor
+        .rollback_plugin(&plugin_id, None)
+        .await
+        .expect_err("Rollback of a package should be refused");
+    assert!(error.to_string().contains("installed as a package"));
+
+    let (entrypoint, metadata) = discovered.get(&plugin_id).unwrap().clone();
+    supervisor
+        .spawn_plugin(&plugin_id, &entrypoint, metadata)
+        .await
+        .expect("Package entrypoint should spawn");
+    supervisor
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    // Binaries declaring the package's ID lose to it, whether the directory
+    // lists them before or after the package
+    let binary = create_test_plugin(&plugins_dir, &plugin_id);
+    for name in ["0-shadow.binary", "zz-shadow.binary"] {
+        fs::copy(&binary, plugins_dir.join(name)).unwrap();
+    }
+    for _ in 0..2 {
+        let discovered = supervisor
+            .scan_plugins_directory()
+            .await
+            .expect("Scan failed");
+        assert_eq!(discovered[&plugin_id].0, package_dir.join("bin/run"));
+        assert_eq!(
+            supervisor.plugin_assets_dir(&plugin_id),
+            Some(package_dir.join("assets"))
+        );
+    }
+
+    println!("✅ T29: Package directory discovered from manifest");
+}
+
+/// Test T30: A plugin built on the SDK runtime reaches KV through the host
+/// socket, serves concurrent requests and exits cleanly on `shutdown`
+#[tokio::test]
+async fn test_t30_sdk_runtime_kv_and_shutdown() {
+    use steering_center::services::plugin_http::HttpLimits;
+    use toru_plugin_api::{HttpRequest, Message, PluginProtocol};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    fs::create_dir_all(&plugins_dir).expect("Failed to create plugins dir");
+
+    // Keep the hello binary itself out of the scanned directory
+    let hello = temp_dir.path().join("hello.binary");
+    fs::copy("plugins/hello-plugin-rust.binary", &hello).expect("Failed to copy test binary");
+    let plugin_id = format!(
+        "sdk-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    create_wrapped_hello_plugin(
+        &plugins_dir.join(format!("{}.binary", plugin_id)),
+        &hello,
+        &plugin_id,
+        "1.0.0",
+    );
+
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert_eq!(report.added, vec![plugin_id.clone()]);
+
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/".to_string(),
+        headers: Default::default(),
+        body: None,
+    };
+    let target = supervisor
+        .http_target(&plugin_id)
+        .expect("Plugin should be routable");
+    let limits = HttpLimits::default();
+    let responses =
+        futures::future::join_all((0..5).map(|_| target.forward(&request, &limits))).await;
+    for response in responses {
+        let response = response.expect("Request should be answered");
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(response.body.as_deref().unwrap())
+            .expect("Response should be JSON");
+        assert_eq!(body["instance_id"], "test-instance-id");
+    }
+
+    // Visits are counted through the host socket into the core's KV table
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let visits = db::plugin_kv_get(&db_pool, &plugin_id, "visits")
+        .await
+        .expect("Failed to read KV");
+    assert_eq!(visits.as_deref(), Some("5"));
+
+    // The bundle embedded by #[toru_plugin] is served without a handler
+    let bundle_request = HttpRequest {
+        path: "/bundle.js".to_string(),
+        ..request.clone()
+    };
+    let bundle = target
+        .forward(&bundle_request, &limits)
+        .await
+        .expect("Bundle request should be answered");
+    assert_eq!(bundle.status, 200);
+    assert_eq!(
+        bundle.headers.get("Content-Type").map(String::as_str),
+        Some("application/javascript")
+    );
+
+    // Shutdown stops the plugin and removes its socket
+    let socket_path = supervisor
+        .get_plugin_status(&plugin_id)
+        .unwrap()
+        .socket_path
+        .clone();
+    let mut stream = tokio::net::UnixStream::connect(&socket_path)
+        .await
+        .expect("Failed to connect to plugin socket");
+    PluginProtocol::new()
+        .write_message(&mut stream, &Message::new_lifecycle("shutdown", None))
+        .await
+        .expect("Failed to send shutdown");
+    for _ in 0..50 {
+        if !Path::new(&socket_path).exists() {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+    assert!(
+        !Path::new(&socket_path).exists(),
+        "Plugin should remove its socket on shutdown"
+    );
+
+    supervisor
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    println!("✅ T30: SDK runtime handles KV, concurrency and shutdown");
+}
+
+/// Test T31: The SDK's test harness drives a compiled plugin without the core
+#[tokio::test]
+async fn test_t31_binary_harness_drives_plugin() {
+    use toru_plugin_api::testing::BinaryHarness;
+    use toru_plugin_api::PluginKvStore;
+
+    let harness = BinaryHarness::spawn("plugins/hello-plugin-rust.binary")
+        .await
+        .expect("Failed to start plugin");
+    harness.kv().set("visits", "9").await.unwrap();
+
+    let response = harness.get("/").await.expect("Request failed");
+    assert_eq!(response.status, 200);
+    let body: serde_json::Value =
+        serde_json::from_str(response.body.as_deref().unwrap()).expect("Response should be JSON");
+    assert_eq!(body["instance_id"], "test-instance");
+    assert_eq!(body["visits"], 10);
+    assert_eq!(
+        harness.kv().get("visits").await.unwrap().as_deref(),
+        Some("10")
+    );
+
+    let response = harness.get("/missing").await.expect("Request failed");
+    assert_eq!(response.status, 404);
+
+    harness
+        .shutdown()
+        .await
+        .expect("Plugin should exit cleanly");
+
+    println!("✅ T31: Test harness drives a plugin binary");
+}
+
+// ============ T32: Plugin KV ============
+
+/// Test T32: Listing, TTLs, compare-and-swap, counters and batches work over
+/// the host socket, and a failed operation is answered instead of dropping
+/// the connection
+#[tokio::test]
+async fn test_t32_rich_kv_over_host_socket() {
+    use std::time::Duration;
+    use toru_plugin_api::{HostKvStore, KvSetEntry, PluginError, PluginKvStore};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("host.sock");
+    let plugin_id = format!("kv-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let listener = steering_center::services::host_socket::spawn_host_listener(
+        &plugin_id,
+        &socket_path,
+        db_pool,
+        None,
+    )
+    .expect("Failed to bind host socket");
+
+    let kv = HostKvStore::new(&socket_path);
+    kv.set_many(&[
+        KvSetEntry {
+            key: "user:1".to_string(),
+            value: "ada".to_string(),
+            ttl_secs: None,
+        },
+        KvSetEntry {
+            key: "user:2".to_string(),
+            value: "grace".to_string(),
+            ttl_secs: None,
+        },
+        KvSetEntry {
+            key: "user:3".to_string(),
+            value: "linus".to_string(),
+            ttl_secs: None,
+        },
+    ])
+    .await
+    .expect("Batch set failed");
+
+    let page = kv.list("user:", None, 2).await.expect("List failed");
+    assert_eq!(page.entries.len(), 2);
+    let rest = kv
+        .list("user:", page.next_cursor.as_deref(), 2)
+        .await
+        .expect("List failed");
+    assert_eq!(rest.entries[0].key, "user:3");
+    assert_eq!(rest.next_cursor, None);
+
+    kv.set_with_ttl("session", "x", Duration::ZERO)
+        .await
+        .expect("Set with TTL failed");
+    assert_eq!(kv.get("session").await.unwrap(), None);
+
+    assert!(kv.compare_and_swap("lock", None, Some("a")).await.unwrap());
+    assert!(!kv.compare_and_swap("lock", None, Some("b")).await.unwrap());
+    assert_eq!(kv.increment("hits", 3).await.unwrap(), 3);
+    assert_eq!(
+        kv.get_many(&["hits".to_string(), "lock".to_string()])
+            .await
+            .unwrap(),
+        vec![Some("3".to_string()), Some("a".to_string())]
+    );
+
+    // Incrementing text fails, and the connection keeps working afterwards
+    assert!(matches!(
+        kv.increment("lock", 1).await,
+        Err(PluginError::InvalidRequest(_))
+    ));
+    assert_eq!(kv.get("user:1").await.unwrap().as_deref(), Some("ada"));
+
+    listener.abort();
+
+    println!("✅ T32: Rich KV operations work over the host socket");
+}
+
+/// Test T33: Admins can search a plugin's KV namespace and move it to
+/// another plugin ID by export and import
+#[tokio::test]
+async fn test_t33_kv_browse_export_import() {
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let source = format!("kv-src-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let target = format!("kv-dst-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+
+    for (key, value) in [
+        ("city:paris", "fr"),
+        ("city:rome", "it"),
+        ("token", "paris"),
+    ] {
+        db::plugin_kv_set(&db_pool, &source, key, value)
+            .await
+            .unwrap();
+    }
+
+    let found = db::plugin_kv_browse(&db_pool, &source, Some("paris"), None, 10)
+        .await
+        .unwrap();
+    let keys: Vec<_> = found.iter().map(|e| e.key.as_str()).collect();
+    assert_eq!(keys, ["city:paris", "token"]);
+    let page = db::plugin_kv_browse(&db_pool, &source, None, Some("city:paris"), 1)
+        .await
+        .unwrap();
+    assert_eq!(page[0].key, "city:rome");
+
+    let mut export = db::plugin_kv_get_all(&db_pool, &source).await.unwrap();
+    assert_eq!(export.len(), 3);
+    export.push(db::PluginKvEntry {
+        key: "expired".to_string(),
+        value: "x".to_string(),
+        expires_at: Some(0),
+    });
+
+    db::plugin_kv_set(&db_pool, &target, "local", "kept")
+        .await
+        .unwrap();
+    let imported = db::plugin_kv_import(&db_pool, &target, &export, false)
+        .await
+        .unwrap();
+    assert_eq!(imported, 3, "Expired entries should be skipped");
+    assert_eq!(
+        db::plugin_kv_get_all(&db_pool, &target)
+            .await
+            .unwrap()
+            .len(),
+        4
+    );
+
+    db::plugin_kv_import(&db_pool, &target, &export, true)
+        .await
+        .unwrap();
+    assert_eq!(
+        db::plugin_kv_get_all(&db_pool, &target).await.unwrap(),
+        db::plugin_kv_get_all(&db_pool, &source).await.unwrap()
+    );
+
+    println!("✅ T33: KV namespaces can be searched, exported and imported");
+}
+
+// ============ T34: Plugin Data Directories ============
+
+/// Test T34: Plugins get a private data directory whose quota stops them
+#[tokio::test]
+async fn test_t34_data_directory_quota() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let plugin_id = format!("data-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let binary_path = create_test_plugin(&supervisor.get_plugins_dir(), &plugin_id);
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    db::set_setting(
+        &db_pool,
+        &format!("plugin_quota_data_max_bytes.{}", plugin_id),
+        "16",
+    )
+    .await
+    .unwrap();
+
+    let discovered = supervisor.scan_plugins_directory().await.unwrap();
+    let (path, metadata) = discovered.get(&plugin_id).expect("Plugin discovered");
+    supervisor
+        .spawn_plugin(&plugin_id, path, metadata.clone())
+        .await
+        .expect("Failed to spawn plugin");
+
+    let data_dir = supervisor.plugin_data_dir(&plugin_id);
+    assert!(data_dir.is_dir(), "Data directory created on start");
+    assert_eq!(
+        supervisor.get_plugin_status(&plugin_id).unwrap().data_dir,
+        Some(data_dir.clone())
+    );
+
+    // Within quota: nothing happens
+    let supervisor = std::sync::Arc::new(tokio::sync::Mutex::new(supervisor));
+    fs::write(data_dir.join("small"), [0u8; 8]).unwrap();
+    assert!(PluginSupervisor::enforce_data_quotas(&supervisor)
+        .await
+        .is_empty());
+    assert!(
+        supervisor
+            .lock()
+            .await
+            .get_plugin_status(&plugin_id)
+            .unwrap()
+            .enabled
+    );
+
+    // Over quota: the running plugin is stopped and may not start again
+    fs::write(data_dir.join("large"), [0u8; 64]).unwrap();
+    assert_eq!(
+        PluginSupervisor::enforce_data_quotas(&supervisor).await,
+        vec![plugin_id.clone()]
+    );
+    let mut supervisor = supervisor.lock().await;
+    assert!(!supervisor.get_plugin_status(&plugin_id).unwrap().enabled);
+
+    let events = db::plugin_event_get_recent(&db_pool, &plugin_id, 10)
+        .await
+        .unwrap();
+    assert!(events.iter().any(|e| e.event_type == "data_quota_exceeded"));
+
+    let result = supervisor
+        .spawn_plugin(&plugin_id, &binary_path, metadata.clone())
+        .await;
+    assert!(result.is_err(), "Start refused while over quota");
+
+    // Freeing space lets the plugin start again
+    fs::remove_file(data_dir.join("large")).unwrap();
+    supervisor
+        .spawn_plugin(&plugin_id, &binary_path, metadata.clone())
+        .await
+        .expect("Plugin starts once back under quota");
+    supervisor.kill_plugin(&plugin_id).await.ok();
+
+    println!("✅ T34: Data directory created and its quota enforced");
+}
+
+// ============ T35: Plugin Databases ============
+
+/// Test T35: Package migrations run when the plugin starts, and the plugin
+/// reaches only its own database over the host socket
+#[tokio::test]
+async fn test_t35_plugin_database_migrations_and_sql() {
+    use steering_center::services::plugin_database::SqlitePluginDatabase;
+    use toru_plugin_api::{HostDatabase, PluginDatabase, PluginError, SqlMigration, SqlValue};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    let plugin_id = format!("sql-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+
+    let package_dir = plugins_dir.join(&plugin_id);
+    fs::create_dir_all(package_dir.join("bin")).unwrap();
+    fs::create_dir_all(package_dir.join("migrations")).unwrap();
+    fs::write(
+        package_dir.join("plugin.toml"),
+        format!(
+            "id = \"{id}\"\nname = \"SQL Test\"\nversion = \"1.0.0\"\nicon = \"🗃\"\nroute = \"/{id}\"\nentrypoint = \"bin/run\"\n",
+            id = plugin_id
+        ),
+    )
+    .unwrap();
+    fs::write(package_dir.join("bin/run"), "#!/bin/bash\nsleep 3600\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(
+            package_dir.join("bin/run"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+    fs::write(
+        package_dir.join("migrations/001_init.sql"),
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL);",
+    )
+    .unwrap();
+    fs::write(
+        package_dir.join("migrations/002_customer.sql"),
+        "ALTER TABLE orders ADD COLUMN customer TEXT;",
+    )
+    .unwrap();
+
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let discovered = supervisor.scan_plugins_directory().await.unwrap();
+    let (entrypoint, metadata) = discovered.get(&plugin_id).unwrap().clone();
+    supervisor
+        .spawn_plugin(&plugin_id, &entrypoint, metadata.clone())
+        .await
+        .expect("Failed to spawn plugin");
+    supervisor.kill_plugin(&plugin_id).await.unwrap();
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let events = db::plugin_event_get_recent(&db_pool, &plugin_id, 10)
+        .await
+        .unwrap();
+    assert!(events.iter().any(|e| e.event_type == "migrated"));
+
+    // A failing migration keeps the plugin from starting
+    fs::write(
+        package_dir.join("migrations/003_broken.sql"),
+        "ALTER TABLE missing ADD COLUMN x;",
+    )
+    .unwrap();
+    supervisor.scan_plugins_directory().await.unwrap();
+    assert!(supervisor
+        .spawn_plugin(&plugin_id, &entrypoint, metadata)
+        .await
+        .is_err());
+    fs::remove_file(package_dir.join("migrations/003_broken.sql")).unwrap();
+
+    // The plugin talks to its database through the host socket
+    let database = SqlitePluginDatabase::open(&plugins_dir.join(".databases"), &plugin_id).unwrap();
+    let socket_path = temp_dir.path().join("host.sock");
+    let listener = steering_center::services::host_socket::spawn_host_listener(
+        &plugin_id,
+        &socket_path,
+        db_pool.clone(),
+        Some(database),
+    )
+    .expect("Failed to bind host socket");
+
+    let sql = HostDatabase::new(&socket_path);
+    let applied = sql
+        .migrate(&[SqlMigration {
+            version: "002_customer".to_string(),
+            sql: "ALTER TABLE orders ADD COLUMN customer TEXT;".to_string(),
+        }])
+        .await
+        .unwrap();
+    assert!(applied.is_empty(), "Package migrations were recorded");
+
+    let inserted = sql
+        .execute(
+            "INSERT INTO orders (total, customer) VALUES (?, ?)",
+            &[SqlValue::from(12.5), SqlValue::from("ada")],
+        )
+        .await
+        .unwrap();
+    assert_eq!(inserted.rows_affected, 1);
+    let rows = sql
+        .query("SELECT customer, total FROM orders", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        rows.rows,
+        [vec![SqlValue::from("ada"), SqlValue::Real(12.5)]]
+    );
+
+    assert!(matches!(
+        sql.query("SELECT * FROM missing", &[]).await,
+        Err(PluginError::InvalidRequest(_))
+    ));
+    assert!(sql
+        .execute("ATTACH DATABASE 'steering.db' AS core", &[])
+        .await
+        .is_err());
+
+    // An operation the core doesn't know gets an error reply, not silence
+    {
+        use tokio::io::AsyncWriteExt;
+        use toru_plugin_api::{MessagePayload, PluginProtocol, SqlMessagePayload};
+
+        let mut stream = tokio::net::UnixStream::connect(&socket_path).await.unwrap();
+        let raw = serde_json::to_vec(&serde_json::json!({
+            "type": "sql",
+            "timestamp": chrono::Utc::now(),
+            "request_id": "bogus-1",
+            "payload": {"type": "sql", "request_id": "bogus-1", "action": "drop_everything"}
+        }))
+        .unwrap();
+        stream
+            .write_all(&(raw.len() as u32).to_be_bytes())
+            .await
+            .unwrap();
+        stream.write_all(&raw).await.unwrap();
+        let reply = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            PluginProtocol::new().read_message(&mut stream),
+        )
+        .await
+        .expect("No reply to an unknown SQL operation")
+        .unwrap();
+        match reply.payload {
+            MessagePayload::Sql {
+                request_id,
+                payload: SqlMessagePayload::Response(response),
+            } => {
+                assert_eq!(request_id, "bogus-1");
+                assert!(matches!(
+                    response.error(),
+                    Some(PluginError::InvalidRequest(_))
+                ));
+            }
+            other => panic!("Unexpected reply: {:?}", other),
+        }
+    }
+    listener.abort();
+
+    // Without a database the request fails instead of hanging
+    let socket_path = temp_dir.path().join("host-none.sock");
+    let listener = steering_center::services::host_socket::spawn_host_listener(
+        &plugin_id,
+        &socket_path,
+        db_pool,
+        None,
+    )
+    .expect("Failed to bind host socket");
+    assert!(HostDatabase::new(&socket_path)
+        .query("SELECT 1", &[])
+        .await
+        .is_err());
+    listener.abort();
+
+    println!("✅ T35: Plugin database migrated on start and reached over the host socket");
+}
+
+// ============ T36: Event Timeline ============
+
+/// Test T36: Events are filtered by plugin, type and time, paged with a
+/// cursor, and summarized into uptime and crash counts
+#[tokio::test]
+async fn test_t36_event_timeline_filters_and_summary() {
+    use chrono::{Duration, Utc};
+    use rusqlite::Connection;
+    use std::sync::Arc;
+    use steering_center::services::plugin_events;
+    use tokio::sync::Mutex;
+
+    let conn = Connection::open_in_memory().expect("Failed to open db");
+    conn.execute(
+        "CREATE TABLE plugin_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            plugin_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            details TEXT
+        )",
+        [],
+    )
+    .expect("Failed to create table");
+
+    // Started 2 days ago, crashed 10 hours ago and back 2 hours later
+    let now = Utc::now();
+    let history = [
+        ("timeline-a", "started", now - Duration::days(2)),
+        ("timeline-b", "started", now - Duration::hours(30)),
+        (
+            "timeline-a",
+            "restarting_with_backoff",
+            now - Duration::hours(10),
+        ),
+        ("timeline-a", "started", now - Duration::hours(8)),
+        ("timeline-a", "migrated", now - Duration::hours(1)),
+    ];
+    for (plugin_id, event_type, at) in history {
+        conn.execute(
+            "INSERT INTO plugin_events (plugin_id, event_type, timestamp) VALUES (?1, ?2, ?3)",
+            rusqlite::params![plugin_id, event_type, at.to_rfc3339()],
+        )
+        .unwrap();
+    }
+    let db_pool = Arc::new(Mutex::new(conn));
+
+    let query = |filter: db::PluginEventFilter| {
+        let db_pool = db_pool.clone();
+        async move {
+            db::plugin_event_query(&db_pool, &filter)
+                .await
+                .expect("Failed to query events")
+                .into_iter()
+                .map(|e| (e.plugin_id, e.event_type))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Type and time filters, newest first
+    let started = query(db::PluginEventFilter {
+        event_types: vec!["started".to_string()],
+        since: Some((now - Duration::days(1) - Duration::hours(12)).to_rfc3339()),
+        limit: 10,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(
+        started,
+        [
+            ("timeline-a".to_string(), "started".to_string()),
+            ("timeline-b".to_string(), "started".to_string()),
+        ]
+    );
+    let until = query(db::PluginEventFilter {
+        plugin_id: Some("timeline-a".to_string()),
+        until: Some((now - Duration::hours(10)).to_rfc3339()),
+        limit: 10,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(until.len(), 1, "until is exclusive");
+
+    // Cursor pagination walks all events of a plugin exactly once
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = db::plugin_event_query(
+            &db_pool,
+            &db::PluginEventFilter {
+                plugin_id: Some("timeline-a".to_string()),
+                before_id: cursor,
+                limit: 2,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        seen.extend(page.iter().map(|e| e.event_type.clone()));
+        if page.len() < 2 {
+            break;
+        }
+        cursor = page.last().map(|e| e.id);
+    }
+    assert_eq!(
+        seen,
+        ["migrated", "started", "restarting_with_backoff", "started"]
+    );
+
+    // Down for 2 of the last 24 hours, up the whole week before
+    let summary = plugin_events::summarize(&db_pool, "timeline-a", now)
+        .await
+        .expect("Failed to summarize");
+    assert_eq!(summary.crashes_24h, 1);
+    assert_eq!(summary.crashes_7d, 1);
+    let uptime_24h = summary.uptime_24h.unwrap();
+    assert!(
+        (uptime_24h - 91.67).abs() < 0.02,
+        "uptime_24h {}",
+        uptime_24h
+    );
+    let uptime_7d = summary.uptime_7d.unwrap();
+    assert!((uptime_7d - 95.83).abs() < 0.02, "uptime_7d {}", uptime_7d);
+
+    let unknown = plugin_events::summarize(&db_pool, "timeline-none", now)
+        .await
+        .unwrap();
+    assert_eq!(unknown.uptime_24h, None);
+    assert_eq!(unknown.crashes_7d, 0);
+
+    println!("✅ T36: Event timeline filtered, paged and summarized");
+}
+
+/// Test T37: Failed requests open a plugin's circuit, which rejects requests
+/// until a probe reaches the restarted plugin; transitions become events
+#[tokio::test]
+async fn test_t37_circuit_breaker_opens_and_recovers() {
+    use std::time::Duration;
+    use steering_center::services::plugin_http::{BreakerPolicy, ForwardError, HttpLimits};
+    use toru_plugin_api::HttpRequest;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let plugins_dir = temp_dir.path().join("plugins");
+    fs::create_dir_all(&plugins_dir).expect("Failed to create plugins dir");
+
+    let hello = temp_dir.path().join("hello.binary");
+    fs::copy("plugins/hello-plugin-rust.binary", &hello).expect("Failed to copy test binary");
+    let plugin_id = format!(
+        "circuit-test-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    create_wrapped_hello_plugin(
+        &plugins_dir.join(format!("{}.binary", plugin_id)),
+        &hello,
+        &plugin_id,
+        "1.0.0",
+    );
+
+    let mut supervisor = create_test_supervisor(&temp_dir).await;
+    let report = supervisor.rescan().await.expect("Rescan failed");
+    assert_eq!(report.added, vec![plugin_id.clone()]);
+
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        path: "/".to_string(),
+        headers: Default::default(),
+        body: None,
+    };
+    let limits = HttpLimits {
+        breaker: BreakerPolicy {
+            failure_threshold: 2,
+            open_for: Duration::from_millis(300),
+        },
+        ..Default::default()
+    };
+    let target = supervisor
+        .http_target(&plugin_id)
+        .expect("Plugin should be routable");
+    let response = target.forward(&request, &limits).await.unwrap();
+    assert_eq!(response.status, 200);
+
+    // The pinned process goes away: two failures open the circuit
+    let process = supervisor.get_plugin_status(&plugin_id).unwrap();
+    let binary_path = process.binary_path.clone();
+    let metadata = process.metadata.clone().unwrap();
+    supervisor
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+    for _ in 0..2 {
+        let error = target.forward(&request, &limits).await.unwrap_err();
+        assert!(error.downcast_ref::<ForwardError>().is_none());
+    }
+    let error = target.forward(&request, &limits).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ForwardError>(),
+        Some(ForwardError::CircuitOpen { .. })
+    ));
+
+    // Once the open period is over, a probe to the restarted plugin closes it
+    supervisor
+        .spawn_plugin(&plugin_id, &binary_path, metadata)
+        .await
+        .expect("Failed to restart plugin");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let target = supervisor
+        .http_target(&plugin_id)
+        .expect("Plugin should be routable");
+    let response = target
+        .forward(&request, &limits)
+        .await
+        .expect("Probe should reach the plugin");
+    assert_eq!(response.status, 200);
+
+    // Oversized responses are refused and count as failures
+    let small = HttpLimits {
+        max_response_bytes: 1,
+        ..limits
+    };
+    for _ in 0..2 {
+        let error = target.forward(&request, &small).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ForwardError>(),
+            Some(ForwardError::ResponseTooLarge { .. })
+        ));
+    }
+    let error = target.forward(&request, &limits).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<ForwardError>(),
+        Some(ForwardError::CircuitOpen { .. })
+    ));
+
+    let db_pool = db::init_db().expect("Failed to init test db");
+    let events: Vec<String> = db::plugin_event_query(
+        &db_pool,
+        &db::PluginEventFilter {
+            plugin_id: Some(plugin_id.clone()),
+            limit: 100,
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("Failed to query events")
+    .into_iter()
+    .map(|e| e.event_type)
+    .filter(|event_type| event_type.starts_with("circuit_"))
+    .collect();
+    assert_eq!(
+        events,
+        [
+            "circuit_opened",
+            "circuit_closed",
+            "circuit_half_open",
+            "circuit_opened"
+        ]
+    );
+
+    supervisor
+        .kill_plugin(&plugin_id)
+        .await
+        .expect("Failed to kill plugin");
+
+    println!("✅ T37: Circuit breaker opens on failures and closes after a probe");
+}
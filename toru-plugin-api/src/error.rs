@@ -27,4 +27,8 @@ pub enum PluginError {
 
     #[error("Timeout")]
     Timeout,
+
+    /// A write would exceed the plugin's storage quota
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
 }
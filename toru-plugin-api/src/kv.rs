@@ -0,0 +1,512 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{PluginError, PluginResult};
+use crate::protocol::HostConnection;
+use crate::types::{
+    KvEntry, KvMessagePayload, KvMessageResponse, KvOp, KvPage, KvResult, KvSetEntry, Message,
+    MessagePayload, PluginKvStore,
+};
+
+/// How long a KV operation waits for the core to answer
+const KV_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Page size of a `List` operation without a limit
+pub const DEFAULT_LIST_LIMIT: usize = 100;
+/// Largest page a `List` operation returns
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// Run a KV operation against a store
+///
+/// Shared by everything that answers `kv` messages, so all stores get the
+/// same limits and result shapes.
+///
+/// # Arguments
+/// * `store` - Store the operation is applied to
+/// * `op` - Operation from a `kv` message
+pub async fn execute(store: &dyn PluginKvStore, op: KvOp) -> PluginResult<KvMessageResponse> {
+    let (value, result) = match op {
+        KvOp::Get { key } => (store.get(&key).await?, None),
+        KvOp::Set {
+            key,
+            value,
+            ttl_secs: None,
+        } => {
+            store.set(&key, &value).await?;
+            (None, None)
+        }
+        KvOp::Set {
+            key,
+            value,
+            ttl_secs: Some(ttl),
+        } => {
+            store
+                .set_with_ttl(&key, &value, Duration::from_secs(ttl))
+                .await?;
+            (None, None)
+        }
+        KvOp::Delete { key } => {
+            store.delete(&key).await?;
+            (None, None)
+        }
+        KvOp::List {
+            prefix,
+            cursor,
+            limit,
+        } => {
+            let limit = limit
+                .map_or(DEFAULT_LIST_LIMIT, |l| l as usize)
+                .clamp(1, MAX_LIST_LIMIT);
+            let page = store.list(&prefix, cursor.as_deref(), limit).await?;
+            (None, Some(KvResult::Page(page)))
+        }
+        KvOp::CompareAndSwap { key, expected, new } => {
+            let swapped = store
+                .compare_and_swap(&key, expected.as_deref(), new.as_deref())
+                .await?;
+            (None, Some(KvResult::Swapped { swapped }))
+        }
+        KvOp::Increment { key, delta } => {
+            let count = store.increment(&key, delta).await?;
+            (Some(count.to_string()), Some(KvResult::Counter { count }))
+        }
+        KvOp::GetMany { keys } => {
+            let values = store.get_many(&keys).await?;
+            (None, Some(KvResult::Values { values }))
+        }
+        KvOp::SetMany { entries } => {
+            store.set_many(&entries).await?;
+            (None, None)
+        }
+    };
+
+    Ok(KvMessageResponse {
+        value,
+        result,
+        ..Default::default()
+    })
+}
+
+/// KV store backed by the core, reached over the host socket
+///
+/// Operations are sent as `kv` messages on a single connection that is
+/// reopened after errors. The core scopes keys to the plugin owning the
+/// socket, so plugins cannot read each other's data.
+pub struct HostKvStore {
+    connection: HostConnection,
+}
+
+impl HostKvStore {
+    /// Create a store for the host socket passed in the init payload
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            connection: HostConnection::new(socket_path.into()),
+        }
+    }
+
+    async fn request(&self, op: KvOp) -> PluginResult<KvMessageResponse> {
+        let request = Message::new_kv(uuid::Uuid::new_v4().to_string(), op);
+        let reply = self.connection.request(&request, KV_TIMEOUT).await?;
+
+        match reply.payload {
+            MessagePayload::Kv {
+                payload: KvMessagePayload::Response(response),
+                ..
+            } => match response.error() {
+                Some(error) => Err(error),
+                None => Ok(response),
+            },
+            _ => Err(PluginError::Protocol("Unexpected KV response".to_string())),
+        }
+    }
+
+    async fn request_result(&self, op: KvOp) -> PluginResult<KvResult> {
+        self.request(op)
+            .await?
+            .result
+            .ok_or_else(|| PluginError::Protocol("KV response without result".to_string()))
+    }
+}
+
+fn unexpected_result() -> PluginError {
+    PluginError::Protocol("Unexpected KV result".to_string())
+}
+
+#[async_trait::async_trait]
+impl PluginKvStore for HostKvStore {
+    async fn get(&self, key: &str) -> PluginResult<Option<String>> {
+        Ok(self
+            .request(KvOp::Get {
+                key: key.to_string(),
+            })
+            .await?
+            .value)
+    }
+
+    async fn set(&self, key: &str, value: &str) -> PluginResult<()> {
+        self.request(KvOp::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+            ttl_secs: None,
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn delete(&self, key: &str) -> PluginResult<()> {
+        self.request(KvOp::Delete {
+            key: key.to_string(),
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn set_with_ttl(&self, key: &str, value: &str, ttl: Duration) -> PluginResult<()> {
+        self.request(KvOp::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+            ttl_secs: Some(ttl.as_secs()),
+        })
+        .await
+        .map(|_| ())
+    }
+
+    async fn list(&self, prefix: &str, cursor: Option<&str>, limit: usize) -> PluginResult<KvPage> {
+        let op = KvOp::List {
+            prefix: prefix.to_string(),
+            cursor: cursor.map(str::to_string),
+            limit: Some(limit.min(MAX_LIST_LIMIT) as u32),
+        };
+        match self.request_result(op).await? {
+            KvResult::Page(page) => Ok(page),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        expected: Option<&str>,
+        new: Option<&str>,
+    ) -> PluginResult<bool> {
+        let op = KvOp::CompareAndSwap {
+            key: key.to_string(),
+            expected: expected.map(str::to_string),
+            new: new.map(str::to_string),
+        };
+        match self.request_result(op).await? {
+            KvResult::Swapped { swapped } => Ok(swapped),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn increment(&self, key: &str, delta: i64) -> PluginResult<i64> {
+        let op = KvOp::Increment {
+            key: key.to_string(),
+            delta,
+        };
+        match self.request_result(op).await? {
+            KvResult::Counter { count } => Ok(count),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn get_many(&self, keys: &[String]) -> PluginResult<Vec<Option<String>>> {
+        let op = KvOp::GetMany {
+            keys: keys.to_vec(),
+        };
+        match self.request_result(op).await? {
+            KvResult::Values { values } => Ok(values),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn set_many(&self, entries: &[KvSetEntry]) -> PluginResult<()> {
+        self.request(KvOp::SetMany {
+            entries: entries.to_vec(),
+        })
+        .await
+        .map(|_| ())
+    }
+}
+
+/// In-memory KV store
+///
+/// Used when the core does not provide a host socket, and handy in tests.
+/// Clones share the same data, which is lost when the plugin exits.
+#[derive(Clone, Default)]
+pub struct MemoryKvStore {
+    values: Arc<std::sync::Mutex<BTreeMap<String, MemoryValue>>>,
+}
+
+struct MemoryValue {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl MemoryValue {
+    fn live(&self) -> bool {
+        self.expires_at.is_none_or(|at| at > Instant::now())
+    }
+}
+
+impl MemoryKvStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn values(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, MemoryValue>> {
+        let mut values = self.values.lock().unwrap_or_else(|e| e.into_inner());
+        values.retain(|_, value| value.live());
+        values
+    }
+}
+
+#[async_trait::async_trait]
+impl PluginKvStore for MemoryKvStore {
+    async fn get(&self, key: &str) -> PluginResult<Option<String>> {
+        Ok(self.values().get(key).map(|v| v.value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &str) -> PluginResult<()> {
+        self.values().insert(
+            key.to_string(),
+            MemoryValue {
+                value: value.to_string(),
+                expires_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> PluginResult<()> {
+        self.values().remove(key);
+        Ok(())
+    }
+
+    async fn set_with_ttl(&self, key: &str, value: &str, ttl: Duration) -> PluginResult<()> {
+        self.values().insert(
+            key.to_string(),
+            MemoryValue {
+                value: value.to_string(),
+                // A TTL too large to represent never expires, as in the core
+                expires_at: Instant::now().checked_add(ttl),
+            },
+        );
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str, cursor: Option<&str>, limit: usize) -> PluginResult<KvPage> {
+        let values = self.values();
+        let mut entries: Vec<KvEntry> = values
+            .range::<str, _>((
+                match cursor {
+                    Some(cursor) => std::ops::Bound::Excluded(cursor),
+                    None => std::ops::Bound::Included(prefix),
+                },
+                std::ops::Bound::Unbounded,
+            ))
+            .skip_while(|(key, _)| key.as_str() < prefix)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .take(limit + 1)
+            .map(|(key, value)| KvEntry {
+                key: key.clone(),
+                value: value.value.clone(),
+            })
+            .collect();
+
+        let next_cursor = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|entry| entry.key.clone())
+        } else {
+            None
+        };
+        Ok(KvPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        expected: Option<&str>,
+        new: Option<&str>,
+    ) -> PluginResult<bool> {
+        let mut values = self.values();
+        if values.get(key).map(|v| v.value.as_str()) != expected {
+            return Ok(false);
+        }
+        match new {
+            Some(new) => {
+                values.insert(
+                    key.to_string(),
+                    MemoryValue {
+                        value: new.to_string(),
+                        expires_at: None,
+                    },
+                );
+            }
+            None => {
+                values.remove(key);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn increment(&self, key: &str, delta: i64) -> PluginResult<i64> {
+        let mut values = self.values();
+        let entry = values.entry(key.to_string()).or_insert(MemoryValue {
+            value: "0".to_string(),
+            expires_at: None,
+        });
+        let count = entry
+            .value
+            .parse::<i64>()
+            .map_err(|_| {
+                PluginError::InvalidRequest(format!("Value of {:?} is not an integer", key))
+            })?
+            .checked_add(delta)
+            .ok_or_else(|| PluginError::InvalidRequest("Integer overflow".to_string()))?;
+        entry.value = count.to_string();
+        Ok(count)
+    }
+
+    async fn set_many(&self, entries: &[KvSetEntry]) -> PluginResult<()> {
+        let now = Instant::now();
+        let mut values = self.values();
+        for entry in entries {
+            values.insert(
+                entry.key.clone(),
+                MemoryValue {
+                    value: entry.value.clone(),
+                    expires_at: entry
+                        .ttl_secs
+                        .and_then(|ttl| now.checked_add(Duration::from_secs(ttl))),
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_execute_against_memory_store() {
+        let store = MemoryKvStore::new();
+        for key in ["item:1", "item:2", "item:3", "other"] {
+            store.set(key, key).await.unwrap();
+        }
+
+        let list = |cursor: Option<&str>| KvOp::List {
+            prefix: "item:".to_string(),
+            cursor: cursor.map(str::to_string),
+            limit: Some(2),
+        };
+        let first = execute(&store, list(None)).await.unwrap();
+        let Some(KvResult::Page(first)) = first.result else {
+            panic!("Expected a page");
+        };
+        assert_eq!(first.entries.len(), 2);
+        assert_eq!(first.next_cursor.as_deref(), Some("item:2"));
+        let second = execute(&store, list(first.next_cursor.as_deref()))
+            .await
+            .unwrap();
+        assert_eq!(
+            second.result,
+            Some(KvResult::Page(KvPage {
+                entries: vec![KvEntry {
+                    key: "item:3".to_string(),
+                    value: "item:3".to_string(),
+                }],
+                next_cursor: None,
+            }))
+        );
+
+        let increment = KvOp::Increment {
+            key: "counter".to_string(),
+            delta: 5,
+        };
+        let response = execute(&store, increment).await.unwrap();
+        assert_eq!(response.value.as_deref(), Some("5"));
+        assert!(execute(
+            &store,
+            KvOp::Increment {
+                key: "other".to_string(),
+                delta: 1,
+            }
+        )
+        .await
+        .is_err());
+
+        let cas = |expected: Option<&str>| KvOp::CompareAndSwap {
+            key: "counter".to_string(),
+            expected: expected.map(str::to_string),
+            new: Some("10".to_string()),
+        };
+        let response = execute(&store, cas(Some("4"))).await.unwrap();
+        assert_eq!(response.result, Some(KvResult::Swapped { swapped: false }));
+        let response = execute(&store, cas(Some("5"))).await.unwrap();
+        assert_eq!(response.result, Some(KvResult::Swapped { swapped: true }));
+
+        store
+            .set_with_ttl("session", "x", Duration::ZERO)
+            .await
+            .unwrap();
+        let response = execute(
+            &store,
+            KvOp::GetMany {
+                keys: vec!["counter".to_string(), "session".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.result,
+            Some(KvResult::Values {
+                values: vec![Some("10".to_string()), None],
+            })
+        );
+
+        // A huge TTL from a plugin means the value never expires
+        let forever = |key: &str| KvSetEntry {
+            key: key.to_string(),
+            value: "kept".to_string(),
+            ttl_secs: Some(u64::MAX),
+        };
+        for op in [
+            KvOp::Set {
+                key: "forever:1".to_string(),
+                value: "kept".to_string(),
+                ttl_secs: Some(u64::MAX),
+            },
+            KvOp::SetMany {
+                entries: vec![forever("forever:2")],
+            },
+        ] {
+            execute(&store, op).await.unwrap();
+        }
+        for key in ["forever:1", "forever:2"] {
+            assert_eq!(store.get(key).await.unwrap().as_deref(), Some("kept"));
+        }
+    }
+
+    #[test]
+    fn test_failed_response_keeps_error_kind() {
+        let response = KvMessageResponse::failed(&PluginError::QuotaExceeded("full".to_string()));
+        assert_eq!(response.error.as_deref(), Some("full"));
+        assert!(matches!(
+            response.error(),
+            Some(PluginError::QuotaExceeded(message)) if message == "full"
+        ));
+
+        let response = KvMessageResponse::failed(&PluginError::Timeout);
+        assert!(matches!(response.error(), Some(PluginError::Internal(_))));
+        assert!(KvMessageResponse::default().error().is_none());
+    }
+}
@@ -1,13 +1,30 @@
 pub mod error;
+pub mod kv;
 pub mod message;
 pub mod protocol;
+pub mod router;
+pub mod runtime;
+pub mod sql;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
 
 pub use error::{PluginError, PluginResult};
+pub use kv::{HostKvStore, MemoryKvStore};
 pub use message::Message;
 pub use protocol::PluginProtocol;
+pub use router::{RoutedRequest, Router};
+pub use runtime::run;
+pub use sql::HostDatabase;
+pub use toru_plugin_macros::toru_plugin;
 pub use types::{KvMessagePayload, *};
 
+/// Used by code generated by [`toru_plugin`], not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use tokio;
+}
+
 #[async_trait::async_trait]
 pub trait ToruPlugin {
     fn metadata() -> PluginMetadata;
@@ -17,4 +34,14 @@ pub trait ToruPlugin {
     async fn handle_http(&self, req: HttpRequest) -> PluginResult<HttpResponse>;
 
     async fn handle_kv(&mut self, op: KvOp) -> PluginResult<Option<String>>;
+
+    /// Frontend bundle served at `/bundle.js`, without calling `handle_http`
+    fn bundle() -> Option<&'static str> {
+        None
+    }
+
+    /// Called once after the core sent `shutdown` and in-flight requests finished
+    async fn shutdown(&mut self) -> PluginResult<()> {
+        Ok(())
+    }
 }
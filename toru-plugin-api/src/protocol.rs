@@ -1,8 +1,15 @@
-use crate::{error::PluginResult, types::Message};
+use crate::{
+    error::{PluginError, PluginResult},
+    types::Message,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::UnixStream;
+use tokio::sync::Mutex;
 
 /// Maximum message size to prevent memory exhaustion attacks (16 MB)
-const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
 
 pub struct PluginProtocol;
 
@@ -11,13 +18,17 @@ impl PluginProtocol {
         Self
     }
 
-    pub async fn read_message(&mut self, stream: &mut UnixStream) -> PluginResult<Message> {
-        use tokio::io::{AsyncReadExt, BufReader};
+    pub async fn read_message<R>(&mut self, stream: &mut R) -> PluginResult<Message>
+    where
+        R: AsyncRead + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
 
-        let mut reader = BufReader::new(stream);
+        // Read straight from the stream: a buffered reader would consume
+        // bytes of the next message and lose them when dropped
         let mut length_buf = [0u8; 4];
 
-        reader.read_exact(&mut length_buf).await?;
+        stream.read_exact(&mut length_buf).await?;
 
         let length = u32::from_be_bytes(length_buf) as usize;
 
@@ -31,18 +42,17 @@ impl PluginProtocol {
 
         let mut msg_buf = vec![0u8; length];
 
-        reader.read_exact(&mut msg_buf).await?;
+        stream.read_exact(&mut msg_buf).await?;
 
         let message: Message = serde_json::from_slice(&msg_buf)?;
 
         Ok(message)
     }
 
-    pub async fn write_message(
-        &self,
-        stream: &mut UnixStream,
-        message: &Message,
-    ) -> PluginResult<()> {
+    pub async fn write_message<W>(&self, stream: &mut W, message: &Message) -> PluginResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
         use tokio::io::AsyncWriteExt;
 
         let json = serde_json::to_vec(message)?;
@@ -61,3 +71,65 @@ impl Default for PluginProtocol {
         Self::new()
     }
 }
+
+/// Request/reply connection to the core's host socket
+///
+/// Requests are sent one at a time on a single connection, opened on first
+/// use and reopened after errors.
+pub(crate) struct HostConnection {
+    socket_path: PathBuf,
+    connection: Mutex<Option<UnixStream>>,
+}
+
+impl HostConnection {
+    pub(crate) fn new(socket_path: PathBuf) -> Self {
+        Self {
+            socket_path,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Send a message and wait for the reply carrying the same request ID
+    pub(crate) async fn request(
+        &self,
+        message: &Message,
+        timeout: Duration,
+    ) -> PluginResult<Message> {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            let stream = UnixStream::connect(&self.socket_path)
+                .await
+                .map_err(|e| PluginError::Socket(format!("Failed to connect to core: {}", e)))?;
+            *connection = Some(stream);
+        }
+        let Some(stream) = connection.as_mut() else {
+            return Err(PluginError::Socket("Not connected to core".to_string()));
+        };
+
+        let result = async {
+            let mut protocol = PluginProtocol::new();
+            protocol.write_message(stream, message).await?;
+            tokio::time::timeout(timeout, protocol.read_message(stream))
+                .await
+                .map_err(|_| PluginError::Timeout)?
+        }
+        .await;
+
+        match result {
+            Ok(reply) if reply.request_id.is_some() && reply.request_id == message.request_id => {
+                Ok(reply)
+            }
+            Ok(_) => {
+                // The connection is out of sync, start over next time
+                *connection = None;
+                Err(PluginError::Protocol(
+                    "Reply does not match the request".to_string(),
+                ))
+            }
+            Err(e) => {
+                *connection = None;
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{PluginError, PluginResult};
+use crate::types::{HttpRequest, HttpResponse};
+
+/// Maps method and path patterns to routes
+///
+/// A route is any value, usually a fieldless enum, that `handle_http`
+/// matches on. This keeps handlers as plain async code with access to
+/// the plugin:
+///
+/// ```
+/// # use toru_plugin_api::*;
+/// #[derive(Clone, Copy)]
+/// enum Route { ListItems, GetItem }
+///
+/// let router = Router::new()
+///     .get("/items", Route::ListItems)
+///     .get("/items/:id", Route::GetItem);
+///
+/// # fn handle(router: &Router<Route>, req: HttpRequest) -> PluginResult<HttpResponse> {
+/// let (route, req) = match router.route(req) {
+///     Ok(matched) => matched,
+///     Err(response) => return Ok(response),
+/// };
+/// match route {
+///     Route::ListItems => HttpResponse::json(200, &["a", "b"]),
+///     Route::GetItem => {
+///         let id: u64 = req.param("id")?;
+///         HttpResponse::json(200, &serde_json::json!({ "id": id }))
+///     }
+/// }
+/// # }
+/// ```
+///
+/// Patterns are matched segment by segment: `:name` captures one segment,
+/// `*name` as the last segment captures the rest of the path. Trailing
+/// slashes are ignored.
+pub struct Router<T> {
+    routes: Vec<RouteEntry<T>>,
+}
+
+struct RouteEntry<T> {
+    method: String,
+    segments: Vec<Segment>,
+    route: T,
+}
+
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Add a route
+    ///
+    /// Routes are tried in the order they were added.
+    ///
+    /// # Arguments
+    /// * `method` - HTTP method, matched case-insensitively
+    /// * `pattern` - Path pattern such as `/items/:id`
+    /// * `route` - Value returned when the pattern matches
+    pub fn add(mut self, method: &str, pattern: &str, route: T) -> Self {
+        let segments = split_path(pattern)
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    Segment::Param(name.to_string())
+                } else if let Some(name) = segment.strip_prefix('*') {
+                    Segment::Rest(name.to_string())
+                } else {
+                    Segment::Literal(segment.to_string())
+                }
+            })
+            .collect();
+
+        self.routes.push(RouteEntry {
+            method: method.to_ascii_uppercase(),
+            segments,
+            route,
+        });
+        self
+    }
+
+    pub fn get(self, pattern: &str, route: T) -> Self {
+        self.add("GET", pattern, route)
+    }
+
+    pub fn post(self, pattern: &str, route: T) -> Self {
+        self.add("POST", pattern, route)
+    }
+
+    pub fn put(self, pattern: &str, route: T) -> Self {
+        self.add("PUT", pattern, route)
+    }
+
+    pub fn patch(self, pattern: &str, route: T) -> Self {
+        self.add("PATCH", pattern, route)
+    }
+
+    pub fn delete(self, pattern: &str, route: T) -> Self {
+        self.add("DELETE", pattern, route)
+    }
+
+    /// Find the route for a request
+    ///
+    /// Returns a ready-made 404 response when no pattern matches the path,
+    /// or 405 with an `Allow` header when only the method differs.
+    pub fn route(&self, request: HttpRequest) -> Result<(&T, RoutedRequest), HttpResponse> {
+        let (path, query) = match request.path.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (request.path.clone(), None),
+        };
+        let segments: Vec<&str> = split_path(&path).collect();
+
+        let mut allowed = Vec::new();
+        for entry in &self.routes {
+            let Some(params) = match_segments(&entry.segments, &segments) else {
+                continue;
+            };
+            if !entry.method.eq_ignore_ascii_case(&request.method) {
+                allowed.push(entry.method.clone());
+                continue;
+            }
+
+            return Ok((
+                &entry.route,
+                RoutedRequest {
+                    path,
+                    query,
+                    params,
+                    request,
+                },
+            ));
+        }
+
+        if allowed.is_empty() {
+            return Err(HttpResponse::error(404, "Not found"));
+        }
+        allowed.sort_unstable();
+        allowed.dedup();
+        Err(HttpResponse::error(405, "Method not allowed").with_header("Allow", allowed.join(", ")))
+    }
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+fn match_segments(pattern: &[Segment], path: &[&str]) -> Option<HashMap<String, String>> {
+    let mut params = HashMap::new();
+
+    for (index, segment) in pattern.iter().enumerate() {
+        match segment {
+            Segment::Rest(name) => {
+                let rest: Vec<String> = path.get(index..)?.iter().map(|s| decode(s)).collect();
+                params.insert(name.clone(), rest.join("/"));
+                return Some(params);
+            }
+            Segment::Literal(literal) => {
+                if path.get(index) != Some(&literal.as_str()) {
+                    return None;
+                }
+            }
+            Segment::Param(name) => {
+                params.insert(name.clone(), decode(path.get(index)?));
+            }
+        }
+    }
+
+    (pattern.len() == path.len()).then_some(params)
+}
+
+fn decode(segment: &str) -> String {
+    percent_encoding::percent_decode_str(segment)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// A request matched by [`Router::route`], with typed access to its parts
+#[derive(Debug)]
+pub struct RoutedRequest {
+    path: String,
+    query: Option<String>,
+    params: HashMap<String, String>,
+    request: HttpRequest,
+}
+
+impl RoutedRequest {
+    pub fn method(&self) -> &str {
+        &self.request.method
+    }
+
+    /// Path without the query string
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Raw query string, without the leading `?`
+    pub fn query_string(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// Header value, looked up case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.request
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.request.body.as_deref()
+    }
+
+    /// Parse a path parameter
+    ///
+    /// Fails with [`PluginError::InvalidRequest`] (a 400 response) when the
+    /// parameter does not parse.
+    pub fn param<P: FromStr>(&self, name: &str) -> PluginResult<P> {
+        let value = self
+            .params
+            .get(name)
+            .ok_or_else(|| PluginError::Internal(format!("No path parameter named {:?}", name)))?;
+        value
+            .parse()
+            .map_err(|_| PluginError::InvalidRequest(format!("Invalid path parameter {:?}", name)))
+    }
+
+    /// Deserialize the query string
+    ///
+    /// A missing query string deserializes like an empty one.
+    pub fn query<Q: DeserializeOwned>(&self) -> PluginResult<Q> {
+        serde_urlencoded::from_str(self.query.as_deref().unwrap_or(""))
+            .map_err(|e| PluginError::InvalidRequest(format!("Invalid query string: {}", e)))
+    }
+
+    /// Deserialize the JSON body
+    pub fn json<B: DeserializeOwned>(&self) -> PluginResult<B> {
+        let body = self
+            .body()
+            .ok_or_else(|| PluginError::InvalidRequest("Missing request body".to_string()))?;
+        serde_json::from_str(body)
+            .map_err(|e| PluginError::InvalidRequest(format!("Invalid JSON body: {}", e)))
+    }
+
+    /// The original request
+    pub fn into_inner(self) -> HttpRequest {
+        self.request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Route {
+        Index,
+        Item,
+        CreateItem,
+        Files,
+    }
+
+    fn router() -> Router<Route> {
+        Router::new()
+            .get("/", Route::Index)
+            .get("/items/:id", Route::Item)
+            .post("/items", Route::CreateItem)
+            .get("/files/*path", Route::Files)
+    }
+
+    fn request(method: &str, path: &str, body: Option<&str>) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: [("content-type".to_string(), "application/json".to_string())].into(),
+            body: body.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_routes_and_params() {
+        let router = router();
+
+        let (route, _) = router.route(request("GET", "", None)).unwrap();
+        assert_eq!(*route, Route::Index);
+
+        let (route, req) = router.route(request("get", "/items/42/", None)).unwrap();
+        assert_eq!(*route, Route::Item);
+        assert_eq!(req.param::<u64>("id").unwrap(), 42);
+        assert!(matches!(
+            req.param::<u64>("missing"),
+            Err(PluginError::Internal(_))
+        ));
+
+        let (route, req) = router
+            .route(request("GET", "/files/a/b%20c.txt", None))
+            .unwrap();
+        assert_eq!(*route, Route::Files);
+        assert_eq!(req.param::<String>("path").unwrap(), "a/b c.txt");
+
+        let (_, req) = router.route(request("GET", "/items/abc", None)).unwrap();
+        assert!(matches!(
+            req.param::<u64>("id"),
+            Err(PluginError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_found_and_method_not_allowed() {
+        let router = router();
+
+        let response = router.route(request("GET", "/nope", None)).unwrap_err();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.headers["Content-Type"], "application/json");
+
+        let response = router.route(request("DELETE", "/items", None)).unwrap_err();
+        assert_eq!(response.status, 405);
+        assert_eq!(response.headers["Allow"], "POST");
+
+        // Methods of every matching pattern, each listed once
+        let router = Router::new()
+            .get("/a/:id", 1)
+            .post("/a/:id", 2)
+            .get("/a/x", 3);
+        let response = router.route(request("DELETE", "/a/x", None)).unwrap_err();
+        assert_eq!(response.headers["Allow"], "GET, POST");
+    }
+
+    #[test]
+    fn test_query_and_json_extractors() {
+        #[derive(Deserialize)]
+        struct Page {
+            limit: u32,
+            search: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct NewItem {
+            name: String,
+        }
+
+        let router = router();
+
+        let (_, req) = router
+            .route(request("GET", "/items/1?limit=10&search=a%20b", None))
+            .unwrap();
+        assert_eq!(req.path(), "/items/1");
+        assert_eq!(req.header("Content-Type"), Some("application/json"));
+        let page: Page = req.query().unwrap();
+        assert_eq!(page.limit, 10);
+        assert_eq!(page.search.as_deref(), Some("a b"));
+
+        let (_, req) = router.route(request("GET", "/items/1", None)).unwrap();
+        assert!(matches!(
+            req.query::<Page>(),
+            Err(PluginError::InvalidRequest(_))
+        ));
+
+        let (_, req) = router
+            .route(request("POST", "/items", Some(r#"{"name":"x"}"#)))
+            .unwrap();
+        assert_eq!(req.json::<NewItem>().unwrap().name, "x");
+
+        let (_, req) = router
+            .route(request("POST", "/items", Some("not json")))
+            .unwrap();
+        assert!(matches!(
+            req.json::<NewItem>(),
+            Err(PluginError::InvalidRequest(_))
+        ));
+    }
+}
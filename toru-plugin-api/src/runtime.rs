@@ -0,0 +1,329 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{Mutex, Notify, RwLock};
+
+use crate::error::{PluginError, PluginResult};
+use crate::kv::{HostKvStore, MemoryKvStore};
+use crate::sql::HostDatabase;
+use crate::types::{
+    HttpMessageResponse, HttpRequest, HttpResponse, KvMessagePayload, LifecycleInitPayload,
+    Message, MessagePayload, PluginConfig, PluginContext, PluginDatabase, PluginKvStore,
+};
+use crate::{PluginProtocol, ToruPlugin};
+
+/// Directory used for the plugin socket when `TORU_PLUGIN_SOCKET` is not set
+const DEFAULT_SOCKET_DIR: &str = "/tmp/toru-plugins";
+
+/// How long shutdown waits for requests that are still being handled.
+/// The core kills plugins 5s after sending `shutdown`.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+/// Run a plugin until the core shuts it down
+///
+/// Handles everything around the [`ToruPlugin`] implementation:
+/// - `--metadata` prints the metadata as JSON and exits
+/// - listens on `TORU_PLUGIN_SOCKET` (or `/tmp/toru-plugins/<id>.sock`)
+/// - `init` builds a [`PluginContext`] whose KV store talks to the core
+/// - HTTP requests are handled concurrently and answered with the request ID
+/// - `GET /bundle.js` is answered with [`ToruPlugin::bundle`] when it is set
+/// - `shutdown` (or SIGTERM/SIGINT) stops accepting connections, lets
+///   in-flight requests finish and calls [`ToruPlugin::shutdown`]
+///
+/// ```no_run
+/// # use toru_plugin_api::*;
+/// # struct MyPlugin;
+/// # #[async_trait::async_trait]
+/// # impl ToruPlugin for MyPlugin {
+/// #     fn metadata() -> PluginMetadata { unimplemented!() }
+/// #     async fn init(&mut self, _ctx: PluginContext) -> PluginResult<()> { Ok(()) }
+/// #     async fn handle_http(&self, _req: HttpRequest) -> PluginResult<HttpResponse> { unimplemented!() }
+/// #     async fn handle_kv(&mut self, _op: KvOp) -> PluginResult<Option<String>> { Ok(None) }
+/// # }
+/// #[tokio::main]
+/// async fn main() -> PluginResult<()> {
+///     toru_plugin_api::run(MyPlugin).await
+/// }
+/// ```
+pub async fn run<P>(plugin: P) -> PluginResult<()>
+where
+    P: ToruPlugin + Send + Sync + 'static,
+{
+    if std::env::args().nth(1).as_deref() == Some("--metadata") {
+        println!("{}", serde_json::to_string_pretty(&P::metadata())?);
+        return Ok(());
+    }
+
+    serve(plugin, &socket_path::<P>()).await
+}
+
+/// Socket path the plugin should listen on
+pub fn socket_path<P: ToruPlugin>() -> PathBuf {
+    std::env::var_os("TORU_PLUGIN_SOCKET")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(DEFAULT_SOCKET_DIR).join(format!("{}.sock", P::metadata().id)))
+}
+
+/// Serve a plugin on a socket until it is shut down
+///
+/// Like [`run`], without argument handling or environment lookup.
+pub async fn serve<P>(plugin: P, socket_path: &Path) -> PluginResult<()>
+where
+    P: ToruPlugin + Send + Sync + 'static,
+{
+    let listener = bind(socket_path)?;
+    let state = Arc::new(RuntimeState {
+        plugin: RwLock::new(plugin),
+        shutdown: Notify::new(),
+        in_flight: AtomicUsize::new(0),
+        idle: Notify::new(),
+    });
+
+    let shutdown_signal = termination_signal();
+    tokio::pin!(shutdown_signal);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(Arc::clone(&state), stream));
+                }
+                Err(e) => eprintln!("Failed to accept connection: {}", e),
+            },
+            _ = state.shutdown.notified() => break,
+            _ = &mut shutdown_signal => break,
+        }
+    }
+
+    // Stop accepting before draining, so no new work arrives
+    drop(listener);
+    std::fs::remove_file(socket_path).ok();
+
+    if tokio::time::timeout(SHUTDOWN_GRACE, state.wait_idle())
+        .await
+        .is_err()
+    {
+        eprintln!("Shutting down with requests still in flight");
+    }
+
+    state.plugin.write().await.shutdown().await?;
+    Ok(())
+}
+
+/// Bind the plugin socket, replacing a stale one
+fn bind(socket_path: &Path) -> PluginResult<UnixListener> {
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if socket_path.exists() {
+        std::fs::remove_file(socket_path)?;
+    }
+    UnixListener::bind(socket_path)
+        .map_err(|e| PluginError::Socket(format!("Failed to bind {:?}: {}", socket_path, e)))
+}
+
+/// Resolves when the process is asked to terminate
+async fn termination_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+    ) {
+        (Ok(mut terminate), Ok(mut interrupt)) => {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = interrupt.recv() => {}
+            }
+        }
+        // Without signal handlers, only the shutdown message stops the plugin
+        _ => std::future::pending().await,
+    }
+}
+
+struct RuntimeState<P> {
+    plugin: RwLock<P>,
+    shutdown: Notify,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl<P> RuntimeState<P> {
+    /// Wait until no request is being handled
+    async fn wait_idle(&self) {
+        loop {
+            let idle = self.idle.notified();
+            tokio::pin!(idle);
+            // Register before checking, so a request finishing in between is not missed
+            idle.as_mut().enable();
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+/// Marks a request as in flight until dropped
+struct RequestGuard<P>(Arc<RuntimeState<P>>);
+
+impl<P> RequestGuard<P> {
+    fn new(state: &Arc<RuntimeState<P>>) -> Self {
+        state.in_flight.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(state))
+    }
+}
+
+impl<P> Drop for RequestGuard<P> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Read messages from one core connection until it closes
+async fn handle_connection<P>(state: Arc<RuntimeState<P>>, stream: UnixStream)
+where
+    P: ToruPlugin + Send + Sync + 'static,
+{
+    let (mut reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+    let mut protocol = PluginProtocol::new();
+
+    loop {
+        let message = match protocol.read_message(&mut reader).await {
+            Ok(message) => message,
+            // The core closes connections after each exchange
+            Err(PluginError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                eprintln!("Failed to read message: {}", e);
+                break;
+            }
+        };
+
+        match message.payload {
+            MessagePayload::Lifecycle { action, payload } => match action.as_str() {
+                "init" => match payload {
+                    Some(payload) => {
+                        let ctx = plugin_context(payload);
+                        if let Err(e) = state.plugin.write().await.init(ctx).await {
+                            eprintln!("Plugin init failed: {}", e);
+                        }
+                    }
+                    None => eprintln!("Init message without payload"),
+                },
+                "shutdown" => {
+                    state.shutdown.notify_one();
+                    break;
+                }
+                other => eprintln!("Ignoring unknown lifecycle action {:?}", other),
+            },
+            MessagePayload::Http {
+                request_id,
+                payload,
+            } => {
+                let guard = RequestGuard::new(&state);
+                let state = Arc::clone(&state);
+                let writer = Arc::clone(&writer);
+                tokio::spawn(async move {
+                    let response = match bundle_response::<P>(&payload) {
+                        Some(response) => response,
+                        None => match state.plugin.read().await.handle_http(payload).await {
+                            Ok(response) => response.into(),
+                            Err(e) => error_response(&e),
+                        },
+                    };
+                    send(&writer, &Message::new_http_response(request_id, response)).await;
+                    drop(guard);
+                });
+            }
+            MessagePayload::Kv {
+                request_id,
+                payload: KvMessagePayload::Request(op),
+            } => {
+                let guard = RequestGuard::new(&state);
+                let value = match state.plugin.write().await.handle_kv(op).await {
+                    Ok(value) => value,
+                    Err(e) => {
+                        eprintln!("KV operation failed: {}", e);
+                        None
+                    }
+                };
+                send(&writer, &Message::new_kv_response(request_id, value)).await;
+                drop(guard);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Build the context handed to [`ToruPlugin::init`]
+fn plugin_context(payload: LifecycleInitPayload) -> PluginContext {
+    let db = payload
+        .host_socket
+        .as_ref()
+        .map(|host_socket| Box::new(HostDatabase::new(host_socket)) as Box<dyn PluginDatabase>);
+    let kv: Box<dyn PluginKvStore> = match payload.host_socket {
+        Some(host_socket) => Box::new(HostKvStore::new(host_socket)),
+        None => {
+            eprintln!("Core did not provide a host socket, KV data will not persist");
+            Box::new(MemoryKvStore::new())
+        }
+    };
+
+    PluginContext {
+        instance_id: payload.instance_id,
+        config: PluginConfig {
+            env: std::env::vars()
+                .filter(|(key, _)| key.starts_with("TORU_"))
+                .collect(),
+        },
+        kv,
+        data_dir: payload.data_dir.map(std::path::PathBuf::from),
+        db,
+    }
+}
+
+/// Serve the bundle embedded with [`ToruPlugin::bundle`], if any
+pub(crate) fn bundle_response<P: ToruPlugin>(request: &HttpRequest) -> Option<HttpMessageResponse> {
+    if request.method != "GET" || request.path != "/bundle.js" {
+        return None;
+    }
+
+    P::bundle().map(|bundle| HttpMessageResponse {
+        status: 200,
+        headers: [(
+            "Content-Type".to_string(),
+            "application/javascript".to_string(),
+        )]
+        .into(),
+        body: Some(bundle.to_string()),
+    })
+}
+
+/// Response sent when the plugin's handler fails
+pub(crate) fn error_response(error: &PluginError) -> HttpMessageResponse {
+    let status = match error {
+        PluginError::InvalidRequest(_) => 400,
+        PluginError::NotInitialized => 503,
+        PluginError::Timeout => 504,
+        PluginError::QuotaExceeded(_) => 507,
+        _ => 500,
+    };
+
+    HttpResponse::error(status, error).into()
+}
+
+async fn send(writer: &Mutex<OwnedWriteHalf>, message: &Message) {
+    let mut writer = writer.lock().await;
+    if let Err(e) = PluginProtocol::new()
+        .write_message(&mut *writer, message)
+        .await
+    {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::{PluginError, PluginResult};
+use crate::protocol::HostConnection;
+use crate::types::{
+    Message, MessagePayload, PluginDatabase, SqlExecuted, SqlMessagePayload, SqlMessageResponse,
+    SqlMigration, SqlOp, SqlResult, SqlRows, SqlStatement, SqlValue,
+};
+
+/// How long a SQL operation waits for the core to answer
+const SQL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a SQL operation against a database
+///
+/// Shared by everything that answers `sql` messages, so all databases give
+/// the same result shapes.
+///
+/// # Arguments
+/// * `db` - Database the operation is applied to
+/// * `op` - Operation from a `sql` message
+pub async fn execute(db: &dyn PluginDatabase, op: SqlOp) -> PluginResult<SqlMessageResponse> {
+    let result = match op {
+        SqlOp::Query { sql, params } => SqlResult::Rows(db.query(&sql, &params).await?),
+        SqlOp::Execute { sql, params } => SqlResult::Executed(db.execute(&sql, &params).await?),
+        SqlOp::Batch { statements } => SqlResult::Batch {
+            rows_affected: db.batch(&statements).await?,
+        },
+        SqlOp::Migrate { migrations } => SqlResult::Migrated {
+            applied: db.migrate(&migrations).await?,
+        },
+    };
+
+    Ok(SqlMessageResponse {
+        result: Some(result),
+        ..Default::default()
+    })
+}
+
+/// SQL database provisioned by the core, reached over the host socket
+///
+/// Every plugin gets its own database file; the core picks it from the
+/// socket a message arrives on, so plugins cannot reach each other's data.
+pub struct HostDatabase {
+    connection: HostConnection,
+}
+
+impl HostDatabase {
+    /// Create a client for the host socket passed in the init payload
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            connection: HostConnection::new(socket_path.into()),
+        }
+    }
+
+    async fn request(&self, op: SqlOp) -> PluginResult<SqlResult> {
+        let request = Message::new_sql(uuid::Uuid::new_v4().to_string(), op);
+        let reply = self.connection.request(&request, SQL_TIMEOUT).await?;
+
+        match reply.payload {
+            MessagePayload::Sql {
+                payload: SqlMessagePayload::Response(response),
+                ..
+            } => match (response.error(), response.result) {
+                (Some(error), _) => Err(error),
+                (None, Some(result)) => Ok(result),
+                (None, None) => Err(PluginError::Protocol(
+                    "SQL response without result".to_string(),
+                )),
+            },
+            _ => Err(PluginError::Protocol("Unexpected SQL response".to_string())),
+        }
+    }
+}
+
+fn unexpected_result() -> PluginError {
+    PluginError::Protocol("Unexpected SQL result".to_string())
+}
+
+#[async_trait::async_trait]
+impl PluginDatabase for HostDatabase {
+    async fn query(&self, sql: &str, params: &[SqlValue]) -> PluginResult<SqlRows> {
+        let op = SqlOp::Query {
+            sql: sql.to_string(),
+            params: params.to_vec(),
+        };
+        match self.request(op).await? {
+            SqlResult::Rows(rows) => Ok(rows),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn execute(&self, sql: &str, params: &[SqlValue]) -> PluginResult<SqlExecuted> {
+        let op = SqlOp::Execute {
+            sql: sql.to_string(),
+            params: params.to_vec(),
+        };
+        match self.request(op).await? {
+            SqlResult::Executed(executed) => Ok(executed),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn batch(&self, statements: &[SqlStatement]) -> PluginResult<Vec<u64>> {
+        let op = SqlOp::Batch {
+            statements: statements.to_vec(),
+        };
+        match self.request(op).await? {
+            SqlResult::Batch { rows_affected } => Ok(rows_affected),
+            _ => Err(unexpected_result()),
+        }
+    }
+
+    async fn migrate(&self, migrations: &[SqlMigration]) -> PluginResult<Vec<String>> {
+        let op = SqlOp::Migrate {
+            migrations: migrations.to_vec(),
+        };
+        match self.request(op).await? {
+            SqlResult::Migrated { applied } => Ok(applied),
+            _ => Err(unexpected_result()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_message_roundtrip() {
+        let message = Message::new_sql(
+            "req-1".to_string(),
+            SqlOp::Query {
+                sql: "SELECT * FROM orders WHERE id = ?".to_string(),
+                params: vec![SqlValue::from(7), SqlValue::Null],
+            },
+        );
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["payload"]["action"], "Query");
+        assert_eq!(json["payload"]["params"], serde_json::json!([7, null]));
+
+        let decoded: Message = serde_json::from_value(json).unwrap();
+        match decoded.payload {
+            MessagePayload::Sql {
+                payload: SqlMessagePayload::Request(SqlOp::Query { params, .. }),
+                ..
+            } => assert_eq!(params, [SqlValue::Integer(7), SqlValue::Null]),
+            other => panic!("unexpected payload: {:?}", other),
+        }
+
+        let reply = Message::new_sql_reply(
+            "req-1".to_string(),
+            SqlMessageResponse {
+                result: Some(SqlResult::Rows(SqlRows {
+                    columns: vec!["id".to_string(), "total".to_string()],
+                    rows: vec![vec![SqlValue::Integer(7), SqlValue::Real(9.5)]],
+                })),
+                ..Default::default()
+            },
+        );
+        let decoded: Message =
+            serde_json::from_str(&serde_json::to_string(&reply).unwrap()).unwrap();
+        match decoded.payload {
+            MessagePayload::Sql {
+                payload: SqlMessagePayload::Response(response),
+                ..
+            } => assert!(
+                matches!(response.result, Some(SqlResult::Rows(rows)) if rows.rows.len() == 1)
+            ),
+            other => panic!("unexpected payload: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failed_response_keeps_error_kind() {
+        let response =
+            SqlMessageResponse::failed(&PluginError::InvalidRequest("no such table".to_string()));
+        assert!(matches!(
+            response.error(),
+            Some(PluginError::InvalidRequest(message)) if message == "no such table"
+        ));
+    }
+}
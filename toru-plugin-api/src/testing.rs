@@ -0,0 +1,435 @@
+//! Test harness for plugin authors
+//!
+//! Plays the part of the core so plugins can be tested without a running
+//! steering-center. [`PluginHarness`] calls a [`ToruPlugin`] implementation
+//! directly; [`BinaryHarness`] runs a compiled plugin and talks to it over
+//! Unix sockets in a temporary directory, exactly like the core does.
+//!
+//! Both give the plugin a [`MemoryKvStore`] that tests can seed and inspect.
+//!
+//! Enabled with the `testing` feature, usually as a dev-dependency:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! toru-plugin-api = { version = "0.1", features = ["testing"] }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+use crate::error::{PluginError, PluginResult};
+use crate::kv::MemoryKvStore;
+use crate::runtime::{bundle_response, error_response};
+use crate::types::{
+    HttpRequest, HttpResponse, KvMessagePayload, KvMessageResponse, LifecycleInitPayload, Message,
+    MessagePayload, PluginConfig, PluginContext, SqlMessagePayload, SqlMessageResponse,
+};
+use crate::{PluginProtocol, ToruPlugin};
+
+/// Instance ID sent in the init message
+pub const TEST_INSTANCE_ID: &str = "test-instance";
+
+/// How long [`BinaryHarness`] waits for the plugin to start, answer or exit
+const BINARY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Build a request without headers
+///
+/// # Arguments
+/// * `method` - HTTP method
+/// * `path` - Path relative to the plugin route, may include a query string
+/// * `body` - Optional request body
+pub fn request(method: &str, path: &str, body: Option<&str>) -> HttpRequest {
+    HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers: Default::default(),
+        body: body.map(str::to_string),
+    }
+}
+
+/// Build a request with a JSON body and `Content-Type` header
+pub fn json_request<T: serde::Serialize + ?Sized>(
+    method: &str,
+    path: &str,
+    body: &T,
+) -> PluginResult<HttpRequest> {
+    let mut request = request(method, path, Some(&serde_json::to_string(body)?));
+    request
+        .headers
+        .insert("Content-Type".to_string(), "application/json".to_string());
+    Ok(request)
+}
+
+/// Drives a [`ToruPlugin`] implementation in-process
+///
+/// Requests go through the same steps as in the runtime: `/bundle.js` is
+/// served from [`ToruPlugin::bundle`] and handler errors become JSON error
+/// responses with the runtime's status codes.
+pub struct PluginHarness<P> {
+    plugin: P,
+    kv: MemoryKvStore,
+}
+
+impl<P: ToruPlugin + Send + Sync> PluginHarness<P> {
+    /// Initialize a plugin with an empty KV store
+    pub async fn new(plugin: P) -> PluginResult<Self> {
+        Self::with_kv(plugin, MemoryKvStore::new()).await
+    }
+
+    /// Initialize a plugin with a KV store prepared by the test
+    pub async fn with_kv(mut plugin: P, kv: MemoryKvStore) -> PluginResult<Self> {
+        plugin
+            .init(PluginContext {
+                instance_id: TEST_INSTANCE_ID.to_string(),
+                config: PluginConfig::default(),
+                kv: Box::new(kv.clone()),
+                data_dir: None,
+                db: None,
+            })
+            .await?;
+        Ok(Self { plugin, kv })
+    }
+
+    /// The plugin's KV store
+    pub fn kv(&self) -> &MemoryKvStore {
+        &self.kv
+    }
+
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    /// Send a request and return the response the core would receive
+    pub async fn request(&self, request: HttpRequest) -> HttpResponse {
+        if let Some(response) = bundle_response::<P>(&request) {
+            return response.into();
+        }
+        match self.plugin.handle_http(request).await {
+            Ok(response) => response,
+            Err(e) => error_response(&e).into(),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> HttpResponse {
+        self.request(request("GET", path, None)).await
+    }
+
+    /// Run the plugin's shutdown hook
+    pub async fn shutdown(mut self) -> PluginResult<()> {
+        self.plugin.shutdown().await
+    }
+}
+
+/// Runs a compiled plugin binary against a mock core
+///
+/// The binary gets `TORU_PLUGIN_SOCKET` and `TORU_HOST_SOCKET` in a
+/// temporary directory, is sent an init message and has its KV operations
+/// answered from a [`MemoryKvStore`]. Its data directory is inside the same
+/// temporary directory. The process is killed and the directory removed
+/// when the harness is dropped.
+pub struct BinaryHarness {
+    child: Child,
+    dir: PathBuf,
+    plugin_socket: PathBuf,
+    kv: MemoryKvStore,
+    host_task: JoinHandle<()>,
+}
+
+impl BinaryHarness {
+    /// Start a plugin binary and initialize it
+    pub async fn spawn(binary: impl AsRef<Path>) -> PluginResult<Self> {
+        Self::spawn_with_kv(binary, MemoryKvStore::new()).await
+    }
+
+    /// Start a plugin binary with a KV store prepared by the test
+    pub async fn spawn_with_kv(binary: impl AsRef<Path>, kv: MemoryKvStore) -> PluginResult<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "toru-plugin-test-{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..12]
+        ));
+        std::fs::create_dir_all(&dir)?;
+        let plugin_socket = dir.join("plugin.sock");
+        let host_socket = dir.join("host.sock");
+        let data_dir = dir.join("data");
+        std::fs::create_dir_all(&data_dir)?;
+
+        let listener = UnixListener::bind(&host_socket)?;
+        let host_task = tokio::spawn(serve_host(listener, kv.clone()));
+
+        let child = Command::new(binary.as_ref())
+            .env("TORU_PLUGIN_SOCKET", &plugin_socket)
+            .env("TORU_HOST_SOCKET", &host_socket)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut harness = Self {
+            child,
+            dir,
+            plugin_socket,
+            kv,
+            host_task,
+        };
+        harness.wait_for_socket().await?;
+        harness
+            .send(&Message::new_lifecycle(
+                "init",
+                Some(LifecycleInitPayload {
+                    instance_id: TEST_INSTANCE_ID.to_string(),
+                    plugin_socket: harness.plugin_socket.to_string_lossy().into_owned(),
+                    log_path: harness
+                        .dir
+                        .join("plugin.log")
+                        .to_string_lossy()
+                        .into_owned(),
+                    host_socket: Some(host_socket.to_string_lossy().into_owned()),
+                    data_dir: Some(data_dir.to_string_lossy().into_owned()),
+                }),
+            ))
+            .await?;
+        Ok(harness)
+    }
+
+    async fn wait_for_socket(&mut self) -> PluginResult<()> {
+        let deadline = tokio::time::Instant::now() + BINARY_TIMEOUT;
+        while !self.plugin_socket.exists() {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(PluginError::Internal(format!(
+                    "Plugin exited before creating its socket: {}",
+                    status
+                )));
+            }
+            if tokio::time::Instant::now() > deadline {
+                return Err(PluginError::Timeout);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Ok(())
+    }
+
+    async fn connect(&self) -> PluginResult<UnixStream> {
+        UnixStream::connect(&self.plugin_socket)
+            .await
+            .map_err(|e| PluginError::Socket(format!("Failed to connect to plugin: {}", e)))
+    }
+
+    /// Send a message that gets no reply and wait until it was handled
+    ///
+    /// Plugins handle messages on one connection in order and close it when
+    /// the core does, so the close tells us the message went through.
+    async fn send(&self, message: &Message) -> PluginResult<()> {
+        let mut stream = self.connect().await?;
+        PluginProtocol::new()
+            .write_message(&mut stream, message)
+            .await?;
+        stream.shutdown().await?;
+        let _ = tokio::time::timeout(BINARY_TIMEOUT, stream.read(&mut [0u8; 1])).await;
+        Ok(())
+    }
+
+    /// The KV store the plugin reaches through the host socket
+    pub fn kv(&self) -> &MemoryKvStore {
+        &self.kv
+    }
+
+    /// The data directory passed to the plugin in `init`
+    pub fn data_dir(&self) -> PathBuf {
+        self.dir.join("data")
+    }
+
+    /// Send a request and wait for the plugin's response
+    pub async fn request(&self, request: HttpRequest) -> PluginResult<HttpResponse> {
+        let mut stream = self.connect().await?;
+        let mut protocol = PluginProtocol::new();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        protocol
+            .write_message(&mut stream, &Message::new_http(request_id.clone(), request))
+            .await?;
+
+        let reply = tokio::time::timeout(BINARY_TIMEOUT, protocol.read_message(&mut stream))
+            .await
+            .map_err(|_| PluginError::Timeout)??;
+        match &reply.payload {
+            MessagePayload::Http {
+                request_id: reply_id,
+                ..
+            } if *reply_id == request_id => {}
+            _ => return Err(PluginError::Protocol("Unexpected reply".to_string())),
+        }
+        reply
+            .http_response()
+            .map(Into::into)
+            .ok_or_else(|| PluginError::Protocol("Reply is not an HTTP response".to_string()))
+    }
+
+    pub async fn get(&self, path: &str) -> PluginResult<HttpResponse> {
+        self.request(request("GET", path, None)).await
+    }
+
+    /// Send `shutdown` and wait for the process to exit
+    ///
+    /// Fails if the plugin does not exit in time or exits unsuccessfully.
+    pub async fn shutdown(mut self) -> PluginResult<()> {
+        self.send(&Message::new_lifecycle("shutdown", None)).await?;
+        let status = tokio::time::timeout(BINARY_TIMEOUT, self.child.wait())
+            .await
+            .map_err(|_| PluginError::Timeout)??;
+        if !status.success() {
+            return Err(PluginError::Internal(format!(
+                "Plugin exited with {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BinaryHarness {
+    fn drop(&mut self) {
+        self.host_task.abort();
+        let _ = self.child.start_kill();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Answer KV messages on a mock core's host socket until the task is aborted
+///
+/// SQL messages are answered with an error, the mock core has no database.
+///
+/// # Arguments
+/// * `listener` - Listener bound to the path passed as `host_socket` in init
+/// * `kv` - Store the operations are applied to
+pub async fn serve_host(listener: UnixListener, kv: MemoryKvStore) {
+    while let Ok((mut stream, _)) = listener.accept().await {
+        let kv = kv.clone();
+        tokio::spawn(async move {
+            let mut protocol = PluginProtocol::new();
+            while let Ok(message) = protocol.read_message(&mut stream).await {
+                let reply = match message.payload {
+                    MessagePayload::Kv {
+                        request_id,
+                        payload: KvMessagePayload::Request(op),
+                    } => {
+                        let response = crate::kv::execute(&kv, op)
+                            .await
+                            .unwrap_or_else(|e| KvMessageResponse::failed(&e));
+                        Message::new_kv_reply(request_id, response)
+                    }
+                    MessagePayload::Sql {
+                        request_id,
+                        payload: SqlMessagePayload::Request(_),
+                    } => Message::new_sql_reply(
+                        request_id,
+                        SqlMessageResponse::failed(&PluginError::Internal(
+                            "The mock core has no SQL database".to_string(),
+                        )),
+                    ),
+                    _ => continue,
+                };
+                if protocol.write_message(&mut stream, &reply).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Router;
+    use crate::types::{KvOp, PluginKvStore};
+
+    #[derive(Clone, Copy)]
+    enum Route {
+        Count,
+        Fail,
+    }
+
+    #[derive(Default)]
+    struct CounterPlugin {
+        ctx: Option<PluginContext>,
+    }
+
+    #[async_trait::async_trait]
+    impl ToruPlugin for CounterPlugin {
+        fn metadata() -> crate::PluginMetadata {
+            crate::PluginMetadata {
+                id: "counter".to_string(),
+                name: "Counter".to_string(),
+                version: "0.1.0".to_string(),
+                author: None,
+                icon: "🔢".to_string(),
+                route: "/counter".to_string(),
+            }
+        }
+
+        async fn init(&mut self, ctx: PluginContext) -> PluginResult<()> {
+            self.ctx = Some(ctx);
+            Ok(())
+        }
+
+        async fn handle_http(&self, req: HttpRequest) -> PluginResult<HttpResponse> {
+            let router = Router::new()
+                .post("/count", Route::Count)
+                .get("/fail", Route::Fail);
+            let (route, _) = match router.route(req) {
+                Ok(matched) => matched,
+                Err(response) => return Ok(response),
+            };
+            let kv = &self.ctx.as_ref().ok_or(PluginError::NotInitialized)?.kv;
+
+            match route {
+                Route::Count => {
+                    let count = kv
+                        .get("count")
+                        .await?
+                        .and_then(|v| v.parse::<u32>().ok())
+                        .unwrap_or(0)
+                        + 1;
+                    kv.set("count", &count.to_string()).await?;
+                    HttpResponse::json(200, &count)
+                }
+                Route::Fail => Err(PluginError::InvalidRequest("nope".to_string())),
+            }
+        }
+
+        async fn handle_kv(&mut self, _op: KvOp) -> PluginResult<Option<String>> {
+            Ok(None)
+        }
+
+        fn bundle() -> Option<&'static str> {
+            Some("console.log('counter')")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_harness() {
+        let kv = MemoryKvStore::new();
+        kv.set("count", "41").await.unwrap();
+        let harness = PluginHarness::with_kv(CounterPlugin::default(), kv)
+            .await
+            .unwrap();
+
+        let response = harness.request(request("POST", "/count", None)).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body.as_deref(), Some("42"));
+        assert_eq!(
+            harness.kv().get("count").await.unwrap().as_deref(),
+            Some("42")
+        );
+
+        assert_eq!(harness.get("/fail").await.status, 400);
+        assert_eq!(harness.get("/count").await.status, 405);
+
+        let bundle = harness.get("/bundle.js").await;
+        assert_eq!(bundle.body.as_deref(), Some("console.log('counter')"));
+
+        harness.shutdown().await.unwrap();
+    }
+}
@@ -15,6 +15,13 @@ pub struct PluginContext {
     pub instance_id: String,
     pub config: PluginConfig,
     pub kv: Box<dyn PluginKvStore>,
+    /// Private directory for files the plugin keeps (reports, caches)
+    ///
+    /// Counted against the plugin's data quota; None when the core did not
+    /// provide one.
+    pub data_dir: Option<std::path::PathBuf>,
+    /// The plugin's own SQL database, None when the core did not provide one
+    pub db: Option<Box<dyn PluginDatabase>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,6 +34,104 @@ pub trait PluginKvStore: Send + Sync {
     async fn get(&self, key: &str) -> crate::PluginResult<Option<String>>;
     async fn set(&self, key: &str, value: &str) -> crate::PluginResult<()>;
     async fn delete(&self, key: &str) -> crate::PluginResult<()>;
+
+    /// Set a value that expires after `ttl`
+    async fn set_with_ttl(
+        &self,
+        _key: &str,
+        _value: &str,
+        _ttl: std::time::Duration,
+    ) -> crate::PluginResult<()> {
+        Err(unsupported("set_with_ttl"))
+    }
+
+    /// Entries whose key starts with `prefix`, in key order
+    ///
+    /// # Arguments
+    /// * `prefix` - Key prefix, empty for all keys
+    /// * `cursor` - `next_cursor` of the previous page, `None` for the first
+    /// * `limit` - Maximum number of entries in the page
+    async fn list(
+        &self,
+        _prefix: &str,
+        _cursor: Option<&str>,
+        _limit: usize,
+    ) -> crate::PluginResult<KvPage> {
+        Err(unsupported("list"))
+    }
+
+    /// Replace a value only if it currently equals `expected`
+    ///
+    /// `None` as `expected` means the key must not exist, `None` as `new`
+    /// deletes the key. Returns whether the swap happened.
+    async fn compare_and_swap(
+        &self,
+        _key: &str,
+        _expected: Option<&str>,
+        _new: Option<&str>,
+    ) -> crate::PluginResult<bool> {
+        Err(unsupported("compare_and_swap"))
+    }
+
+    /// Atomically add `delta` to an integer value, treating a missing key as 0
+    async fn increment(&self, _key: &str, _delta: i64) -> crate::PluginResult<i64> {
+        Err(unsupported("increment"))
+    }
+
+    /// Get several values, in the order of `keys`
+    async fn get_many(&self, keys: &[String]) -> crate::PluginResult<Vec<Option<String>>> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+        Ok(values)
+    }
+
+    /// Set several values
+    ///
+    /// Stores that support it apply all entries or none; the default
+    /// implementation sets them one by one.
+    async fn set_many(&self, entries: &[KvSetEntry]) -> crate::PluginResult<()> {
+        for entry in entries {
+            match entry.ttl_secs {
+                Some(ttl) => {
+                    self.set_with_ttl(
+                        &entry.key,
+                        &entry.value,
+                        std::time::Duration::from_secs(ttl),
+                    )
+                    .await?
+                }
+                None => self.set(&entry.key, &entry.value).await?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A plugin's private SQL (SQLite) database
+///
+/// Statements use `?` placeholders bound to `params`. The database only
+/// holds the plugin's own tables; attaching other databases is refused.
+#[async_trait::async_trait]
+pub trait PluginDatabase: Send + Sync {
+    /// Run a statement returning rows
+    async fn query(&self, sql: &str, params: &[SqlValue]) -> crate::PluginResult<SqlRows>;
+
+    /// Run a statement that changes data
+    async fn execute(&self, sql: &str, params: &[SqlValue]) -> crate::PluginResult<SqlExecuted>;
+
+    /// Run statements in one transaction, returning the rows each changed
+    async fn batch(&self, statements: &[SqlStatement]) -> crate::PluginResult<Vec<u64>>;
+
+    /// Apply the migrations that were not applied yet, in order
+    ///
+    /// Returns the versions applied by this call.
+    async fn migrate(&self, migrations: &[SqlMigration]) -> crate::PluginResult<Vec<String>>;
+}
+
+fn unsupported(operation: &str) -> crate::PluginError {
+    crate::PluginError::Internal(format!("{} is not supported by this KV store", operation))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,12 +149,240 @@ pub struct HttpResponse {
     pub body: Option<String>,
 }
 
+impl HttpResponse {
+    /// Response with a JSON body
+    ///
+    /// # Arguments
+    /// * `status` - HTTP status code
+    /// * `value` - Value serialized as the body
+    pub fn json<T: Serialize + ?Sized>(status: u16, value: &T) -> crate::PluginResult<Self> {
+        Ok(Self::with_body(
+            status,
+            "application/json",
+            serde_json::to_string(value)?,
+        ))
+    }
+
+    /// Response with a plain text body
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self::with_body(status, "text/plain; charset=utf-8", body.into())
+    }
+
+    /// JSON error response in the same shape the runtime uses: `{"error": "..."}`
+    pub fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        let body = serde_json::json!({ "error": message.to_string() });
+        Self::with_body(status, "application/json", body.to_string())
+    }
+
+    /// Response without a body, e.g. 204 No Content
+    pub fn empty(status: u16) -> Self {
+        Self {
+            status,
+            headers: Default::default(),
+            body: None,
+        }
+    }
+
+    /// Add or replace a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    fn with_body(status: u16, content_type: &str, body: String) -> Self {
+        Self {
+            status,
+            headers: [("Content-Type".to_string(), content_type.to_string())].into(),
+            body: Some(body),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action")]
 pub enum KvOp {
-    Get { key: String },
-    Set { key: String, value: String },
-    Delete { key: String },
+    Get {
+        key: String,
+    },
+    Set {
+        key: String,
+        value: String,
+        /// Expire the value after this many seconds
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl_secs: Option<u64>,
+    },
+    Delete {
+        key: String,
+    },
+    /// Page through keys starting with `prefix`
+    List {
+        #[serde(default)]
+        prefix: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cursor: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    CompareAndSwap {
+        key: String,
+        #[serde(default)]
+        expected: Option<String>,
+        #[serde(default)]
+        new: Option<String>,
+    },
+    Increment {
+        key: String,
+        #[serde(default = "default_delta")]
+        delta: i64,
+    },
+    GetMany {
+        keys: Vec<String>,
+    },
+    /// Set all entries in one transaction
+    SetMany {
+        entries: Vec<KvSetEntry>,
+    },
+}
+
+fn default_delta() -> i64 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvSetEntry {
+    pub key: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvEntry {
+    pub key: String,
+    pub value: String,
+}
+
+/// One page of a prefix listing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KvPage {
+    pub entries: Vec<KvEntry>,
+    /// Pass as `cursor` to get the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Result of the KV operations that return more than a single value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum KvResult {
+    Page(KvPage),
+    Swapped { swapped: bool },
+    Counter { count: i64 },
+    Values { values: Vec<Option<String>> },
+}
+
+/// A value bound to or read from a SQL statement
+///
+/// Serialized as plain JSON (`null`, a number or a string); store binary
+/// data encoded as text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f64> for SqlValue {
+    fn from(value: f64) -> Self {
+        Self::Real(value)
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SqlStatement {
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<SqlValue>,
+}
+
+/// A schema migration, applied once and remembered by `version`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SqlMigration {
+    /// Versions are applied in the order given, e.g. "001_init"
+    pub version: String,
+    /// One or more statements separated by `;`
+    pub sql: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action")]
+pub enum SqlOp {
+    Query {
+        sql: String,
+        #[serde(default)]
+        params: Vec<SqlValue>,
+    },
+    Execute {
+        sql: String,
+        #[serde(default)]
+        params: Vec<SqlValue>,
+    },
+    /// Run all statements in one transaction
+    Batch {
+        statements: Vec<SqlStatement>,
+    },
+    Migrate {
+        migrations: Vec<SqlMigration>,
+    },
+}
+
+/// Rows returned by a query
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SqlRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+/// Outcome of a statement changing data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SqlExecuted {
+    pub rows_affected: u64,
+    /// Row ID of the last inserted row
+    pub last_insert_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SqlResult {
+    Rows(SqlRows),
+    Executed(SqlExecuted),
+    Batch { rows_affected: Vec<u64> },
+    Migrated { applied: Vec<String> },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +390,24 @@ pub struct LifecycleInitPayload {
     pub instance_id: String,
     pub plugin_socket: String,
     pub log_path: String,
+    /// Socket the core listens on for plugin-initiated messages (metrics, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_socket: Option<String>,
+    /// Private directory the plugin may write files to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+}
+
+/// A single numeric sample pushed by a plugin to the core time-series store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub name: String,
+    pub value: f64,
+    #[serde(default)]
+    pub labels: std::collections::BTreeMap<String, String>,
+    /// Sample time; the core uses its own clock when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +430,14 @@ pub enum MessagePayload {
         #[serde(flatten)]
         payload: KvMessagePayload,
     },
+    #[serde(rename = "metrics")]
+    Metrics { samples: Vec<MetricSample> },
+    #[serde(rename = "sql")]
+    Sql {
+        request_id: String,
+        #[serde(flatten)]
+        payload: SqlMessagePayload,
+    },
 }
 
 /// KV message payload - can be either a request (operation) or response (value)
@@ -86,7 +445,15 @@ pub enum MessagePayload {
 #[serde(untagged)]
 pub enum KvMessagePayload {
     Request(KvOp),
-    Response { value: Option<String> },
+    Response(KvMessageResponse),
+}
+
+/// SQL message payload - a request (operation) or its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SqlMessagePayload {
+    Request(SqlOp),
+    Response(SqlMessageResponse),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,8 +504,55 @@ impl Message {
         }
     }
 
+    /// Create a SQL request message (sent by plugins to the core host socket)
+    pub fn new_sql(request_id: String, payload: SqlOp) -> Self {
+        Self {
+            message_type: "sql".to_string(),
+            timestamp: Utc::now(),
+            request_id: Some(request_id.clone()),
+            payload: MessagePayload::Sql {
+                request_id,
+                payload: SqlMessagePayload::Request(payload),
+            },
+        }
+    }
+
+    /// Create a SQL response message
+    pub fn new_sql_reply(request_id: String, response: SqlMessageResponse) -> Self {
+        Self {
+            message_type: "sql".to_string(),
+            timestamp: Utc::now(),
+            request_id: Some(request_id.clone()),
+            payload: MessagePayload::Sql {
+                request_id,
+                payload: SqlMessagePayload::Response(response),
+            },
+        }
+    }
+
+    /// Create a metrics push message (sent by plugins to the core host socket)
+    pub fn new_metrics(samples: Vec<MetricSample>) -> Self {
+        Self {
+            message_type: "metrics".to_string(),
+            timestamp: Utc::now(),
+            request_id: None,
+            payload: MessagePayload::Metrics { samples },
+        }
+    }
+
     /// Create a KV response message (used by plugins to respond to KV operations)
     pub fn new_kv_response(request_id: String, value: Option<String>) -> Self {
+        Self::new_kv_reply(
+            request_id,
+            KvMessageResponse {
+                value,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Create a KV response message carrying a structured result or an error
+    pub fn new_kv_reply(request_id: String, response: KvMessageResponse) -> Self {
         let request_id_clone = request_id.clone();
         Self {
             message_type: "kv".to_string(),
@@ -146,10 +560,40 @@ impl Message {
             request_id: Some(request_id),
             payload: MessagePayload::Kv {
                 request_id: request_id_clone,
-                payload: KvMessagePayload::Response { value },
+                payload: KvMessagePayload::Response(response),
             },
         }
     }
+
+    /// Create an HTTP response message (used by plugins to answer HTTP requests)
+    ///
+    /// Responses travel as an `http` message whose method is `RESPONSE` and
+    /// whose body is the JSON-encoded [`HttpMessageResponse`].
+    pub fn new_http_response(request_id: String, response: HttpMessageResponse) -> Self {
+        let body = serde_json::to_string(&response).unwrap_or_default();
+        Self::new_http(
+            request_id,
+            HttpRequest {
+                method: "RESPONSE".to_string(),
+                path: String::new(),
+                headers: std::collections::HashMap::new(),
+                body: Some(body),
+            },
+        )
+    }
+
+    /// Decode a message created with [`Message::new_http_response`]
+    ///
+    /// Returns None if the message is not an HTTP response.
+    pub fn http_response(&self) -> Option<HttpMessageResponse> {
+        match &self.payload {
+            MessagePayload::Http { payload, .. } if payload.method == "RESPONSE" => payload
+                .body
+                .as_deref()
+                .and_then(|body| serde_json::from_str(body).ok()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -159,7 +603,104 @@ pub struct HttpMessageResponse {
     pub body: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<HttpResponse> for HttpMessageResponse {
+    fn from(response: HttpResponse) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            body: response.body,
+        }
+    }
+}
+
+impl From<HttpMessageResponse> for HttpResponse {
+    fn from(response: HttpMessageResponse) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            body: response.body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KvMessageResponse {
     pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<KvResult>,
+    /// Set when the operation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Kind of failure ("invalid_request", "quota_exceeded"), so the plugin
+    /// gets the same `PluginError` variant the core saw
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+impl KvMessageResponse {
+    /// Response reporting a failed operation
+    pub fn failed(error: &crate::PluginError) -> Self {
+        let (error, error_code) = encode_error(error);
+        Self {
+            error: Some(error),
+            error_code,
+            ..Default::default()
+        }
+    }
+
+    /// The error of a failed operation
+    pub fn error(&self) -> Option<crate::PluginError> {
+        decode_error(self.error.as_ref()?, self.error_code.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SqlMessageResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<SqlResult>,
+    /// Set when the operation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Kind of failure, as in [`KvMessageResponse::error_code`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+impl SqlMessageResponse {
+    /// Response reporting a failed operation
+    pub fn failed(error: &crate::PluginError) -> Self {
+        let (error, error_code) = encode_error(error);
+        Self {
+            result: None,
+            error: Some(error),
+            error_code,
+        }
+    }
+
+    /// The error of a failed operation
+    pub fn error(&self) -> Option<crate::PluginError> {
+        decode_error(self.error.as_ref()?, self.error_code.as_deref())
+    }
+}
+
+/// Message and code of an error sent to a plugin, see [`decode_error`]
+fn encode_error(error: &crate::PluginError) -> (String, Option<String>) {
+    match error {
+        crate::PluginError::InvalidRequest(message) => {
+            (message.clone(), Some("invalid_request".to_string()))
+        }
+        crate::PluginError::QuotaExceeded(message) => {
+            (message.clone(), Some("quota_exceeded".to_string()))
+        }
+        other => (other.to_string(), None),
+    }
+}
+
+fn decode_error(message: &str, code: Option<&str>) -> Option<crate::PluginError> {
+    let message = message.to_string();
+    Some(match code {
+        Some("invalid_request") => crate::PluginError::InvalidRequest(message),
+        Some("quota_exceeded") => crate::PluginError::QuotaExceeded(message),
+        _ => crate::PluginError::Internal(message),
+    })
 }
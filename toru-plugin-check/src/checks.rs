@@ -0,0 +1,456 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+use steering_center::services::metadata_cache::{
+    run_metadata_command, validate_metadata, METADATA_TIMEOUT,
+};
+use toru_plugin_api::protocol::MAX_MESSAGE_SIZE;
+use toru_plugin_api::testing::serve_host;
+use toru_plugin_api::{
+    HttpRequest, KvOp, LifecycleInitPayload, MemoryKvStore, Message, MessagePayload, PluginProtocol,
+};
+
+/// How long the core waits for a plugin to exit after `shutdown`
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// Not run because an earlier check the others depend on failed
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+/// Run every check against a plugin binary
+///
+/// # Arguments
+/// * `binary` - Path to the plugin binary
+/// * `timeout` - How long the plugin may take to answer a single message
+pub async fn run_all(binary: &Path, timeout: Duration) -> Vec<CheckResult> {
+    let mut results = vec![check("metadata", check_metadata(binary).await)];
+
+    let mut plugin = match PluginUnderTest::start(binary).await {
+        Ok(plugin) => plugin,
+        Err(e) => {
+            results.push(check("startup", Err(e)));
+            for name in LIVE_CHECKS {
+                results.push(CheckResult {
+                    name,
+                    outcome: Outcome::Skip,
+                    detail: "Plugin did not start".to_string(),
+                });
+            }
+            return results;
+        }
+    };
+    plugin.timeout = timeout;
+    results.push(check("startup", Ok("Socket created".to_string())));
+
+    results.push(check("init", plugin.check_init().await));
+    results.push(check("http-request-id", plugin.check_request_id().await));
+    results.push(check("http-pipelined", plugin.check_pipelined().await));
+    results.push(check(
+        "http-unknown-route",
+        plugin.check_unknown_route().await,
+    ));
+    results.push(check("kv-message", plugin.check_kv_message().await));
+    results.push(check("malformed-message", plugin.check_malformed().await));
+    results.push(check("oversized-message", plugin.check_oversized().await));
+    results.push(check("stalled-connection", plugin.check_stalled().await));
+    results.push(check("shutdown", plugin.check_shutdown().await));
+
+    results
+}
+
+fn check(name: &'static str, result: Result<String>) -> CheckResult {
+    let (outcome, detail) = match result {
+        Ok(detail) => (Outcome::Pass, detail),
+        Err(e) => (Outcome::Fail, format!("{:#}", e)),
+    };
+    CheckResult {
+        name,
+        outcome,
+        detail,
+    }
+}
+
+/// Checks that need a running plugin, skipped when it does not start
+const LIVE_CHECKS: [&str; 9] = [
+    "init",
+    "http-request-id",
+    "http-pipelined",
+    "http-unknown-route",
+    "kv-message",
+    "malformed-message",
+    "oversized-message",
+    "stalled-connection",
+    "shutdown",
+];
+
+/// `--metadata` must succeed quickly and pass the core's validation
+async fn check_metadata(binary: &Path) -> Result<String> {
+    let metadata = run_metadata_command(binary, METADATA_TIMEOUT).await?;
+    validate_metadata(&metadata)?;
+    Ok(format!(
+        "{} {} (route {})",
+        metadata.id, metadata.version, metadata.route
+    ))
+}
+
+fn get(path: &str) -> HttpRequest {
+    HttpRequest {
+        method: "GET".to_string(),
+        path: path.to_string(),
+        headers: Default::default(),
+        body: None,
+    }
+}
+
+fn new_request_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// A plugin process with the sockets the core would give it
+struct PluginUnderTest {
+    child: Child,
+    dir: PathBuf,
+    plugin_socket: PathBuf,
+    host_socket: PathBuf,
+    host_task: JoinHandle<()>,
+    timeout: Duration,
+}
+
+impl PluginUnderTest {
+    async fn start(binary: &Path) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "toru-plugin-check-{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..12]
+        ));
+        std::fs::create_dir_all(&dir)?;
+        let plugin_socket = dir.join("plugin.sock");
+        let host_socket = dir.join("host.sock");
+        let host_task = tokio::spawn(serve_host(
+            UnixListener::bind(&host_socket)?,
+            MemoryKvStore::new(),
+        ));
+
+        let child = Command::new(binary)
+            .env("TORU_PLUGIN_SOCKET", &plugin_socket)
+            .env("TORU_HOST_SOCKET", &host_socket)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to spawn plugin")?;
+
+        let mut plugin = Self {
+            child,
+            dir,
+            plugin_socket,
+            host_socket,
+            host_task,
+            timeout: METADATA_TIMEOUT,
+        };
+
+        let deadline = tokio::time::Instant::now() + plugin.timeout;
+        while !plugin.plugin_socket.exists() {
+            if let Some(status) = plugin.child.try_wait()? {
+                bail!("Plugin exited before creating its socket ({})", status);
+            }
+            if tokio::time::Instant::now() > deadline {
+                bail!(
+                    "Socket not created within {:?} (TORU_PLUGIN_SOCKET ignored?)",
+                    plugin.timeout
+                );
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Ok(plugin)
+    }
+
+    async fn connect(&self) -> Result<UnixStream> {
+        UnixStream::connect(&self.plugin_socket)
+            .await
+            .context("Failed to connect to plugin socket")
+    }
+
+    async fn read(&self, stream: &mut UnixStream) -> Result<Message> {
+        tokio::time::timeout(self.timeout, PluginProtocol::new().read_message(stream))
+            .await
+            .map_err(|_| anyhow!("No reply within {:?}", self.timeout))?
+            .map_err(|e| anyhow!("Invalid reply: {}", e))
+    }
+
+    /// Send one HTTP request on a fresh connection and return its status
+    async fn http(&self, request: HttpRequest) -> Result<u16> {
+        let mut stream = self.connect().await?;
+        let request_id = new_request_id();
+        PluginProtocol::new()
+            .write_message(&mut stream, &Message::new_http(request_id.clone(), request))
+            .await?;
+        let reply = self.read(&mut stream).await?;
+        http_status(&reply, &request_id)
+    }
+
+    /// The process is running and answers requests
+    async fn ensure_alive(&mut self) -> Result<()> {
+        if let Some(status) = self.child.try_wait()? {
+            bail!("Plugin exited ({})", status);
+        }
+        self.http(get("/"))
+            .await
+            .context("Plugin stopped answering requests")?;
+        Ok(())
+    }
+
+    async fn check_init(&mut self) -> Result<String> {
+        std::fs::create_dir_all(self.dir.join("data"))?;
+        let mut stream = self.connect().await?;
+        let init = Message::new_lifecycle(
+            "init",
+            Some(LifecycleInitPayload {
+                instance_id: "conformance-check".to_string(),
+                plugin_socket: self.plugin_socket.to_string_lossy().into_owned(),
+                log_path: self.dir.join("plugin.log").to_string_lossy().into_owned(),
+                host_socket: Some(self.host_socket.to_string_lossy().into_owned()),
+                data_dir: Some(self.dir.join("data").to_string_lossy().into_owned()),
+            }),
+        );
+        PluginProtocol::new()
+            .write_message(&mut stream, &init)
+            .await?;
+
+        // The plugin closes the connection once it handled init
+        stream.shutdown().await?;
+        let _ = tokio::time::timeout(self.timeout, stream.read(&mut [0u8; 1])).await;
+        self.ensure_alive().await?;
+        Ok("Plugin accepted init and keeps answering".to_string())
+    }
+
+    async fn check_request_id(&mut self) -> Result<String> {
+        let status = self.http(get("/")).await?;
+        Ok(format!(
+            "GET / answered with {} and matching request id",
+            status
+        ))
+    }
+
+    async fn check_pipelined(&mut self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        let protocol = PluginProtocol::new();
+        let mut expected = HashSet::new();
+        for _ in 0..3 {
+            let request_id = new_request_id();
+            protocol
+                .write_message(
+                    &mut stream,
+                    &Message::new_http(request_id.clone(), get("/")),
+                )
+                .await?;
+            expected.insert(request_id);
+        }
+
+        for _ in 0..3 {
+            let reply = self.read(&mut stream).await?;
+            let MessagePayload::Http { request_id, .. } = &reply.payload else {
+                bail!("Expected an HTTP reply");
+            };
+            if !expected.remove(request_id) {
+                bail!(
+                    "Reply carries unknown or repeated request id {:?}",
+                    request_id
+                );
+            }
+            http_status(&reply, request_id)?;
+        }
+        Ok("3 requests on one connection answered with their own ids".to_string())
+    }
+
+    async fn check_unknown_route(&mut self) -> Result<String> {
+        let path = format!("/toru-plugin-check/{}", new_request_id());
+        let status = self.http(get(&path)).await?;
+        if !(400..500).contains(&status) {
+            bail!(
+                "Unknown route answered with {}, expected a 4xx status",
+                status
+            );
+        }
+        Ok(format!("Unknown route answered with {}", status))
+    }
+
+    async fn check_kv_message(&mut self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        let request_id = new_request_id();
+        let op = KvOp::Get {
+            key: "toru-plugin-check".to_string(),
+        };
+        PluginProtocol::new()
+            .write_message(&mut stream, &Message::new_kv(request_id.clone(), op))
+            .await?;
+        let reply = self.read(&mut stream).await?;
+        match reply.payload {
+            MessagePayload::Kv {
+                request_id: reply_id,
+                ..
+            } if reply_id == request_id => Ok("KV request answered".to_string()),
+            MessagePayload::Kv { .. } => bail!("KV reply carries a different request id"),
+            _ => bail!("Expected a KV reply"),
+        }
+    }
+
+    async fn check_malformed(&mut self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        let garbage = b"{ not json";
+        stream
+            .write_all(&(garbage.len() as u32).to_be_bytes())
+            .await?;
+        stream.write_all(garbage).await?;
+        stream.flush().await?;
+        drop(stream);
+
+        self.ensure_alive().await?;
+        Ok("Invalid JSON did not take the plugin down".to_string())
+    }
+
+    async fn check_oversized(&mut self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        stream
+            .write_all(&(MAX_MESSAGE_SIZE as u32 + 1).to_be_bytes())
+            .await?;
+        stream.flush().await?;
+
+        // The plugin must give up on the connection instead of waiting for 16 MiB
+        if self.read(&mut stream).await.is_ok() {
+            bail!("Plugin answered an oversized message");
+        }
+        drop(stream);
+
+        self.ensure_alive().await?;
+        Ok(format!(
+            "Length above {} bytes rejected, plugin still answering",
+            MAX_MESSAGE_SIZE
+        ))
+    }
+
+    async fn check_stalled(&mut self) -> Result<String> {
+        // Announce a message and never send it
+        let mut stalled = self.connect().await?;
+        stalled.write_all(&100u32.to_be_bytes()).await?;
+        stalled.flush().await?;
+
+        self.http(get("/"))
+            .await
+            .context("A stalled connection blocks other requests")?;
+        drop(stalled);
+        Ok("Stalled connection does not block other requests".to_string())
+    }
+
+    async fn check_shutdown(&mut self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        PluginProtocol::new()
+            .write_message(&mut stream, &Message::new_lifecycle("shutdown", None))
+            .await?;
+
+        let status = tokio::time::timeout(SHUTDOWN_TIMEOUT, self.child.wait())
+            .await
+            .map_err(|_| anyhow!("Plugin still running {:?} after shutdown", SHUTDOWN_TIMEOUT))??;
+        if !status.success() {
+            bail!("Plugin exited with {}", status);
+        }
+        if self.plugin_socket.exists() {
+            bail!("Plugin exited but left its socket behind");
+        }
+        Ok("Plugin exited cleanly and removed its socket".to_string())
+    }
+}
+
+impl Drop for PluginUnderTest {
+    fn drop(&mut self) {
+        self.host_task.abort();
+        let _ = self.child.start_kill();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Status of an HTTP reply, checking it answers `request_id`
+fn http_status(reply: &Message, request_id: &str) -> Result<u16> {
+    let MessagePayload::Http {
+        request_id: reply_id,
+        ..
+    } = &reply.payload
+    else {
+        bail!("Expected an HTTP reply");
+    };
+    if reply_id != request_id {
+        bail!(
+            "Reply request id {:?} does not match {:?}",
+            reply_id,
+            request_id
+        );
+    }
+
+    let response = reply
+        .http_response()
+        .ok_or_else(|| anyhow!("Reply is not an HTTP response (method must be RESPONSE)"))?;
+    if !(100..600).contains(&response.status) {
+        bail!("Invalid status {}", response.status);
+    }
+    Ok(response.status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sdk_plugin_passes_all_checks() {
+        let binary =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../plugins/hello-plugin-rust.binary");
+        let results = run_all(&binary, Duration::from_secs(5)).await;
+
+        assert_eq!(results.len(), 2 + LIVE_CHECKS.len());
+        for result in &results {
+            assert_eq!(
+                result.outcome,
+                Outcome::Pass,
+                "{} failed: {}",
+                result.name,
+                result.detail
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broken_plugin_is_reported() {
+        let dir = std::env::temp_dir().join(format!("toru-check-test-{}", new_request_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("broken.sh");
+        std::fs::write(&binary, "#!/bin/sh\necho 'not json'\n").unwrap();
+        std::fs::set_permissions(&binary, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let results = run_all(&binary, Duration::from_secs(1)).await;
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(results[0].outcome, Outcome::Fail);
+        assert_eq!(results[1].outcome, Outcome::Fail);
+        assert!(results[2..].iter().all(|r| r.outcome == Outcome::Skip));
+    }
+}
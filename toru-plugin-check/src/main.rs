@@ -0,0 +1,112 @@
+//! Conformance checker for Toru plugins
+//!
+//! Runs a plugin binary through the protocol the way the core does and
+//! prints a pass/fail report. Exits with 1 when any check fails, so it can
+//! gate plugin releases in CI.
+//!
+//! Usage: toru-plugin-check [--json] [--timeout <secs>] <plugin-binary>
+
+mod checks;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use checks::{CheckResult, Outcome};
+
+const USAGE: &str = "Usage: toru-plugin-check [--json] [--timeout <secs>] <plugin-binary>";
+
+/// Default time a plugin gets to answer a single message
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+struct Options {
+    binary: PathBuf,
+    json: bool,
+    timeout: Duration,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut binary = None;
+    let mut json = false;
+    let mut timeout = Duration::from_secs(DEFAULT_TIMEOUT_SECS);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--timeout" => {
+                let secs = args
+                    .next()
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .filter(|secs| *secs > 0)
+                    .ok_or("--timeout expects a positive number of seconds")?;
+                timeout = Duration::from_secs(secs);
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ if binary.is_none() => binary = Some(PathBuf::from(arg)),
+            _ => return Err("Only one plugin binary can be checked at a time".to_string()),
+        }
+    }
+
+    Ok(Options {
+        binary: binary.ok_or(USAGE)?,
+        json,
+        timeout,
+    })
+}
+
+fn print_report(options: &Options, results: &[CheckResult]) {
+    let count = |outcome| results.iter().filter(|r| r.outcome == outcome).count();
+
+    if options.json {
+        let report = serde_json::json!({
+            "binary": options.binary,
+            "passed": count(Outcome::Pass),
+            "failed": count(Outcome::Fail),
+            "skipped": count(Outcome::Skip),
+            "checks": results,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!("Checking {}\n", options.binary.display());
+    for result in results {
+        let label = match result.outcome {
+            Outcome::Pass => "PASS",
+            Outcome::Fail => "FAIL",
+            Outcome::Skip => "SKIP",
+        };
+        println!("  {}  {:<20} {}", label, result.name, result.detail);
+    }
+    println!(
+        "\n{} passed, {} failed, {} skipped",
+        count(Outcome::Pass),
+        count(Outcome::Fail),
+        count(Outcome::Skip)
+    );
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let results = checks::run_all(&options.binary, options.timeout).await;
+    print_report(&options, &results);
+
+    if results.iter().any(|r| r.outcome != Outcome::Pass) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,264 @@
+//! Procedural macros for `toru-plugin-api`
+//!
+//! Use them through the re-exports in `toru_plugin_api`, generated code
+//! refers to that crate by name.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, ItemImpl, LitBool, LitStr};
+
+/// Declare a plugin on its `ToruPlugin` implementation
+///
+/// Generates `metadata()`, embeds the frontend bundle and generates `main`,
+/// so the implementation only contains the handlers. The plugin type must
+/// implement `Default` unless `main = false` is given.
+///
+/// # Arguments
+/// * `id` - Plugin ID, ASCII letters, digits and `-` only
+/// * `name` - Display name, at most 100 bytes
+/// * `route` - Frontend route, must start with `/`
+/// * `icon` - Icon shown in the sidebar
+/// * `version` - Optional, defaults to the crate version
+/// * `author` - Optional, at most 100 bytes
+/// * `bundle` - Optional path of the frontend bundle, relative to the crate root
+/// * `main` - Optional, `false` skips generating `main`
+///
+/// ```ignore
+/// #[toru_plugin(id = "my-plugin", name = "My Plugin", route = "/my-plugin", icon = "🔌")]
+/// #[async_trait::async_trait]
+/// impl ToruPlugin for MyPlugin {
+///     // init, handle_http, handle_kv
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn toru_plugin(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut attrs = PluginAttrs::default();
+    let parser = syn::meta::parser(|meta| attrs.parse(meta));
+    parse_macro_input!(args with parser);
+
+    let item = parse_macro_input!(item as ItemImpl);
+    match expand(attrs, item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct PluginAttrs {
+    id: Option<LitStr>,
+    name: Option<LitStr>,
+    route: Option<LitStr>,
+    icon: Option<LitStr>,
+    version: Option<LitStr>,
+    author: Option<LitStr>,
+    bundle: Option<LitStr>,
+    main: Option<LitBool>,
+}
+
+impl PluginAttrs {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        let slot = if meta.path.is_ident("id") {
+            &mut self.id
+        } else if meta.path.is_ident("name") {
+            &mut self.name
+        } else if meta.path.is_ident("route") {
+            &mut self.route
+        } else if meta.path.is_ident("icon") {
+            &mut self.icon
+        } else if meta.path.is_ident("version") {
+            &mut self.version
+        } else if meta.path.is_ident("author") {
+            &mut self.author
+        } else if meta.path.is_ident("bundle") {
+            &mut self.bundle
+        } else if meta.path.is_ident("main") {
+            if self.main.is_some() {
+                return Err(meta.error("duplicate attribute `main`"));
+            }
+            self.main = Some(meta.value()?.parse()?);
+            return Ok(());
+        } else {
+            return Err(meta.error("unknown attribute, expected one of: id, name, route, icon, version, author, bundle, main"));
+        };
+
+        if slot.is_some() {
+            return Err(meta.error("duplicate attribute"));
+        }
+        *slot = Some(meta.value()?.parse()?);
+        Ok(())
+    }
+}
+
+fn required(value: Option<LitStr>, name: &str) -> syn::Result<LitStr> {
+    value.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("missing required attribute `{}`", name),
+        )
+    })
+}
+
+/// Check an ID, mirroring the core's metadata validation
+fn validate_id(id: &str) -> Result<(), &'static str> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err("plugin ID may only contain ASCII letters, digits and `-`");
+    }
+    Ok(())
+}
+
+/// Check a route, mirroring the core's metadata validation
+fn validate_route(route: &str) -> Result<(), &'static str> {
+    if !route.starts_with('/') || route.contains("..") {
+        return Err("plugin route must start with `/` and must not contain `..`");
+    }
+    Ok(())
+}
+
+/// Check a name or author, mirroring the core's metadata validation
+fn validate_length(value: &str) -> Result<(), &'static str> {
+    if value.len() > 100 {
+        return Err("value must be at most 100 bytes");
+    }
+    Ok(())
+}
+
+fn check(lit: &LitStr, validate: fn(&str) -> Result<(), &'static str>) -> syn::Result<()> {
+    validate(&lit.value()).map_err(|e| syn::Error::new(lit.span(), e))
+}
+
+fn expand(attrs: PluginAttrs, mut item: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    let is_plugin_impl = item
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+        .is_some_and(|segment| segment.ident == "ToruPlugin");
+    if !is_plugin_impl {
+        return Err(syn::Error::new_spanned(
+            &item.self_ty,
+            "#[toru_plugin] must be placed on an `impl ToruPlugin for ...` block",
+        ));
+    }
+
+    let id = required(attrs.id, "id")?;
+    let name = required(attrs.name, "name")?;
+    let route = required(attrs.route, "route")?;
+    let icon = required(attrs.icon, "icon")?;
+    check(&id, validate_id)?;
+    check(&route, validate_route)?;
+    check(&name, validate_length)?;
+
+    let version = match attrs.version {
+        Some(version) => quote!(#version),
+        None => quote!(env!("CARGO_PKG_VERSION")),
+    };
+    let author = match attrs.author {
+        Some(author) => {
+            check(&author, validate_length)?;
+            quote!(Some(#author.to_string()))
+        }
+        None => quote!(None),
+    };
+
+    item.items.push(parse_quote! {
+        fn metadata() -> ::toru_plugin_api::PluginMetadata {
+            ::toru_plugin_api::PluginMetadata {
+                id: #id.to_string(),
+                name: #name.to_string(),
+                version: #version.to_string(),
+                author: #author,
+                icon: #icon.to_string(),
+                route: #route.to_string(),
+            }
+        }
+    });
+
+    if let Some(bundle) = attrs.bundle {
+        item.items.push(parse_quote! {
+            fn bundle() -> Option<&'static str> {
+                Some(include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", #bundle)))
+            }
+        });
+    }
+
+    let main = if attrs.main.as_ref().is_none_or(|main| main.value) {
+        let self_ty = &item.self_ty;
+        quote! {
+            fn main() -> ::toru_plugin_api::PluginResult<()> {
+                ::toru_plugin_api::__private::tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .build()?
+                    .block_on(::toru_plugin_api::run(
+                        <#self_ty as ::core::default::Default>::default(),
+                    ))
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        #item
+        #main
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_matches_core_rules() {
+        assert!(validate_id("hello-plugin-rust").is_ok());
+        assert!(validate_id("").is_err());
+        assert!(validate_id("../etc").is_err());
+        assert!(validate_id("my_plugin").is_err());
+
+        assert!(validate_route("/hello-rust").is_ok());
+        assert!(validate_route("hello").is_err());
+        assert!(validate_route("/a/../b").is_err());
+
+        assert!(validate_length(&"x".repeat(100)).is_ok());
+        assert!(validate_length(&"x".repeat(101)).is_err());
+    }
+
+    #[test]
+    fn test_expand_generates_metadata_and_main() {
+        let attrs = PluginAttrs {
+            id: Some(parse_quote!("demo")),
+            name: Some(parse_quote!("Demo")),
+            route: Some(parse_quote!("/demo")),
+            icon: Some(parse_quote!("🔌")),
+            bundle: Some(parse_quote!("frontend/bundle.js")),
+            ..Default::default()
+        };
+        let item: ItemImpl = parse_quote! {
+            impl ToruPlugin for Demo {}
+        };
+
+        let output = expand(attrs, item).unwrap().to_string();
+        assert!(output.contains("fn metadata"));
+        assert!(output.contains("fn bundle"));
+        assert!(output.contains("fn main"));
+    }
+
+    #[test]
+    fn test_expand_rejects_invalid_metadata() {
+        let attrs = PluginAttrs {
+            id: Some(parse_quote!("bad id")),
+            name: Some(parse_quote!("Demo")),
+            route: Some(parse_quote!("/demo")),
+            icon: Some(parse_quote!("🔌")),
+            ..Default::default()
+        };
+        let item: ItemImpl = parse_quote! {
+            impl ToruPlugin for Demo {}
+        };
+        assert!(expand(attrs, item).is_err());
+
+        let item: ItemImpl = parse_quote! {
+            impl Demo {}
+        };
+        assert!(expand(PluginAttrs::default(), item).is_err());
+    }
+}